@@ -0,0 +1,567 @@
+//! Configuration sources for the Allure runtime.
+//!
+//! Settings are resolved from, in order of precedence:
+//!
+//! 1. code: [`configure()`](crate::configure) builder calls
+//! 2. environment: `ALLURE_RESULTS_DIR`, `ALLURE_CLEAN_RESULTS`,
+//!    `ALLURE_CLEAN_ALL`, `ALLURE_FILTER`, `ALLURE_REDACT_ENV`, `ALLURE_STRICT`,
+//!    `ALLURE_LABEL_<name>=value` and `ALLURE_LINK_<TYPE>_PATTERN=https://.../{}`
+//! 3. file: an `allure.toml`, or a `[package.metadata.allure]` or
+//!    `[workspace.metadata.allure]` section of a `Cargo.toml`, whichever is
+//!    found first walking up from `CARGO_MANIFEST_DIR`
+//! 4. defaults
+//!
+//! Labels and link patterns are merged: a label name or link type set by a
+//! higher source replaces the lower ones. A relative `results_dir` in a file
+//! is relative to that file, so every crate in a workspace can share it.
+//!
+//! ```toml
+//! # allure.toml at the workspace root
+//! results_dir = "target/allure-results"
+//! clean_results = true
+//!
+//! [labels]
+//! layer = "integration"
+//!
+//! [link_patterns]
+//! issue = "https://jira.example.com/browse/{}"
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::error::{AllureError, AllureResult, ErrorPolicy};
+use crate::filter::FILTER_ENV;
+use crate::listener::Listeners;
+use crate::model::Link;
+use crate::redact::REDACT_ENV_ENV;
+use crate::runtime::{AllureConfig, LabelProvider};
+
+/// Environment variable overriding the results directory.
+pub const RESULTS_DIR_ENV: &str = "ALLURE_RESULTS_DIR";
+/// Environment variable overriding whether results are cleaned on init.
+pub const CLEAN_RESULTS_ENV: &str = "ALLURE_CLEAN_RESULTS";
+/// Environment variable making cleaning remove every file, not only Allure's.
+pub const CLEAN_ALL_ENV: &str = "ALLURE_CLEAN_ALL";
+/// Environment variable making runtime calls outside a test and write
+/// failures panic.
+pub const STRICT_ENV: &str = "ALLURE_STRICT";
+/// Prefix of environment variables adding a label to every test.
+pub const LABEL_ENV_PREFIX: &str = "ALLURE_LABEL_";
+/// Name of the configuration file.
+pub const CONFIG_FILE: &str = "allure.toml";
+
+/// Settings given by one configuration source; `None` leaves them to lower ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ConfigLayer {
+    pub(crate) results_dir: Option<String>,
+    pub(crate) clean_results: Option<bool>,
+    pub(crate) clean_all: Option<bool>,
+    pub(crate) default_timeout: Option<Duration>,
+    pub(crate) filter: Option<String>,
+    pub(crate) report_filtered: Option<bool>,
+    pub(crate) labels: Vec<(String, String)>,
+    pub(crate) link_patterns: BTreeMap<String, String>,
+    pub(crate) links: Vec<Link>,
+    pub(crate) parameters: Vec<(String, String)>,
+    pub(crate) label_provider: Option<LabelProvider>,
+    pub(crate) listeners: Listeners,
+    pub(crate) redact_patterns: Vec<String>,
+    pub(crate) redact_secrets: Vec<String>,
+    pub(crate) redact_env: Vec<String>,
+    pub(crate) on_missing_context: Option<ErrorPolicy>,
+    pub(crate) on_write_error: Option<ErrorPolicy>,
+    pub(crate) step_path_in_messages: Option<bool>,
+    pub(crate) print_step_path: Option<bool>,
+}
+
+/// The settings of a configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    #[serde(alias = "results-dir")]
+    results_dir: Option<String>,
+    #[serde(alias = "clean-results")]
+    clean_results: Option<bool>,
+    #[serde(alias = "clean-all")]
+    clean_all: Option<bool>,
+    filter: Option<String>,
+    #[serde(alias = "report-filtered")]
+    report_filtered: Option<bool>,
+    labels: BTreeMap<String, String>,
+    #[serde(alias = "link-patterns")]
+    link_patterns: BTreeMap<String, String>,
+    #[serde(alias = "redact-patterns")]
+    redact_patterns: Vec<String>,
+    #[serde(alias = "redact-env")]
+    redact_env: Vec<String>,
+    strict: Option<bool>,
+    #[serde(alias = "on-missing-context")]
+    on_missing_context: Option<ErrorPolicy>,
+    #[serde(alias = "on-write-error")]
+    on_write_error: Option<ErrorPolicy>,
+    #[serde(alias = "step-path-in-messages")]
+    step_path_in_messages: Option<bool>,
+    #[serde(alias = "print-step-path")]
+    print_step_path: Option<bool>,
+}
+
+impl ConfigLayer {
+    /// Reads the settings given by `ALLURE_*` environment variables.
+    pub(crate) fn from_env() -> Self {
+        Self::from_vars(std::env::vars())
+    }
+
+    fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut layer = Self::default();
+        for (key, value) in vars {
+            if key == RESULTS_DIR_ENV {
+                layer.results_dir = Some(value);
+            } else if key == CLEAN_RESULTS_ENV {
+                layer.clean_results = parse_bool(&value);
+                if layer.clean_results.is_none() {
+                    eprintln!(
+                        "allure: ignoring {}={:?}, expected true or false",
+                        CLEAN_RESULTS_ENV, value
+                    );
+                }
+            } else if key == CLEAN_ALL_ENV {
+                layer.clean_all = parse_bool(&value);
+                if layer.clean_all.is_none() {
+                    eprintln!(
+                        "allure: ignoring {}={:?}, expected true or false",
+                        CLEAN_ALL_ENV, value
+                    );
+                }
+            } else if key == STRICT_ENV {
+                match parse_bool(&value) {
+                    Some(strict) => layer.set_strict(strict),
+                    None => eprintln!(
+                        "allure: ignoring {}={:?}, expected true or false",
+                        STRICT_ENV, value
+                    ),
+                }
+            } else if key == REDACT_ENV_ENV {
+                layer.redact_env = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect();
+            } else if key == FILTER_ENV {
+                layer.filter = Some(value);
+            } else if let Some(name) = key.strip_prefix(LABEL_ENV_PREFIX) {
+                if !name.is_empty() {
+                    layer.labels.push((name.to_string(), value));
+                }
+            } else if let Some(link_type) = key
+                .strip_prefix("ALLURE_LINK_")
+                .and_then(|rest| rest.strip_suffix("_PATTERN"))
+            {
+                layer.link_patterns.insert(link_type.to_lowercase(), value);
+            }
+        }
+        // Environment order is unspecified
+        layer.labels.sort();
+        layer
+    }
+
+    /// Finds and reads the configuration file, reporting invalid ones on stderr.
+    pub(crate) fn from_file() -> Self {
+        let start = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok());
+        let Some(start) = start else {
+            return Self::default();
+        };
+        match find_file(&start) {
+            Ok(Some(layer)) => layer,
+            Ok(None) => Self::default(),
+            Err(e) => {
+                eprintln!("allure: ignoring configuration file: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Parses an `allure.toml`, resolving relative paths against `base`.
+    pub(crate) fn parse_file(text: &str, base: &Path) -> AllureResult<Self> {
+        let file: FileConfig =
+            toml::from_str(text).map_err(|e| AllureError::configuration(e.to_string()))?;
+        Ok(Self::from_file_config(file, base))
+    }
+
+    /// Reads the `[package.metadata.allure]` or `[workspace.metadata.allure]`
+    /// section of a `Cargo.toml`, if any.
+    fn parse_manifest(text: &str, base: &Path) -> AllureResult<Option<Self>> {
+        let manifest: toml::Table =
+            toml::from_str(text).map_err(|e| AllureError::configuration(e.to_string()))?;
+        let section = ["package", "workspace"].iter().find_map(|table| {
+            manifest
+                .get(*table)?
+                .get("metadata")?
+                .get("allure")
+                .cloned()
+        });
+        let Some(section) = section else {
+            return Ok(None);
+        };
+        let file: FileConfig = section
+            .try_into()
+            .map_err(|e: toml::de::Error| AllureError::configuration(e.to_string()))?;
+        Ok(Some(Self::from_file_config(file, base)))
+    }
+
+    fn from_file_config(file: FileConfig, base: &Path) -> Self {
+        let mut layer = Self::default();
+        if let Some(strict) = file.strict {
+            layer.set_strict(strict);
+        }
+        Self {
+            results_dir: file
+                .results_dir
+                .map(|dir| base.join(dir).to_string_lossy().into_owned()),
+            clean_results: file.clean_results,
+            clean_all: file.clean_all,
+            default_timeout: None,
+            filter: file.filter,
+            report_filtered: file.report_filtered,
+            labels: file.labels.into_iter().collect(),
+            link_patterns: file
+                .link_patterns
+                .into_iter()
+                .map(|(link_type, pattern)| (link_type.to_lowercase(), pattern))
+                .collect(),
+            redact_patterns: file.redact_patterns,
+            redact_env: file.redact_env,
+            on_missing_context: file.on_missing_context.or(layer.on_missing_context),
+            on_write_error: file.on_write_error.or(layer.on_write_error),
+            step_path_in_messages: file.step_path_in_messages,
+            print_step_path: file.print_step_path,
+            ..Self::default()
+        }
+    }
+
+    /// Makes missing contexts and write failures panic, or restores the
+    /// default policies.
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        let defaults = AllureConfig::default();
+        self.on_missing_context = Some(if strict {
+            ErrorPolicy::Panic
+        } else {
+            defaults.on_missing_context
+        });
+        self.on_write_error = Some(if strict {
+            ErrorPolicy::Panic
+        } else {
+            defaults.on_write_error
+        });
+    }
+
+    /// Applies this layer over `config`.
+    fn apply(&self, config: &mut AllureConfig) {
+        if let Some(dir) = &self.results_dir {
+            config.results_dir = dir.clone();
+        }
+        if let Some(clean) = self.clean_results {
+            config.clean_results = clean;
+        }
+        if let Some(all) = self.clean_all {
+            config.clean_all = all;
+        }
+        if let Some(policy) = self.on_missing_context {
+            config.on_missing_context = policy;
+        }
+        if let Some(policy) = self.on_write_error {
+            config.on_write_error = policy;
+        }
+        if let Some(enabled) = self.step_path_in_messages {
+            config.step_path_in_messages = enabled;
+        }
+        if let Some(enabled) = self.print_step_path {
+            config.print_step_path = enabled;
+        }
+        if let Some(timeout) = self.default_timeout {
+            config.default_timeout = Some(timeout);
+        }
+        if let Some(filter) = &self.filter {
+            config.filter = Some(filter.clone());
+        }
+        if let Some(report) = self.report_filtered {
+            config.report_filtered = report;
+        }
+        config
+            .labels
+            .retain(|(name, _)| !self.labels.iter().any(|(own, _)| own == name));
+        config.labels.extend(self.labels.iter().cloned());
+        config.links.extend(self.links.iter().cloned());
+        config
+            .parameters
+            .retain(|(name, _)| !self.parameters.iter().any(|(own, _)| own == name));
+        config.parameters.extend(self.parameters.iter().cloned());
+        if let Some(provider) = &self.label_provider {
+            config.label_provider = Some(provider.clone());
+        }
+        config.listeners.extend(&self.listeners);
+        let mut redactor = std::mem::take(&mut config.redactor);
+        for secret in &self.redact_secrets {
+            redactor = redactor.secret(secret);
+        }
+        for name in &self.redact_env {
+            if let Ok(value) = std::env::var(name) {
+                redactor = redactor.secret(value);
+            }
+        }
+        for pattern in &self.redact_patterns {
+            redactor = match redactor.clone().pattern(pattern) {
+                Ok(redactor) => redactor,
+                Err(e) => {
+                    eprintln!("allure: ignoring redaction pattern {:?}: {}", pattern, e);
+                    redactor
+                }
+            };
+        }
+        config.redactor = redactor;
+        config.link_patterns.extend(
+            self.link_patterns
+                .iter()
+                .map(|(link_type, pattern)| (link_type.clone(), pattern.clone())),
+        );
+    }
+}
+
+/// Looks for `allure.toml` or Cargo metadata in `start` and its ancestors.
+fn find_file(start: &Path) -> AllureResult<Option<ConfigLayer>> {
+    for dir in start.ancestors() {
+        let file = dir.join(CONFIG_FILE);
+        if file.is_file() {
+            let text = std::fs::read_to_string(&file)?;
+            return ConfigLayer::parse_file(&text, dir)
+                .map(Some)
+                .map_err(|e| in_file(&file, e));
+        }
+        let manifest = dir.join("Cargo.toml");
+        if manifest.is_file() {
+            let text = std::fs::read_to_string(&manifest)?;
+            if let Some(layer) =
+                ConfigLayer::parse_manifest(&text, dir).map_err(|e| in_file(&manifest, e))?
+            {
+                return Ok(Some(layer));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn in_file(path: &Path, error: AllureError) -> AllureError {
+    AllureError::configuration(format!("{}: {}", path.display(), error))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Resolves the configuration from code, environment, file and defaults.
+pub(crate) fn resolve(code: &ConfigLayer) -> AllureConfig {
+    resolve_layers(&[code, &ConfigLayer::from_env(), &ConfigLayer::from_file()])
+}
+
+/// Merges layers given from the highest precedence to the lowest.
+pub(crate) fn resolve_layers(layers: &[&ConfigLayer]) -> AllureConfig {
+    let mut config = AllureConfig::default();
+    for layer in layers.iter().rev() {
+        layer.apply(&mut config);
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_env_layer_reads_allure_variables() {
+        let layer = ConfigLayer::from_vars(vars(&[
+            ("ALLURE_RESULTS_DIR", "/tmp/results"),
+            ("ALLURE_CLEAN_RESULTS", "false"),
+            ("ALLURE_CLEAN_ALL", "true"),
+            ("ALLURE_REDACT_ENV", "API_TOKEN, DB_PASSWORD,"),
+            ("ALLURE_STRICT", "1"),
+            ("ALLURE_LABEL_layer", "integration"),
+            ("ALLURE_LABEL_component", "billing"),
+            (
+                "ALLURE_LINK_ISSUE_PATTERN",
+                "https://jira.example.com/browse/{}",
+            ),
+            ("ALLURE_UNRELATED", "ignored"),
+            ("PATH", "/usr/bin"),
+        ]));
+
+        assert_eq!(layer.results_dir.as_deref(), Some("/tmp/results"));
+        assert_eq!(layer.clean_results, Some(false));
+        assert_eq!(layer.clean_all, Some(true));
+        assert_eq!(layer.redact_env, ["API_TOKEN", "DB_PASSWORD"]);
+        assert_eq!(layer.on_missing_context, Some(ErrorPolicy::Panic));
+        assert_eq!(layer.on_write_error, Some(ErrorPolicy::Panic));
+        assert_eq!(
+            layer.labels,
+            vec![
+                ("component".to_string(), "billing".to_string()),
+                ("layer".to_string(), "integration".to_string()),
+            ]
+        );
+        assert_eq!(
+            layer.link_patterns.get("issue").map(String::as_str),
+            Some("https://jira.example.com/browse/{}")
+        );
+
+        let invalid = ConfigLayer::from_vars(vars(&[("ALLURE_CLEAN_RESULTS", "maybe")]));
+        assert_eq!(invalid.clean_results, None);
+    }
+
+    #[test]
+    fn test_file_layers_resolve_relative_results_dir() {
+        let base = Path::new("/work/repo");
+        let file = ConfigLayer::parse_file(
+            r#"
+                results_dir = "target/allure-results"
+                clean_results = false
+                strict = true
+                on_write_error = "warn-once"
+                step-path-in-messages = true
+
+                [labels]
+                layer = "unit"
+
+                [link_patterns]
+                tms = "https://tms.example.com/case/{}"
+            "#,
+            base,
+        )
+        .unwrap();
+        assert_eq!(
+            file.results_dir.map(PathBuf::from),
+            Some(base.join("target/allure-results"))
+        );
+        assert_eq!(file.labels, vec![("layer".to_string(), "unit".to_string())]);
+        assert_eq!(file.on_missing_context, Some(ErrorPolicy::Panic));
+        assert_eq!(file.on_write_error, Some(ErrorPolicy::WarnOnce));
+        assert_eq!(file.step_path_in_messages, Some(true));
+
+        let manifest = ConfigLayer::parse_manifest(
+            r#"
+                [package]
+                name = "billing"
+
+                [package.metadata.allure]
+                results-dir = "/abs/results"
+            "#,
+            base,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(manifest.results_dir.as_deref(), Some("/abs/results"));
+
+        let without = ConfigLayer::parse_manifest("[package]\nname = \"billing\"\n", base);
+        assert_eq!(without.unwrap(), None);
+
+        let err = ConfigLayer::parse_file("results_dr = \"x\"", base).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown field `results_dr`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_precedence_is_code_env_file_defaults() {
+        let code = ConfigLayer {
+            clean_results: Some(false),
+            labels: vec![("layer".to_string(), "e2e".to_string())],
+            ..Default::default()
+        };
+        let env = ConfigLayer {
+            results_dir: Some("env-results".to_string()),
+            clean_results: Some(true),
+            labels: vec![("component".to_string(), "billing".to_string())],
+            ..Default::default()
+        };
+        let file = ConfigLayer {
+            results_dir: Some("file-results".to_string()),
+            report_filtered: Some(true),
+            labels: vec![
+                ("layer".to_string(), "unit".to_string()),
+                ("component".to_string(), "core".to_string()),
+                ("team".to_string(), "payments".to_string()),
+            ],
+            link_patterns: [("issue".to_string(), "https://file/{}".to_string())].into(),
+            ..Default::default()
+        };
+
+        let config = resolve_layers(&[&code, &env, &file]);
+        assert_eq!(config.results_dir, "env-results");
+        assert!(!config.clean_results);
+        assert!(config.report_filtered);
+        assert_eq!(config.default_timeout, None);
+        assert_eq!(
+            config.labels,
+            vec![
+                ("team".to_string(), "payments".to_string()),
+                ("component".to_string(), "billing".to_string()),
+                ("layer".to_string(), "e2e".to_string()),
+            ]
+        );
+        assert_eq!(config.link_patterns["issue"], "https://file/{}");
+
+        let defaults = resolve_layers(&[]);
+        assert_eq!(defaults.results_dir, crate::writer::DEFAULT_RESULTS_DIR);
+        assert!(defaults.clean_results);
+    }
+
+    #[test]
+    fn test_find_file_prefers_nearest_source() {
+        let root = tempfile::tempdir().unwrap();
+        let member = root.path().join("crates/billing");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(
+            root.path().join("allure.toml"),
+            "results_dir = \"allure-results\"\n",
+        )
+        .unwrap();
+        std::fs::write(member.join("Cargo.toml"), "[package]\nname = \"billing\"\n").unwrap();
+
+        // The member manifest has no section, so the workspace file is used
+        let layer = find_file(&member).unwrap().unwrap();
+        assert_eq!(
+            layer.results_dir.map(PathBuf::from),
+            Some(root.path().join("allure-results"))
+        );
+
+        std::fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"billing\"\n\n[package.metadata.allure]\nclean_results = false\n",
+        )
+        .unwrap();
+        let layer = find_file(&member).unwrap().unwrap();
+        assert_eq!(layer.results_dir, None);
+        assert_eq!(layer.clean_results, Some(false));
+
+        std::fs::write(member.join("Cargo.toml"), "[package\n").unwrap();
+        let err = find_file(&member).unwrap_err().to_string();
+        assert!(err.contains("Cargo.toml"), "{}", err);
+    }
+}
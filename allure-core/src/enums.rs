@@ -1,7 +1,9 @@
 //! Allure enums for test result status, stage, severity, and other classifications.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeSet;
 use std::fmt;
+use std::sync::Mutex;
 
 /// Test result status indicating the outcome of a test.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
@@ -101,27 +103,111 @@ impl fmt::Display for Severity {
 }
 
 /// Link type for external references.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
-#[serde(rename_all = "lowercase")]
+///
+/// Besides the built-in types, a link can have a custom type such as `jira`
+/// or `github`, with its own URL pattern in the configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[non_exhaustive]
 pub enum LinkType {
     /// Default link type
     #[default]
-    #[serde(rename = "link")]
     Default,
     /// Link to issue tracker
     Issue,
     /// Link to test management system
     Tms,
+    /// Custom link type, written as its name
+    Custom(&'static str),
 }
 
-impl fmt::Display for LinkType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl LinkType {
+    /// Creates a custom link type usable in `static` items.
+    ///
+    /// The names of built-in types give those types, so `custom("issue")`
+    /// is [`LinkType::Issue`].
+    pub const fn custom(name: &'static str) -> Self {
+        if const_eq(name, "link") {
+            LinkType::Default
+        } else if const_eq(name, "issue") {
+            LinkType::Issue
+        } else if const_eq(name, "tms") {
+            LinkType::Tms
+        } else {
+            LinkType::Custom(name)
+        }
+    }
+
+    /// Returns the link type with the given name, built-in or custom.
+    ///
+    /// Custom names are interned, so each distinct name is allocated once
+    /// for the life of the process.
+    pub fn from_name(name: impl Into<String>) -> Self {
+        let name = name.into();
+        match name.as_str() {
+            "link" => LinkType::Default,
+            "issue" => LinkType::Issue,
+            "tms" => LinkType::Tms,
+            _ => LinkType::Custom(intern(name)),
+        }
+    }
+
+    /// Returns the name written to result files.
+    pub fn as_str(&self) -> &'static str {
         match self {
-            LinkType::Default => write!(f, "link"),
-            LinkType::Issue => write!(f, "issue"),
-            LinkType::Tms => write!(f, "tms"),
+            LinkType::Default => "link",
+            LinkType::Issue => "issue",
+            LinkType::Tms => "tms",
+            LinkType::Custom(name) => name,
+        }
+    }
+}
+
+/// String equality usable in `const fn`.
+const fn const_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
         }
+        i += 1;
+    }
+    true
+}
+
+/// Returns a `'static` copy of a custom link type name, leaking each
+/// distinct name only once.
+fn intern(name: String) -> &'static str {
+    static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut names = NAMES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(known) = names.get(name.as_str()) {
+        return known;
+    }
+    let name: &'static str = Box::leak(name.into_boxed_str());
+    names.insert(name);
+    name
+}
+
+impl fmt::Display for LinkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for LinkType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for LinkType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(LinkType::from_name)
     }
 }
 
@@ -411,6 +497,18 @@ mod tests {
         assert_eq!(format!("{}", LinkType::Issue), "issue");
         assert_eq!(format!("{}", LinkType::Tms), "tms");
         assert_eq!(format!("{}", LinkType::Default), "link");
+        assert_eq!(format!("{}", LinkType::custom("jira")), "jira");
+        assert_eq!(LinkType::from_name("tms"), LinkType::Tms);
+        assert_eq!(LinkType::from_name("github"), LinkType::custom("github"));
+        assert_eq!(LinkType::custom("issue"), LinkType::Issue);
+        assert_eq!(LinkType::custom("tms"), LinkType::Tms);
+        assert_eq!(LinkType::custom("link"), LinkType::Default);
+        const ISSUE: LinkType = LinkType::custom("issue");
+        assert_eq!(ISSUE, LinkType::Issue);
+        assert!(std::ptr::eq(
+            LinkType::from_name("gitlab").as_str(),
+            LinkType::from_name(String::from("gitlab")).as_str()
+        ));
     }
 
     #[test]
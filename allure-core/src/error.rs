@@ -3,6 +3,9 @@
 //! This module provides a unified error type for all Allure operations,
 //! including I/O errors, serialization errors, and configuration issues.
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Deserialize;
 use thiserror::Error;
 
 /// Result type alias for Allure operations.
@@ -57,6 +60,41 @@ impl AllureError {
     }
 }
 
+/// What to do when a runtime call can't do its job, such as a `label()`
+/// made outside a test or an attachment that can't be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorPolicy {
+    /// Do nothing.
+    #[default]
+    Ignore,
+    /// Print the first occurrence to stderr.
+    WarnOnce,
+    /// Panic, failing the test or helper that made the call.
+    Panic,
+}
+
+impl ErrorPolicy {
+    /// Applies the policy to a problem described by `message`.
+    ///
+    /// `warned` records whether a warning was already printed. A panic is
+    /// downgraded to a warning while the thread is already panicking.
+    pub(crate) fn report(self, warned: &AtomicBool, message: impl FnOnce() -> String) {
+        match self {
+            ErrorPolicy::Ignore => {}
+            ErrorPolicy::Panic if !std::thread::panicking() => panic!("allure: {}", message()),
+            _ => {
+                if !warned.swap(true, Ordering::Relaxed) {
+                    eprintln!(
+                        "allure: {} (further occurrences are not reported)",
+                        message()
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,6 +121,26 @@ mod tests {
         assert!(matches!(allure_err, AllureError::Io(_)));
     }
 
+    #[test]
+    fn test_error_policy_reports() {
+        let warned = AtomicBool::new(false);
+        ErrorPolicy::Ignore.report(&warned, || unreachable!());
+        assert!(!warned.load(Ordering::Relaxed));
+
+        ErrorPolicy::WarnOnce.report(&warned, || "lost a label".to_string());
+        assert!(warned.load(Ordering::Relaxed));
+        ErrorPolicy::WarnOnce.report(&warned, || unreachable!());
+
+        let panic = std::panic::catch_unwind(|| {
+            ErrorPolicy::Panic.report(&AtomicBool::new(false), || "lost a label".to_string())
+        })
+        .unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().map(String::as_str),
+            Some("allure: lost a label")
+        );
+    }
+
     #[test]
     fn test_error_constructors() {
         let attach_err = AllureError::invalid_attachment("file too large");
@@ -0,0 +1,464 @@
+//! Label-expression test filtering.
+//!
+//! A filter selects tests by their labels and statuses, for example
+//! `severity >= critical` or `tag:smoke && !tag:flaky`. It is read from
+//! `configure().filter(...)`, the `ALLURE_FILTER` environment variable or
+//! `allure.toml` (see [`crate::config`]), and evaluated when an
+//! `#[allure_test]` context is set up. Tests it excludes return without
+//! running; with `configure().report_filtered(true)` they are also written
+//! as skipped, with the filter as the reason.
+//!
+//! The language:
+//!
+//! - `name:value` or `name = value` matches a label, `name != value` its absence
+//! - a bare word such as `smoke` is short for `tag:smoke`
+//! - `severity` also supports `<`, `<=`, `>` and `>=` (`trivial` < `minor` <
+//!   `normal` < `critical` < `blocker`); tests without one count as `normal`
+//! - `status:flaky` and `status:muted` match tests marked flaky or muted
+//! - `!`, `&&`, `||` and parentheses combine expressions
+//! - values with spaces are quoted: `epic:"User Management"`
+//!
+//! Statically known labels are those given as literals in attributes before
+//! `#[allure_test]`, plus the labels every test starts with.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::enums::Status;
+use crate::error::{AllureError, AllureResult};
+use crate::runtime::{get_config, start_current_test, take_context, with_context};
+
+/// Environment variable holding the filter expression.
+pub const FILTER_ENV: &str = "ALLURE_FILTER";
+
+/// Severities from lowest to highest.
+const SEVERITIES: [&str; 5] = ["trivial", "minor", "normal", "critical", "blocker"];
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Label { name: String, value: String },
+    Severity { op: Comparison, rank: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    pub fn parse(source: &str) -> AllureResult<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            source,
+            tokens,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+        if let Some((token, column)) = parser.tokens.get(parser.pos) {
+            return Err(parser.error(*column, format!("unexpected {}", token)));
+        }
+        Ok(Filter {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    /// Returns true if a test with these `(name, value)` labels is selected.
+    ///
+    /// The flaky and muted statuses are given as `("status", "flaky")` and
+    /// `("status", "muted")`.
+    pub fn matches(&self, labels: &[(&str, &str)]) -> bool {
+        self.expr.matches(labels)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Expr {
+    fn matches(&self, labels: &[(&str, &str)]) -> bool {
+        match self {
+            Expr::Not(expr) => !expr.matches(labels),
+            Expr::And(left, right) => left.matches(labels) && right.matches(labels),
+            Expr::Or(left, right) => left.matches(labels) || right.matches(labels),
+            Expr::Label { name, value } => labels
+                .iter()
+                .any(|(label, label_value)| label == name && label_value == value),
+            Expr::Severity { op, rank } => {
+                let actual = labels
+                    .iter()
+                    .filter(|(label, _)| *label == "severity")
+                    .find_map(|(_, value)| severity_rank(value))
+                    .unwrap_or(2);
+                match op {
+                    Comparison::Less => actual < *rank,
+                    Comparison::LessOrEqual => actual <= *rank,
+                    Comparison::Greater => actual > *rank,
+                    Comparison::GreaterOrEqual => actual >= *rank,
+                }
+            }
+        }
+    }
+}
+
+fn severity_rank(value: &str) -> Option<usize> {
+    SEVERITIES
+        .iter()
+        .position(|severity| severity.eq_ignore_ascii_case(value))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+    Colon,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{}`", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+            Token::Not => f.write_str("`!`"),
+            Token::And => f.write_str("`&&`"),
+            Token::Or => f.write_str("`||`"),
+            Token::Open => f.write_str("`(`"),
+            Token::Close => f.write_str("`)`"),
+            Token::Colon => f.write_str("`:`"),
+            Token::Equal => f.write_str("`=`"),
+            Token::NotEqual => f.write_str("`!=`"),
+            Token::Less => f.write_str("`<`"),
+            Token::LessOrEqual => f.write_str("`<=`"),
+            Token::Greater => f.write_str("`>`"),
+            Token::GreaterOrEqual => f.write_str("`>=`"),
+        }
+    }
+}
+
+/// Splits the source into tokens paired with their 1-based column.
+fn tokenize(source: &str) -> AllureResult<Vec<(Token, usize)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (chars[i], next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('!', Some('=')) => (Token::NotEqual, 2),
+            ('=', Some('=')) => (Token::Equal, 2),
+            ('<', Some('=')) => (Token::LessOrEqual, 2),
+            ('>', Some('=')) => (Token::GreaterOrEqual, 2),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            (':', _) => (Token::Colon, 1),
+            ('=', _) => (Token::Equal, 1),
+            ('<', _) => (Token::Less, 1),
+            ('>', _) => (Token::Greater, 1),
+            ('"', _) => {
+                let Some(end) = chars[i + 1..].iter().position(|&c| c == '"') else {
+                    return Err(filter_error(source, column, "unterminated string"));
+                };
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Quoted(text), end + 2)
+            }
+            (c, _) if is_word_char(c) => {
+                let len = chars[i..].iter().take_while(|&&c| is_word_char(c)).count();
+                (Token::Word(chars[i..i + len].iter().collect()), len)
+            }
+            (c, _) => {
+                return Err(filter_error(
+                    source,
+                    column,
+                    format!("unexpected character `{}`", c),
+                ))
+            }
+        };
+        tokens.push((token, column));
+        i += len;
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/')
+}
+
+fn filter_error(source: &str, column: usize, message: impl fmt::Display) -> AllureError {
+    AllureError::configuration(format!(
+        "invalid filter `{}`: {} at column {}",
+        source, message, column
+    ))
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> AllureResult<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            let column = self.source.chars().count() + 1;
+            self.error(column, "unexpected end of expression")
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn error(&self, column: usize, message: impl fmt::Display) -> AllureError {
+        filter_error(self.source, column, message)
+    }
+
+    fn or(&mut self) -> AllureResult<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> AllureResult<Expr> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> AllureResult<Expr> {
+        match self.next()? {
+            (Token::Not, _) => Ok(Expr::Not(Box::new(self.unary()?))),
+            (Token::Open, column) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Ok((Token::Close, _)) => Ok(expr),
+                    Ok((token, column)) => {
+                        Err(self.error(column, format!("expected `)` but found {}", token)))
+                    }
+                    Err(_) => Err(self.error(column, "unclosed `(`")),
+                }
+            }
+            (Token::Word(name), _) | (Token::Quoted(name), _) => self.comparison(name),
+            (token, column) => Err(self.error(column, format!("unexpected {}", token))),
+        }
+    }
+
+    fn comparison(&mut self, name: String) -> AllureResult<Expr> {
+        let op = match self.peek() {
+            Some(
+                Token::Colon
+                | Token::Equal
+                | Token::NotEqual
+                | Token::Less
+                | Token::LessOrEqual
+                | Token::Greater
+                | Token::GreaterOrEqual,
+            ) => self.next()?,
+            // A bare word is a tag
+            _ => {
+                return Ok(Expr::Label {
+                    name: "tag".to_string(),
+                    value: name,
+                })
+            }
+        };
+        let (value, column) = match self.next()? {
+            (Token::Word(value), column) | (Token::Quoted(value), column) => (value, column),
+            (token, column) => {
+                return Err(self.error(column, format!("expected a value but found {}", token)))
+            }
+        };
+
+        let ordering = match op.0 {
+            Token::Colon | Token::Equal => return Ok(Expr::Label { name, value }),
+            Token::NotEqual => return Ok(Expr::Not(Box::new(Expr::Label { name, value }))),
+            Token::Less => Comparison::Less,
+            Token::LessOrEqual => Comparison::LessOrEqual,
+            Token::Greater => Comparison::Greater,
+            _ => Comparison::GreaterOrEqual,
+        };
+        if name != "severity" {
+            return Err(self.error(op.1, format!("{} only applies to `severity`", op.0)));
+        }
+        let rank = severity_rank(&value).ok_or_else(|| {
+            self.error(
+                column,
+                format!(
+                    "unknown severity `{}`, expected one of {}",
+                    value,
+                    SEVERITIES.join(", ")
+                ),
+            )
+        })?;
+        Ok(Expr::Severity { op: ordering, rank })
+    }
+}
+
+/// Returns the filter of this run, from the configuration or `ALLURE_FILTER`.
+pub fn active() -> Option<&'static Filter> {
+    static FILTER: OnceLock<Option<Filter>> = OnceLock::new();
+    FILTER
+        .get_or_init(|| {
+            let source = get_config()
+                .filter
+                .filter(|source| !source.trim().is_empty())?;
+            match Filter::parse(&source) {
+                Ok(filter) => Some(filter),
+                Err(e) => {
+                    eprintln!("allure: ignoring {}; running all tests", e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Applies the active filter to the current test.
+///
+/// `static_labels` are the labels declared by attributes, which are only
+/// added to the context once the body runs. Returns true if the test is
+/// excluded; its context is then closed, and written as skipped only when
+/// `report_filtered` is configured. Otherwise the test starts, see
+/// [`TestContext::unstarted`](crate::runtime::TestContext::unstarted).
+pub fn exclude_current_test(static_labels: &[(&str, &str)]) -> bool {
+    let Some(filter) = active() else {
+        start_current_test();
+        return false;
+    };
+    let excluded = with_context(|ctx| {
+        let details = ctx.result.status_details.as_ref();
+        let mut labels: Vec<(&str, &str)> = ctx
+            .result
+            .labels
+            .iter()
+            .map(|label| (label.name.as_str(), label.value.as_str()))
+            .collect();
+        labels.extend_from_slice(static_labels);
+        if details.and_then(|d| d.flaky).unwrap_or(false) {
+            labels.push(("status", "flaky"));
+        }
+        if details.and_then(|d| d.muted).unwrap_or(false) {
+            labels.push(("status", "muted"));
+        }
+        !filter.matches(&labels)
+    })
+    .unwrap_or(false);
+
+    if excluded {
+        if let Some(mut ctx) = take_context() {
+            if get_config().report_filtered {
+                let reason = format!("excluded by filter: {}", filter);
+                ctx.finish(Status::Skipped, Some(reason), None);
+            }
+        }
+    } else {
+        start_current_test();
+    }
+    excluded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(source: &str, labels: &[(&str, &str)]) -> bool {
+        Filter::parse(source).unwrap().matches(labels)
+    }
+
+    #[test]
+    fn test_filter_matches_labels_and_statuses() {
+        let smoke = [("tag", "smoke"), ("severity", "critical")];
+        let flaky = [
+            ("tag", "smoke"),
+            ("status", "flaky"),
+            ("epic", "User Management"),
+        ];
+
+        assert!(matches("smoke && !slow", &smoke));
+        assert!(matches("tag:smoke && !status:flaky", &smoke));
+        assert!(!matches("tag:smoke && !status:flaky", &flaky));
+        assert!(matches(
+            "epic:\"User Management\" || severity = blocker",
+            &flaky
+        ));
+        assert!(matches("severity != minor", &smoke));
+        assert!(matches("!(tag:slow || tag:nightly) && smoke", &smoke));
+    }
+
+    #[test]
+    fn test_severity_comparisons_default_to_normal() {
+        let critical = [("severity", "critical")];
+        assert!(matches("severity >= critical", &critical));
+        assert!(!matches("severity > critical", &critical));
+        assert!(matches("severity < blocker", &critical));
+        assert!(!matches("severity >= critical", &[]));
+        assert!(matches("severity <= normal", &[]));
+    }
+
+    #[test]
+    fn test_invalid_filters_point_at_the_problem() {
+        let error = |source: &str| Filter::parse(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("smoke &&"),
+            "Configuration error: invalid filter `smoke &&`: unexpected end of expression at column 9"
+        );
+        assert_eq!(
+            error("(smoke || slow"),
+            "Configuration error: invalid filter `(smoke || slow`: unclosed `(` at column 1"
+        );
+        assert_eq!(
+            error("smoke slow"),
+            "Configuration error: invalid filter `smoke slow`: unexpected `slow` at column 7"
+        );
+        assert_eq!(
+            error("tag >= smoke"),
+            "Configuration error: invalid filter `tag >= smoke`: `>=` only applies to `severity` at column 5"
+        );
+        assert!(error("severity >= urgent").contains("unknown severity `urgent`"));
+        assert!(error("epic:\"Users").contains("unterminated string at column 6"));
+        assert!(error("smoke & slow").contains("unexpected character `&` at column 7"));
+    }
+}
@@ -0,0 +1,730 @@
+//! Test harness for `harness = false` test targets.
+//!
+//! libtest offers no hooks around the run and never reports tests it
+//! doesn't execute. This harness runs the tests registered by
+//! `#[allure_test]` instead: it accepts the usual libtest filters, runs
+//! tests in parallel (each on a fresh thread, so contexts never leak
+//! between tests), reports ignored tests to Allure as skipped without
+//! running them, and owns the run's before-all/after-all hooks.
+//!
+//! Supported options: `FILTER...`, `--exact`, `--skip FILTER`, `--ignored`,
+//! `--include-ignored`, `--list`, `--test-threads N` and `-q`/`--quiet`.
+//! Panic messages of the test threads are captured and only shown for
+//! failing tests; other output is never captured, so `--nocapture` and
+//! `--show-output` are accepted and ignored, as are `--color` and `--format`.
+//!
+//! # Example
+//!
+//! ```toml
+//! [[test]]
+//! name = "api"
+//! harness = false
+//! ```
+//!
+//! ```no_run
+//! // tests/api.rs
+//! use allure_core::harness::Harness;
+//!
+//! fn main() {
+//!     let _ = allure_core::configure().results_dir("allure-results").init();
+//!     Harness::new()
+//!         .before_all("Start server", || { /* ... */ })
+//!         .after_all("Stop server", || { /* ... */ })
+//!         .run();
+//! }
+//! ```
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::io::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, Once, OnceLock};
+use std::time::Instant;
+
+use crate::enums::{LabelName, Status};
+use crate::registry::{self, ShouldPanic, TestDescriptor};
+use crate::runtime::{
+    after_all, before_all, end_run, panic_message, set_context, take_context, TestContext,
+};
+use crate::{filter, testplan};
+
+/// Message of the panic raised by `#[allure_test]` when a `#[should_panic]`
+/// test returns normally.
+const DID_NOT_PANIC: &str = "Test did not panic as expected";
+
+/// A before-all or after-all hook.
+type Hook = (String, Box<dyn FnOnce()>);
+
+/// Runs the registered tests with the command-line arguments and exits.
+///
+/// Shorthand for `Harness::new().run()`.
+pub fn main() -> ! {
+    Harness::new().run()
+}
+
+/// Test runner for `harness = false` targets.
+#[derive(Default)]
+pub struct Harness {
+    before_all: Vec<Hook>,
+    after_all: Vec<Hook>,
+}
+
+impl Harness {
+    /// Creates a harness without hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook run once before the tests, recorded as a before-all
+    /// fixture. If it panics, no tests are run.
+    pub fn before_all(mut self, name: impl Into<String>, hook: impl FnOnce() + 'static) -> Self {
+        self.before_all.push((name.into(), Box::new(hook)));
+        self
+    }
+
+    /// Adds a hook run once after the tests, recorded as an after-all
+    /// fixture. Hooks run even if tests or before-all hooks failed.
+    pub fn after_all(mut self, name: impl Into<String>, hook: impl FnOnce() + 'static) -> Self {
+        self.after_all.push((name.into(), Box::new(hook)));
+        self
+    }
+
+    /// Runs the tests selected by the command-line arguments and exits with
+    /// libtest's exit code: `0` on success, `101` on failure.
+    pub fn run(self) -> ! {
+        let code = self.run_with_args(std::env::args().skip(1));
+        std::process::exit(code)
+    }
+
+    /// Runs the tests selected by `args` and returns the exit code.
+    pub fn run_with_args(self, args: impl IntoIterator<Item = String>) -> i32 {
+        let options = match Options::parse(args) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("error: {}", message);
+                return 101;
+            }
+        };
+
+        let mut tests = registry::tests();
+        tests.retain(|test| test.run.is_some());
+        let total = tests.len();
+        tests.retain(|test| {
+            options.selects(test) && testplan::is_selected(&test.full_name(), test.allure_id)
+        });
+        let filtered_out = total - tests.len();
+
+        if options.list {
+            for test in &tests {
+                println!("{}: test", test.test_name());
+            }
+            if !options.quiet {
+                println!();
+                println!("{}, 0 benchmarks", plural(tests.len(), "test"));
+            }
+            return 0;
+        }
+
+        let mut hooks_failed = false;
+        for (name, hook) in self.before_all {
+            if let Err(message) = run_hook(&name, || before_all(name.clone(), hook)) {
+                eprintln!("before-all hook '{}' failed: {}", name, message);
+                hooks_failed = true;
+                break;
+            }
+        }
+
+        let summary = if hooks_failed {
+            Summary::default()
+        } else {
+            run_tests(&tests, &options)
+        };
+
+        for (name, hook) in self.after_all {
+            if let Err(message) = run_hook(&name, || after_all(name.clone(), hook)) {
+                eprintln!("after-all hook '{}' failed: {}", name, message);
+                hooks_failed = true;
+            }
+        }
+        end_run();
+
+        if !hooks_failed {
+            summary.print(filtered_out);
+        }
+        if hooks_failed || !summary.failures.is_empty() {
+            101
+        } else {
+            0
+        }
+    }
+}
+
+/// Whether ignored tests were asked to run with `--ignored` or
+/// `--include-ignored`.
+///
+/// Used by `#[allure_test]`: an ignored test that runs anyway records itself
+/// as skipped unless this returns `true`.
+pub fn runs_ignored_tests() -> bool {
+    static REQUESTED: OnceLock<bool> = OnceLock::new();
+    *REQUESTED.get_or_init(|| {
+        std::env::args().any(|arg| arg == "--ignored" || arg == "--include-ignored")
+    })
+}
+
+/// Runs a future to completion on the current thread.
+///
+/// Used to run async `#[allure_test]` functions. With the `tokio` feature the
+/// future runs on a current-thread tokio runtime with all drivers enabled.
+#[cfg(feature = "tokio")]
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime")
+        .block_on(fut)
+}
+
+/// Runs a future to completion on the current thread.
+///
+/// Used to run async `#[allure_test]` functions. With the `tokio` feature the
+/// future runs on a current-thread tokio runtime with all drivers enabled.
+#[cfg(not(feature = "tokio"))]
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = std::pin::pin!(fut);
+    loop {
+        if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::park();
+    }
+}
+
+/// Which ignored tests to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum RunIgnored {
+    /// Ignored tests are reported as skipped without running.
+    #[default]
+    No,
+    /// Only ignored tests run.
+    Only,
+    /// Ignored tests run along with the others.
+    Include,
+}
+
+/// Parsed command-line options.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    filters: Vec<String>,
+    skip: Vec<String>,
+    exact: bool,
+    ignored: RunIgnored,
+    list: bool,
+    quiet: bool,
+    test_threads: Option<usize>,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = |name: &str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("argument for option '{}' missing", name))
+            };
+            match flag.as_str() {
+                "--exact" => options.exact = true,
+                "--skip" => options.skip.push(value("--skip")?),
+                "--ignored" => options.ignored = RunIgnored::Only,
+                "--include-ignored" => options.ignored = RunIgnored::Include,
+                "--list" => options.list = true,
+                "-q" | "--quiet" => options.quiet = true,
+                "--test-threads" => {
+                    let threads = value("--test-threads")?;
+                    options.test_threads = match threads.parse::<usize>() {
+                        Ok(0) | Err(_) => {
+                            return Err(format!(
+                                "argument for --test-threads must be a number > 0 (got {})",
+                                threads
+                            ))
+                        }
+                        Ok(threads) => Some(threads),
+                    };
+                }
+                "--nocapture" | "--show-output" | "--test" => {}
+                "--color" | "--format" | "-Z" => {
+                    value(&flag)?;
+                }
+                _ if flag.starts_with('-') => {
+                    return Err(format!("Unrecognized option: '{}'", flag));
+                }
+                _ => options.filters.push(arg),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Whether the test passes the name filters and the ignored selection.
+    fn selects(&self, test: &TestDescriptor) -> bool {
+        if self.ignored == RunIgnored::Only && !test.ignore {
+            return false;
+        }
+        let name = test.test_name();
+        let matches = |filter: &String| {
+            if self.exact {
+                name == *filter
+            } else {
+                name.contains(filter.as_str())
+            }
+        };
+        (self.filters.is_empty() || self.filters.iter().any(matches))
+            && !self.skip.iter().any(matches)
+    }
+
+    fn threads(&self) -> usize {
+        self.test_threads
+            .or_else(|| {
+                std::env::var("RUST_TEST_THREADS")
+                    .ok()
+                    .and_then(|threads| threads.parse().ok())
+                    .filter(|threads| *threads > 0)
+            })
+            .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1)
+    }
+}
+
+/// How a single test ended.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Passed,
+    Failed(String),
+    Ignored(Option<&'static str>),
+}
+
+#[derive(Default)]
+struct Summary {
+    passed: usize,
+    ignored: usize,
+    failures: Vec<(String, String)>,
+    elapsed: f64,
+}
+
+impl Summary {
+    fn print(&self, filtered_out: usize) {
+        if !self.failures.is_empty() {
+            println!();
+            println!("failures:");
+            println!();
+            for (name, message) in &self.failures {
+                println!("---- {} stdout ----", name);
+                println!("{}", message);
+                println!();
+            }
+            println!("failures:");
+            for (name, _) in &self.failures {
+                println!("    {}", name);
+            }
+        }
+        println!();
+        println!(
+            "test result: {}. {} passed; {} failed; {} ignored; 0 measured; {} filtered out; finished in {:.2}s",
+            if self.failures.is_empty() { "ok" } else { "FAILED" },
+            self.passed,
+            self.failures.len(),
+            self.ignored,
+            filtered_out,
+            self.elapsed
+        );
+        println!();
+    }
+}
+
+/// Runs the tests on a pool of worker threads, printing each outcome as it
+/// comes in.
+fn run_tests(tests: &[&'static TestDescriptor], options: &Options) -> Summary {
+    let started = Instant::now();
+    println!();
+    println!("running {}", plural(tests.len(), "test"));
+
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::new());
+    let workers = options.threads().min(tests.len()).max(1);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(test) = tests.get(index) else {
+                    break;
+                };
+                let outcome = run_test(test, options.ignored);
+                report(test, &outcome, options.quiet);
+                outcomes
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push((test.test_name(), outcome));
+            });
+        }
+    });
+    if options.quiet {
+        println!();
+    }
+
+    let mut summary = Summary {
+        elapsed: started.elapsed().as_secs_f64(),
+        ..Default::default()
+    };
+    let mut outcomes = outcomes
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    outcomes.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, outcome) in outcomes {
+        match outcome {
+            Outcome::Passed => summary.passed += 1,
+            Outcome::Ignored(_) => summary.ignored += 1,
+            Outcome::Failed(message) => summary.failures.push((name, message)),
+        }
+    }
+    summary
+}
+
+/// Runs one test on a thread of its own, named after the test.
+fn run_test(test: &TestDescriptor, ignored: RunIgnored) -> Outcome {
+    let Some(run) = test.run else {
+        return Outcome::Ignored(Some("needs a runtime attribute to run"));
+    };
+    if test.ignore && ignored == RunIgnored::No {
+        record_ignored(test);
+        return Outcome::Ignored(test.ignore_reason);
+    }
+
+    let name = test.test_name();
+    capture_panics(&name);
+    let result = std::thread::Builder::new()
+        .name(name.clone())
+        .spawn(run)
+        .map_err(|e| format!("failed to spawn test thread: {}", e))
+        .and_then(|handle| {
+            handle
+                .join()
+                .map_err(|panic| panic_message(panic.as_ref(), "Test panicked"))
+        });
+    let output = take_captured_panics(&name);
+
+    match evaluate(test.should_panic, result) {
+        Outcome::Failed(message) if !output.is_empty() => {
+            if output.contains(&message) {
+                Outcome::Failed(output.trim_end().to_string())
+            } else {
+                Outcome::Failed(format!("{}\n{}", output.trim_end(), message))
+            }
+        }
+        outcome => outcome,
+    }
+}
+
+/// Writes an ignored test as skipped, with the metadata its attributes
+/// declared, without running it.
+fn record_ignored(test: &TestDescriptor) {
+    let mut ctx = TestContext::unstarted(test.display_name, test.full_name());
+    for (name, value) in test.labels {
+        ctx.add_label(*name, *value);
+    }
+    for link in test.links {
+        ctx.add_link(link.url, link.name.map(str::to_string), link.link_type);
+    }
+    if let Some(id) = test.allure_id {
+        ctx.add_label_name(LabelName::AllureId, id);
+    }
+    set_context(ctx);
+    if filter::exclude_current_test(&[]) {
+        return;
+    }
+    let reason = test.ignore_reason.unwrap_or("Ignored test");
+    if let Some(mut ctx) = take_context() {
+        ctx.finish(Status::Skipped, Some(reason.to_string()), None);
+    }
+}
+
+/// Panic output of the running tests, by test thread name. Threads a test
+/// starts under the same name, such as timeout workers, are included.
+static CAPTURED_PANICS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn lock_captured_panics() -> MutexGuard<'static, BTreeMap<String, String>> {
+    CAPTURED_PANICS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Starts capturing the panics of the test thread called `name`, so
+/// passing `#[should_panic]` tests stay quiet.
+fn capture_panics(name: &str) {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let thread = std::thread::current();
+            let name = thread.name().unwrap_or_default();
+            let mut captured = lock_captured_panics();
+            if let Some(output) = captured.get_mut(name) {
+                let message = panic_message(info.payload(), "Box<dyn Any>");
+                let _ = match info.location() {
+                    Some(location) => {
+                        writeln!(output, "thread '{}' panicked at {}:", name, location)
+                    }
+                    None => writeln!(output, "thread '{}' panicked:", name),
+                };
+                let _ = writeln!(output, "{}", message);
+                let backtrace = Backtrace::capture();
+                if backtrace.status() == BacktraceStatus::Captured {
+                    let _ = writeln!(output, "stack backtrace:\n{}", backtrace);
+                }
+                return;
+            }
+            drop(captured);
+            previous(info);
+        }));
+    });
+    lock_captured_panics().insert(name.to_string(), String::new());
+}
+
+/// Stops capturing the panics of the test thread called `name` and returns
+/// what was captured.
+fn take_captured_panics(name: &str) -> String {
+    lock_captured_panics().remove(name).unwrap_or_default()
+}
+
+/// Applies libtest's pass/fail rules to a finished test. `Err` is a panic
+/// message; `Ok(Err(_))` is the error of a `Result` test.
+fn evaluate(should_panic: ShouldPanic, result: Result<Result<(), String>, String>) -> Outcome {
+    match (should_panic, result) {
+        (ShouldPanic::No, Ok(Ok(()))) => Outcome::Passed,
+        (ShouldPanic::No, Ok(Err(error))) => Outcome::Failed(format!("Error: {}", error)),
+        (ShouldPanic::No, Err(message)) => Outcome::Failed(message),
+        (_, Ok(_)) => Outcome::Failed("test did not panic as expected".to_string()),
+        (ShouldPanic::Yes, Err(message)) if message == DID_NOT_PANIC => {
+            Outcome::Failed("test did not panic as expected".to_string())
+        }
+        (ShouldPanic::Yes, Err(_)) => Outcome::Passed,
+        (ShouldPanic::YesWithMessage(expected), Err(message)) => {
+            if message.contains(expected) {
+                Outcome::Passed
+            } else {
+                Outcome::Failed(format!(
+                    "panic did not contain expected string\n      panic message: {:?},\n expected substring: {:?}",
+                    message, expected
+                ))
+            }
+        }
+    }
+}
+
+fn report(test: &TestDescriptor, outcome: &Outcome, quiet: bool) {
+    let mut out = std::io::stdout().lock();
+    let _ = if quiet {
+        let mark = match outcome {
+            Outcome::Passed => ".",
+            Outcome::Failed(_) => "F",
+            Outcome::Ignored(_) => "i",
+        };
+        write!(out, "{}", mark)
+    } else {
+        match outcome {
+            Outcome::Passed => writeln!(out, "test {} ... ok", test.test_name()),
+            Outcome::Failed(_) => writeln!(out, "test {} ... FAILED", test.test_name()),
+            Outcome::Ignored(None) => writeln!(out, "test {} ... ignored", test.test_name()),
+            Outcome::Ignored(Some(reason)) => {
+                writeln!(out, "test {} ... ignored, {}", test.test_name(), reason)
+            }
+        }
+    };
+    let _ = out.flush();
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+fn run_hook(name: &str, hook: impl FnOnce()) -> Result<(), String> {
+    catch_unwind(AssertUnwindSafe(hook))
+        .map_err(|panic| panic_message(panic.as_ref(), &format!("hook '{}' panicked", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn descriptor(name: &'static str, ignore: bool) -> TestDescriptor {
+        TestDescriptor {
+            name,
+            module_path: "api_tests::auth",
+            display_name: name,
+            labels: &[],
+            links: &[],
+            allure_id: None,
+            file: "tests/api_tests.rs",
+            line: 1,
+            ignore,
+            ignore_reason: None,
+            should_panic: ShouldPanic::No,
+            run: Some(|| Ok(())),
+        }
+    }
+
+    #[test]
+    fn test_parse_libtest_options() {
+        let options = Options::parse(args(&[
+            "login",
+            "--exact",
+            "--skip",
+            "slow",
+            "--skip=flaky",
+            "--include-ignored",
+            "--test-threads=4",
+            "--nocapture",
+            "--color",
+            "never",
+        ]))
+        .unwrap();
+        assert_eq!(options.filters, ["login"]);
+        assert_eq!(options.skip, ["slow", "flaky"]);
+        assert!(options.exact);
+        assert_eq!(options.ignored, RunIgnored::Include);
+        assert_eq!(options.test_threads, Some(4));
+        assert_eq!(options.threads(), 4);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_options() {
+        assert_eq!(
+            Options::parse(args(&["--bogus"])).unwrap_err(),
+            "Unrecognized option: '--bogus'"
+        );
+        assert!(Options::parse(args(&["--test-threads", "0"])).is_err());
+        assert!(Options::parse(args(&["--skip"])).is_err());
+    }
+
+    #[test]
+    fn test_selects_by_filter_skip_and_ignored() {
+        let login = descriptor("test_login", false);
+        let logout = descriptor("test_logout", true);
+
+        let options = Options::parse(args(&["login"])).unwrap();
+        assert!(options.selects(&login));
+        assert!(!options.selects(&logout));
+
+        let options = Options::parse(args(&["auth::test_log", "--skip", "out"])).unwrap();
+        assert!(options.selects(&login));
+        assert!(!options.selects(&logout));
+
+        let options = Options::parse(args(&["test_login", "--exact"])).unwrap();
+        assert!(!options.selects(&login), "exact matches the full path");
+        let options = Options::parse(args(&["auth::test_login", "--exact"])).unwrap();
+        assert!(options.selects(&login));
+
+        let options = Options::parse(args(&["--ignored"])).unwrap();
+        assert!(!options.selects(&login));
+        assert!(options.selects(&logout));
+    }
+
+    #[test]
+    fn test_evaluate_should_panic() {
+        assert_eq!(evaluate(ShouldPanic::No, Ok(Ok(()))), Outcome::Passed);
+        assert_eq!(
+            evaluate(ShouldPanic::No, Ok(Err("\"refused\"".into()))),
+            Outcome::Failed("Error: \"refused\"".into())
+        );
+        assert_eq!(
+            evaluate(ShouldPanic::Yes, Err("boom".into())),
+            Outcome::Passed
+        );
+        assert!(matches!(
+            evaluate(ShouldPanic::Yes, Err(DID_NOT_PANIC.into())),
+            Outcome::Failed(_)
+        ));
+        assert_eq!(
+            evaluate(ShouldPanic::YesWithMessage("boom"), Err("big boom".into())),
+            Outcome::Passed
+        );
+        assert!(matches!(
+            evaluate(ShouldPanic::YesWithMessage("boom"), Err("fizz".into())),
+            Outcome::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn test_block_on_runs_future() {
+        assert_eq!(block_on(async { 40 + 2 }), 42);
+    }
+
+    #[test]
+    fn test_run_test_reports_ignored_without_running() {
+        static RAN: AtomicUsize = AtomicUsize::new(0);
+        let mut test = descriptor("test_logout", true);
+        test.ignore_reason = Some("needs staging");
+        test.run = Some(|| {
+            RAN.fetch_add(1, Ordering::SeqCst);
+            panic!("broken")
+        });
+        assert_eq!(
+            run_test(&test, RunIgnored::No),
+            Outcome::Ignored(Some("needs staging"))
+        );
+        assert_eq!(RAN.load(Ordering::SeqCst), 0);
+
+        let Outcome::Failed(message) = run_test(&test, RunIgnored::Include) else {
+            panic!("ignored test should run with --include-ignored");
+        };
+        assert_eq!(RAN.load(Ordering::SeqCst), 1);
+        assert!(message.starts_with("thread 'auth::test_logout' panicked at "));
+        assert!(message.contains("\nbroken"));
+    }
+
+    #[test]
+    fn test_run_test_captures_expected_panics() {
+        let mut test = descriptor("test_rejects_overdraft", false);
+        test.should_panic = ShouldPanic::YesWithMessage("overdraft");
+        test.run = Some(|| panic!("overdraft refused"));
+        assert_eq!(run_test(&test, RunIgnored::No), Outcome::Passed);
+        assert!(lock_captured_panics().is_empty());
+
+        test.should_panic = ShouldPanic::YesWithMessage("timeout");
+        let Outcome::Failed(message) = run_test(&test, RunIgnored::No) else {
+            panic!("unexpected panic message should fail");
+        };
+        assert!(message.contains("overdraft refused"));
+        assert!(message.ends_with("expected substring: \"timeout\""));
+    }
+}
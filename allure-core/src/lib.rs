@@ -7,6 +7,14 @@
 //! - Enum types for status, stage, severity, and other classifications
 //! - A file writer for outputting results to the `allure-results` directory
 //! - Runtime context management for tracking test execution state
+//! - Configuration from code, `ALLURE_*` environment variables or `allure.toml`
+//! - Soft assertions that report every failure instead of stopping at the first
+//! - Time limits that write hung tests as broken instead of blocking the run
+//! - A registry of `#[allure_test]` functions and a harness for `harness = false` targets
+//! - Test plans (`ALLURE_TESTPLAN_PATH`) that select which tests run
+//! - Label-expression filters (`ALLURE_FILTER`) over labels and statuses
+//! - Listeners that observe and adjust results as they are built and written
+//! - Redaction of secrets in results and attachments before they are written
 //!
 //! # Example
 //!
@@ -30,28 +38,52 @@
 //! });
 //! ```
 
+pub mod config;
 pub mod enums;
 pub mod error;
+pub mod filter;
+pub mod harness;
+pub mod listener;
 pub mod model;
+pub mod redact;
+pub mod registry;
+pub mod run;
 pub mod runtime;
+pub mod soft;
+pub mod testplan;
+pub mod timeout;
 pub mod writer;
 
 // Re-exports for convenience
 pub use enums::{ContentType, LabelName, LinkType, ParameterMode, Severity, Stage, Status};
-pub use error::{AllureError, AllureResult};
+pub use error::{AllureError, AllureResult, ErrorPolicy};
+pub use listener::AllureListener;
 pub use model::{
     Attachment, Category, FixtureResult, Label, Link, Parameter, StatusDetails, StepResult,
     TestResult, TestResultContainer,
 };
 pub use runtime::{
-    allure_id, attach_binary, attach_file, attach_json, attach_text, configure, description,
-    description_html, display_name, epic, feature, flaky, issue, known_issue, label, link,
-    log_step, muted, owner, parameter, parameter_excluded, parameter_hidden, parameter_masked,
-    parent_suite, run_test, severity, skip, step, story, sub_suite, suite, tag, tags, test_case_id,
-    title, tms, with_async_context, with_context, with_test_context, AllureConfig,
-    AllureConfigBuilder, TestContext,
+    after, after_all, allure_id, attach_binary, attach_file, attach_json, attach_reader,
+    attach_text, attachment_writer, before, before_all, before_async, configure, current_labels,
+    current_step_path, current_test, description, description_html, display_name, end_run, epic,
+    feature, flaky, has_failures, issue, known_issue, label, link, log_step, muted, on_failure,
+    on_step_failure, owner, parameter, parameter_excluded, parameter_hidden, parameter_masked,
+    parent_suite, run_test, severity, skip, skip_if, start_step, step, story, sub_suite, subtest,
+    suite, tag, tags, teardown, test_case_id, title, tms, try_attach_binary, try_attach_file,
+    try_attach_json, try_attach_text, try_label, try_parameter, try_step, try_with_context,
+    typed_link, with_async_context, with_context, with_test_context, AllureConfig,
+    AllureConfigBuilder, AttachmentContent, AttachmentWriter, FixtureGuard, LabelProvider, Retry,
+    SkipSignal, StepGuard, TestContext, TestInfo,
 };
-pub use writer::{compute_history_id, generate_uuid, AllureWriter, DEFAULT_RESULTS_DIR};
+pub use soft::{soft, SoftAssertions};
+pub use timeout::{run_with_timeout, with_timeout_async};
+pub use writer::{
+    compute_history_id, generate_uuid, AllureWriter, AttachmentStream, DEFAULT_RESULTS_DIR,
+};
+
+// Re-export inventory for test registration in macros
+#[doc(hidden)]
+pub use inventory;
 
 // Re-export futures for async panic handling in macros
 #[cfg(feature = "async")]
@@ -105,7 +137,11 @@ pub mod bdd {
 /// Attachment helper module with convenience functions.
 pub mod attachment {
     use crate::enums::ContentType;
-    use crate::runtime::{attach_binary, attach_file as attach_file_fn, attach_json, attach_text};
+    use crate::error::AllureResult;
+    use crate::runtime::{
+        attach_binary, attach_file as attach_file_fn, attach_json, attach_reader, attach_text,
+        attachment_writer, AttachmentWriter,
+    };
 
     /// Attaches text content.
     pub fn text(name: impl Into<String>, content: impl AsRef<str>) {
@@ -160,6 +196,35 @@ pub mod attachment {
     pub fn image_diff(name: impl Into<String>, content: &[u8]) {
         attach_binary(name, content, ContentType::ImageDiff);
     }
+
+    /// Starts an attachment written in chunks, added to the current step
+    /// when the returned writer is finished or dropped.
+    pub fn writer(name: impl Into<String>, content_type: ContentType) -> AttachmentWriter {
+        attachment_writer(name, content_type)
+    }
+
+    /// Attaches everything read from `reader`, without holding it in memory.
+    pub fn from_reader(
+        name: impl Into<String>,
+        reader: impl std::io::Read,
+        content_type: ContentType,
+    ) -> AllureResult<u64> {
+        attach_reader(name, reader, content_type)
+    }
+
+    /// Attaches everything read from `reader`, such as a child process's
+    /// stdout, while also copying it to `out`.
+    pub fn tee(
+        name: impl Into<String>,
+        mut reader: impl std::io::Read,
+        content_type: ContentType,
+        out: impl std::io::Write + Send + 'static,
+    ) -> AllureResult<u64> {
+        let mut writer = attachment_writer(name, content_type).tee(out);
+        let copied = std::io::copy(&mut reader, &mut writer)?;
+        writer.finish()?;
+        Ok(copied)
+    }
 }
 
 /// Environment info builder for generating `environment.properties`.
@@ -0,0 +1,141 @@
+//! Listeners observing the lifecycle of test results.
+//!
+//! A listener registered with
+//! [`configure().listener(...)`](crate::runtime::AllureConfigBuilder::listener)
+//! is called as tests start, steps start and stop, attachments are added and
+//! results are written. It can add labels, rename tests or send results to
+//! another system without wrapping every test:
+//!
+//! ```
+//! use allure_core::listener::AllureListener;
+//! use allure_core::model::{Label, TestResult};
+//!
+//! struct TeamLabel;
+//!
+//! impl AllureListener for TeamLabel {
+//!     fn before_test_write(&self, result: &mut TestResult) {
+//!         result.labels.push(Label::new("team", "payments"));
+//!     }
+//! }
+//!
+//! let builder = allure_core::configure().listener(TeamLabel);
+//! # drop(builder);
+//! ```
+
+use std::sync::Arc;
+
+use crate::model::{Attachment, StepResult, TestResult};
+
+/// Callbacks for the lifecycle of test results.
+///
+/// Every method does nothing by default. Listeners are called in the order
+/// they were registered, on the thread running the test.
+pub trait AllureListener: Send + Sync {
+    /// Called when a test starts, with its configured metadata in place.
+    ///
+    /// Tests excluded by the filter only start if they are reported as
+    /// skipped, so every start is followed by
+    /// [`on_test_stop`](Self::on_test_stop).
+    fn on_test_start(&self, _result: &mut TestResult) {}
+
+    /// Called when a step starts.
+    fn on_step_start(&self, _step: &StepResult) {}
+
+    /// Called when a step finishes, with its status and stop time set.
+    fn on_step_stop(&self, _step: &StepResult) {}
+
+    /// Called when an attachment is added to a test or step.
+    fn on_attachment(&self, _attachment: &Attachment) {}
+
+    /// Called with the finished result right before it is written.
+    fn before_test_write(&self, _result: &mut TestResult) {}
+
+    /// Called after the result has been written.
+    fn on_test_stop(&self, _result: &TestResult) {}
+
+    /// Called once when the run ends, see [`crate::runtime::end_run`].
+    fn on_run_end(&self) {}
+}
+
+/// The listeners registered for a run.
+///
+/// Two lists are equal if they hold the same listener instances.
+#[derive(Clone, Default)]
+pub struct Listeners(Vec<Arc<dyn AllureListener>>);
+
+impl Listeners {
+    /// Adds a listener after the existing ones.
+    pub fn push(&mut self, listener: Arc<dyn AllureListener>) {
+        self.0.push(listener);
+    }
+
+    /// Adds the listeners of `other` after the existing ones.
+    pub fn extend(&mut self, other: &Listeners) {
+        self.0.extend(other.0.iter().cloned());
+    }
+
+    /// Returns whether no listener is registered.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Calls `f` with each listener in registration order.
+    pub fn notify(&self, mut f: impl FnMut(&dyn AllureListener)) {
+        for listener in &self.0 {
+            f(listener.as_ref());
+        }
+    }
+}
+
+impl std::fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Listeners({})", self.0.len())
+    }
+}
+
+impl PartialEq for Listeners {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&other.0)
+                .all(|(own, theirs)| Arc::ptr_eq(own, theirs))
+    }
+}
+
+impl Eq for Listeners {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl AllureListener for Recorder {
+        fn on_step_start(&self, step: &StepResult) {
+            self.0.lock().unwrap().push(step.name.clone());
+        }
+    }
+
+    #[test]
+    fn test_listeners_notify_in_order_and_compare_by_instance() {
+        let first = Arc::new(Recorder::default());
+        let second = Arc::new(Recorder::default());
+        let mut listeners = Listeners::default();
+        listeners.push(first.clone());
+        listeners.push(second.clone());
+
+        listeners.notify(|listener| listener.on_step_start(&StepResult::new("step")));
+        assert_eq!(*first.0.lock().unwrap(), ["step"]);
+        assert_eq!(*second.0.lock().unwrap(), ["step"]);
+
+        assert_eq!(listeners, listeners.clone());
+        let mut other = Listeners::default();
+        other.push(Arc::new(Recorder::default()));
+        other.push(second);
+        assert_ne!(listeners, other);
+    }
+}
@@ -711,6 +711,23 @@ impl FixtureResult {
     }
 }
 
+impl From<StepResult> for FixtureResult {
+    /// Converts a finished step into a fixture with the same contents.
+    fn from(step: StepResult) -> Self {
+        Self {
+            name: step.name,
+            status: step.status,
+            status_details: step.status_details,
+            stage: step.stage,
+            steps: step.steps,
+            attachments: step.attachments,
+            parameters: step.parameters,
+            start: step.start,
+            stop: step.stop,
+        }
+    }
+}
+
 /// Category definition for defect classification.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
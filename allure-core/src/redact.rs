@@ -0,0 +1,290 @@
+//! Redaction of secrets before results are written.
+//!
+//! A [`Redactor`] replaces literal secrets and regex matches with
+//! [`REDACTED`]. The writer runs it over parameter values, status messages
+//! and traces, step names, and text and JSON attachments, including copied
+//! text files. Masked parameters
+//! are always written as [`REDACTED`], as their mode is only a display hint.
+//!
+//! Secrets come from [`configure()`](crate::configure):
+//!
+//! ```
+//! use allure_core::configure;
+//!
+//! let builder = configure()
+//!     .redact_pattern(r"Bearer [A-Za-z0-9._-]+")
+//!     .redact_secret("hunter2")
+//!     .redact_env("API_TOKEN");
+//! # drop(builder);
+//! ```
+//!
+//! or from `ALLURE_REDACT_ENV` (comma-separated variable names) and the
+//! `redact_patterns` and `redact_env` keys of `allure.toml`. Variables are
+//! read when the configuration is resolved; unset or empty ones are skipped.
+
+use std::borrow::Cow;
+
+use regex::Regex;
+
+use crate::enums::ParameterMode;
+use crate::model::{FixtureResult, Parameter, StatusDetails, StepResult, TestResult};
+
+/// Placeholder written in place of a secret.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Environment variable listing variables whose values are secrets.
+pub const REDACT_ENV_ENV: &str = "ALLURE_REDACT_ENV";
+
+/// Longest pattern match kept whole when a long line is streamed in pieces.
+const MAX_STREAMED_MATCH: usize = 1024;
+
+/// Replaces secrets in text with [`REDACTED`].
+///
+/// Two redactors are equal if they have the same secrets and patterns. Its
+/// `Debug` output only counts them, so the secrets never reach logs.
+#[derive(Clone, Default)]
+pub struct Redactor {
+    secrets: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Creates a redactor that leaves text unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a literal secret. Empty secrets are ignored.
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        let secret = secret.into();
+        if !secret.is_empty() && !self.secrets.contains(&secret) {
+            self.secrets.push(secret);
+        }
+        self
+    }
+
+    /// Adds a regex whose matches are secrets.
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        if !self.patterns.iter().any(|own| own.as_str() == pattern) {
+            self.patterns.push(Regex::new(pattern)?);
+        }
+        Ok(self)
+    }
+
+    /// Returns whether the redactor has no secrets or patterns.
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Longest text a secret can span, counting pattern matches as at most
+    /// [`MAX_STREAMED_MATCH`] bytes.
+    pub(crate) fn max_match_len(&self) -> usize {
+        let longest = self.secrets.iter().map(String::len).max().unwrap_or(0);
+        if self.patterns.is_empty() {
+            longest
+        } else {
+            longest.max(MAX_STREAMED_MATCH)
+        }
+    }
+
+    /// Moves `at` back until it no longer falls inside a secret or pattern
+    /// match in `text`, so that both sides can be redacted on their own.
+    pub(crate) fn split_point(&self, text: &str, mut at: usize) -> usize {
+        loop {
+            let secrets = self.secrets.iter().flat_map(|secret| {
+                text.match_indices(secret.as_str())
+                    .map(|(start, found)| (start, start + found.len()))
+            });
+            let matches = self.patterns.iter().flat_map(|pattern| {
+                pattern
+                    .find_iter(text)
+                    .map(|found| (found.start(), found.end()))
+            });
+            let straddling = secrets
+                .chain(matches)
+                .filter(|&(start, end)| start < at && at < end)
+                .map(|(start, _)| start)
+                .min();
+            match straddling {
+                Some(start) => at = start,
+                None => return at,
+            }
+        }
+    }
+
+    /// Returns `text` with every secret replaced.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        // Longer secrets first, so one containing another is fully replaced
+        let mut secrets: Vec<&String> = self.secrets.iter().collect();
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        for secret in secrets {
+            if text.contains(secret.as_str()) {
+                text = Cow::Owned(text.replace(secret.as_str(), REDACTED));
+            }
+        }
+        for pattern in &self.patterns {
+            if let Cow::Owned(replaced) = pattern.replace_all(&text, REDACTED) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+
+    /// Redacts a string in place.
+    pub fn redact_in_place(&self, text: &mut String) {
+        if let Cow::Owned(redacted) = self.redact(text) {
+            *text = redacted;
+        }
+    }
+
+    /// Redacts the string values of a JSON document, keeping it valid JSON.
+    pub fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => self.redact_in_place(text),
+            serde_json::Value::Array(items) => {
+                items.iter_mut().for_each(|item| self.redact_json(item))
+            }
+            serde_json::Value::Object(fields) => fields
+                .values_mut()
+                .for_each(|field| self.redact_json(field)),
+            _ => {}
+        }
+    }
+
+    /// Redacts a test result and masks its masked parameters.
+    pub fn redact_result(&self, result: &mut TestResult) {
+        self.redact_parameters(&mut result.parameters);
+        self.redact_details(result.status_details.as_mut());
+        self.redact_steps(&mut result.steps);
+    }
+
+    /// Redacts a fixture and masks its masked parameters.
+    pub fn redact_fixture(&self, fixture: &mut FixtureResult) {
+        self.redact_in_place(&mut fixture.name);
+        self.redact_parameters(&mut fixture.parameters);
+        self.redact_details(fixture.status_details.as_mut());
+        self.redact_steps(&mut fixture.steps);
+    }
+
+    fn redact_steps(&self, steps: &mut [StepResult]) {
+        for step in steps {
+            self.redact_in_place(&mut step.name);
+            self.redact_parameters(&mut step.parameters);
+            self.redact_details(step.status_details.as_mut());
+            self.redact_steps(&mut step.steps);
+        }
+    }
+
+    fn redact_parameters(&self, parameters: &mut [Parameter]) {
+        for parameter in parameters {
+            if parameter.mode == Some(ParameterMode::Masked) {
+                parameter.value = REDACTED.to_string();
+            } else {
+                self.redact_in_place(&mut parameter.value);
+            }
+        }
+    }
+
+    fn redact_details(&self, details: Option<&mut StatusDetails>) {
+        if let Some(details) = details {
+            if let Some(message) = &mut details.message {
+                self.redact_in_place(message);
+            }
+            if let Some(trace) = &mut details.trace {
+                self.redact_in_place(trace);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Redactor")
+            .field("secrets", &self.secrets.len())
+            .field("patterns", &self.patterns.len())
+            .finish()
+    }
+}
+
+impl PartialEq for Redactor {
+    fn eq(&self, other: &Self) -> bool {
+        self.secrets == other.secrets
+            && self.patterns.len() == other.patterns.len()
+            && self
+                .patterns
+                .iter()
+                .zip(&other.patterns)
+                .all(|(own, theirs)| own.as_str() == theirs.as_str())
+    }
+}
+
+impl Eq for Redactor {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::Status;
+
+    fn redactor() -> Redactor {
+        Redactor::new()
+            .secret("hunter2")
+            .secret("hunter")
+            .pattern(r"Bearer [A-Za-z0-9._-]+")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_redacts_secrets_and_pattern_matches() {
+        let redactor = redactor();
+        assert_eq!(
+            redactor.redact("password hunter2, header Bearer abc.def"),
+            "password [REDACTED], header [REDACTED]"
+        );
+        assert!(matches!(redactor.redact("nothing here"), Cow::Borrowed(_)));
+        assert!(Redactor::new().pattern("(unclosed").is_err());
+        assert_eq!(redactor, redactor.clone());
+        assert_ne!(redactor, Redactor::new().secret("hunter2"));
+        assert_eq!(
+            format!("{:?}", redactor),
+            "Redactor { secrets: 2, patterns: 1 }"
+        );
+    }
+
+    #[test]
+    fn test_redacts_results_and_masks_parameters() {
+        let redactor = redactor();
+        let mut result = TestResult::new("uuid".to_string(), "login".to_string());
+        result
+            .parameters
+            .push(Parameter::new("user", "admin:hunter2"));
+        result
+            .parameters
+            .push(Parameter::masked("password", "s3cret"));
+        let mut step = StepResult::new("Send Bearer tok.en");
+        step.steps.push(StepResult::new("Use hunter2"));
+        result.steps.push(step);
+        result.status = Status::Failed;
+        result.status_details = Some(StatusDetails {
+            message: Some("login failed for hunter2".to_string()),
+            trace: Some("at Bearer tok.en".to_string()),
+            ..Default::default()
+        });
+
+        redactor.redact_result(&mut result);
+        assert_eq!(result.parameters[0].value, "admin:[REDACTED]");
+        assert_eq!(result.parameters[1].value, REDACTED);
+        assert_eq!(result.steps[0].name, "Send [REDACTED]");
+        assert_eq!(result.steps[0].steps[0].name, "Use [REDACTED]");
+        let details = result.status_details.unwrap();
+        assert_eq!(details.message.unwrap(), "login failed for [REDACTED]");
+        assert_eq!(details.trace.unwrap(), "at [REDACTED]");
+
+        let mut json = serde_json::json!({"token": "hunter2", "list": ["Bearer x"], "n": 1});
+        redactor.redact_json(&mut json);
+        assert_eq!(
+            json,
+            serde_json::json!({"token": REDACTED, "list": [REDACTED], "n": 1})
+        );
+    }
+}
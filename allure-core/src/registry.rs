@@ -0,0 +1,148 @@
+//! Static registry of the tests defined with `#[allure_test]`.
+//!
+//! Each test submits a [`TestDescriptor`] that is collected at startup with
+//! [`inventory`], so a binary can discover its tests and their metadata
+//! without running them. The [`harness`](crate::harness) uses it to run
+//! `harness = false` targets.
+//!
+//! Only metadata given as literals in attributes before `#[allure_test]`
+//! is known statically; calls made from the test body are not.
+//!
+//! ```no_run
+//! for test in allure_core::registry::tests() {
+//!     let features: Vec<_> = test.labels_named("feature").collect();
+//!     println!("{} ({}:{}) {:?}", test.display_name, test.file, test.line, features);
+//! }
+//! ```
+
+use crate::enums::LinkType;
+
+/// How a test expects to panic, mirroring `#[should_panic]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShouldPanic {
+    /// The test must not panic.
+    No,
+    /// The test must panic.
+    Yes,
+    /// The test must panic with a message containing the given text.
+    YesWithMessage(&'static str),
+}
+
+/// A link declared with `#[allure_issue]`, `#[allure_tms]` or `#[allure_link]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkDescriptor {
+    /// Kind of link.
+    pub link_type: LinkType,
+    /// Target URL.
+    pub url: &'static str,
+    /// Optional display name.
+    pub name: Option<&'static str>,
+}
+
+/// A test registered by `#[allure_test]`.
+#[derive(Debug)]
+pub struct TestDescriptor {
+    /// Name of the test function.
+    pub name: &'static str,
+    /// `module_path!()` of the module defining the test.
+    pub module_path: &'static str,
+    /// Name shown in the report: the `#[allure_title]`, the name given to
+    /// `#[allure_test("...")]`, or the function name.
+    pub display_name: &'static str,
+    /// Labels as `(name, value)` pairs using Allure label names, such as
+    /// `("parentSuite", "API")` or `("severity", "critical")`.
+    pub labels: &'static [(&'static str, &'static str)],
+    /// Links in declaration order.
+    pub links: &'static [LinkDescriptor],
+    /// Allure test case ID (`AS_ID`) from `#[allure_id]`.
+    pub allure_id: Option<&'static str>,
+    /// Source file of the test.
+    pub file: &'static str,
+    /// Line of the test function in `file`.
+    pub line: u32,
+    /// Whether the test is marked `#[ignore]`.
+    pub ignore: bool,
+    /// Reason given with `#[ignore = "..."]`.
+    pub ignore_reason: Option<&'static str>,
+    /// Panic expectation from `#[should_panic]`.
+    pub should_panic: ShouldPanic,
+    /// Runs the test, returning the error of a failing `Result` test.
+    ///
+    /// `None` for tests that only a runtime attribute such as
+    /// `#[tokio::test]` can run; they are listed but not run by the harness.
+    pub run: Option<fn() -> Result<(), String>>,
+}
+
+impl TestDescriptor {
+    /// Full name of the test, as written to Allure results.
+    pub fn full_name(&self) -> String {
+        format!("{}::{}", self.module_path, self.name)
+    }
+
+    /// Name of the test as libtest prints it: the path without the crate name.
+    pub fn test_name(&self) -> String {
+        match self.module_path.split_once("::") {
+            Some((_, module)) => format!("{}::{}", module, self.name),
+            None => self.name.to_string(),
+        }
+    }
+
+    /// Values of the labels with the given Allure name.
+    pub fn labels_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'static str> + 'a {
+        self.labels
+            .iter()
+            .filter(move |(label, _)| *label == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+inventory::collect!(TestDescriptor);
+
+/// Returns every registered test, sorted by full name.
+pub fn tests() -> Vec<&'static TestDescriptor> {
+    let mut tests: Vec<_> = inventory::iter::<TestDescriptor>.into_iter().collect();
+    tests.sort_by_key(|test| test.full_name());
+    tests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(module_path: &'static str) -> TestDescriptor {
+        TestDescriptor {
+            name: "test_login",
+            module_path,
+            display_name: "test_login",
+            labels: &[("feature", "Login"), ("tag", "smoke"), ("tag", "api")],
+            links: &[],
+            allure_id: None,
+            file: "tests/api_tests.rs",
+            line: 1,
+            ignore: false,
+            ignore_reason: None,
+            should_panic: ShouldPanic::No,
+            run: None,
+        }
+    }
+
+    #[test]
+    fn test_names_strip_crate_for_libtest() {
+        let nested = descriptor("api_tests::auth");
+        assert_eq!(nested.full_name(), "api_tests::auth::test_login");
+        assert_eq!(nested.test_name(), "auth::test_login");
+
+        let root = descriptor("api_tests");
+        assert_eq!(root.test_name(), "test_login");
+    }
+
+    #[test]
+    fn test_labels_named_keeps_declaration_order() {
+        let test = descriptor("api_tests");
+        assert_eq!(
+            test.labels_named("tag").collect::<Vec<_>>(),
+            ["smoke", "api"]
+        );
+        assert_eq!(test.labels_named("epic").count(), 0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Run-scoped initialization of the results directory.
+//!
+//! A single `cargo test` starts one process per test binary, and
+//! cargo-nextest one per test. Each of them initializes the runtime, so
+//! cleaning the results directory on every init would wipe the results of
+//! the processes that ran before. Instead, processes of one run share a run
+//! ID and the directory is cleaned once per run: the first process takes a
+//! lock file in the directory, cleans it and records the run ID in a marker
+//! file; the others find the marker and leave the directory alone.
+//!
+//! The run ID is `ALLURE_RUN_ID` if set, then `NEXTEST_RUN_ID`, then the ID
+//! and start time of the cargo process for binaries started by `cargo test`,
+//! so that each cargo invocation is a run of its own even when the OS reuses
+//! the PID. A binary started directly, such as from a shell, is a run of its
+//! own; set `ALLURE_RUN_ID` to share a run between such binaries.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::writer::AllureWriter;
+
+/// Environment variable holding the ID shared by the processes of one run.
+pub const RUN_ID_ENV: &str = "ALLURE_RUN_ID";
+
+/// File recording the run that last cleaned the results directory.
+pub(crate) const MARKER_FILE: &str = ".allure-run";
+/// Lock file held while the results directory is cleaned.
+pub(crate) const LOCK_FILE: &str = ".allure-run.lock";
+
+/// How long to wait for another process to finish cleaning.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Age after which a lock left behind by a crashed process is removed.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(60);
+
+/// Returns the ID of the current run.
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| {
+        std::env::var(RUN_ID_ENV)
+            .or_else(|_| std::env::var("NEXTEST_RUN_ID"))
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(default_run_id)
+    })
+}
+
+#[cfg(unix)]
+fn default_run_id() -> String {
+    // Cargo sets `CARGO` for the binaries it runs
+    if std::env::var_os("CARGO").is_none() {
+        return format!("pid-{}", std::process::id());
+    }
+    let parent = std::os::unix::process::parent_id();
+    match process_start_time(parent) {
+        Some(started) => format!("ppid-{}-{}", parent, started),
+        None => format!("ppid-{}", parent),
+    }
+}
+
+/// Start time of a process, in clock ticks since boot.
+#[cfg(target_os = "linux")]
+fn process_start_time(pid: u32) -> Option<String> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces, so count fields after it
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19).map(str::to_string)
+}
+
+/// Start time of a process, as reported by `ps`.
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_start_time(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "lstart=", "-p", &pid.to_string()])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let started = String::from_utf8(output.stdout).ok()?;
+    let started: Vec<_> = started.split_whitespace().collect();
+    (!started.is_empty()).then(|| started.join("-"))
+}
+
+#[cfg(not(unix))]
+fn default_run_id() -> String {
+    format!("pid-{}", std::process::id())
+}
+
+/// Creates the results directory and, if `clean` is set, cleans it unless
+/// another process of this run already did. Returns whether it cleaned.
+///
+/// `all` removes every file rather than only Allure's, see
+/// [`AllureWriter::clean`].
+pub(crate) fn prepare_results_dir(dir: &Path, clean: bool, all: bool) -> io::Result<bool> {
+    prepare_for_run(dir, clean, all, run_id())
+}
+
+fn prepare_for_run(dir: &Path, clean: bool, all: bool, run_id: &str) -> io::Result<bool> {
+    fs::create_dir_all(dir)?;
+    if !clean {
+        return Ok(false);
+    }
+
+    crate::writer::check_cleanable(dir)?;
+    let _lock = RunLock::acquire(dir)?;
+    let marker = dir.join(MARKER_FILE);
+    if fs::read_to_string(&marker).is_ok_and(|id| id.trim() == run_id) {
+        return Ok(false);
+    }
+    AllureWriter::with_results_dir(dir).clean(all)?;
+    fs::write(&marker, run_id)?;
+    Ok(true)
+}
+
+/// A lock file created exclusively, removed when dropped.
+struct RunLock {
+    path: PathBuf,
+}
+
+impl RunLock {
+    fn acquire(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(LOCK_FILE);
+        let started = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    let _ = writeln!(file, "{}", std::process::id());
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if started.elapsed() > LOCK_TIMEOUT {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("timed out waiting for {}", path.display()),
+                        ));
+                    }
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_LOCK_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_dir_is_cleaned_once_per_run() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("allure-results");
+        let result = dir.join("old-result.json");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&result, "{}").unwrap();
+        assert!(prepare_for_run(&dir, true, false, "run-1").unwrap());
+        assert!(!result.exists());
+
+        // A later process of the same run keeps what earlier ones wrote
+        fs::write(&result, "{}").unwrap();
+        assert!(!prepare_for_run(&dir, true, false, "run-1").unwrap());
+        assert!(result.exists());
+
+        assert!(prepare_for_run(&dir, true, false, "run-2").unwrap());
+        assert!(!result.exists());
+        assert_eq!(fs::read_to_string(dir.join(MARKER_FILE)).unwrap(), "run-2");
+        assert!(!dir.join(LOCK_FILE).exists());
+
+        fs::write(&result, "{}").unwrap();
+        assert!(!prepare_for_run(&dir, false, false, "run-3").unwrap());
+        assert!(result.exists());
+    }
+
+    #[test]
+    fn test_concurrent_processes_clean_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+
+        let cleaned = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| prepare_for_run(&dir, true, false, "shared").unwrap()))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .filter(|cleaned| *cleaned)
+                .count()
+        });
+        assert_eq!(cleaned, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_default_run_id_includes_parent_start_time() {
+        let started = process_start_time(std::process::id()).unwrap();
+        assert_eq!(process_start_time(std::process::id()).unwrap(), started);
+
+        let parent = std::os::unix::process::parent_id();
+        assert!(default_run_id().starts_with(&format!("ppid-{}-", parent)));
+    }
+
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let temp = tempfile::tempdir().unwrap();
+        let lock = temp.path().join(LOCK_FILE);
+        fs::write(&lock, "12345").unwrap();
+        let old = std::time::SystemTime::now() - STALE_LOCK_AGE * 2;
+        fs::File::options()
+            .write(true)
+            .open(&lock)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        assert!(prepare_for_run(temp.path(), true, false, "after-crash").unwrap());
+        assert!(!lock.exists());
+    }
+}
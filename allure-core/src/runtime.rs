@@ -5,19 +5,28 @@
 
 use std::backtrace::Backtrace;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::OnceLock;
-#[cfg(feature = "tokio")]
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
 
+/// A test context shared between threads (task-local handles and watchdogs).
+pub(crate) type SharedContext = Arc<Mutex<Option<TestContext>>>;
 #[cfg(feature = "tokio")]
-type SharedAsyncContext = Arc<Mutex<Option<TestContext>>>;
+type SharedAsyncContext = SharedContext;
 #[cfg(feature = "tokio")]
 type GlobalAsyncContext = Mutex<Option<SharedAsyncContext>>;
 
+use crate::config::ConfigLayer;
 use crate::enums::{ContentType, LabelName, LinkType, Severity, Status};
-use crate::model::{Attachment, Label, Parameter, StepResult, TestResult, TestResultContainer};
-use crate::writer::{compute_history_id, generate_uuid, AllureWriter};
+use crate::error::{AllureError, AllureResult, ErrorPolicy};
+use crate::listener::{AllureListener, Listeners};
+use crate::model::{
+    Attachment, FixtureResult, Label, Link, Parameter, StepResult, TestResult, TestResultContainer,
+};
+use crate::redact::Redactor;
+use crate::writer::{compute_history_id, generate_uuid, AllureWriter, AttachmentStream};
 
 /// Global configuration for the Allure runtime.
 static CONFIG: OnceLock<AllureConfig> = OnceLock::new();
@@ -34,12 +43,108 @@ fn global_async_context() -> &'static GlobalAsyncContext {
 }
 
 /// Configuration for the Allure runtime.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AllureConfig {
     /// Directory where results are written.
     pub results_dir: String,
     /// Whether to clean the results directory on init.
     pub clean_results: bool,
+    /// Whether cleaning removes everything in the results directory rather
+    /// than only the files Allure writes.
+    pub clean_all: bool,
+    /// Time limit applied to every `#[allure_test]` body, if any.
+    pub default_timeout: Option<Duration>,
+    /// Label expression selecting the tests to run, see [`crate::filter`].
+    pub filter: Option<String>,
+    /// Whether tests excluded by the filter are written as skipped.
+    pub report_filtered: bool,
+    /// Labels added to every test, as `(name, value)` pairs.
+    pub labels: Vec<(String, String)>,
+    /// URL patterns per link type, with `{}` standing for the link's ID.
+    pub link_patterns: BTreeMap<String, String>,
+    /// Links added to every test.
+    pub links: Vec<Link>,
+    /// Parameters added to every test, as `(name, value)` pairs.
+    pub parameters: Vec<(String, String)>,
+    /// Computes extra labels for every test from its full name.
+    pub label_provider: Option<LabelProvider>,
+    /// Listeners called as results are built and written.
+    pub listeners: Listeners,
+    /// Secrets redacted from results and attachments, see [`crate::redact`].
+    pub redactor: Redactor,
+    /// What to do when a runtime function is called outside a test.
+    pub on_missing_context: ErrorPolicy,
+    /// What to do when a result, container or attachment cannot be written.
+    pub on_write_error: ErrorPolicy,
+    /// Whether failure messages start with the path of the step that failed,
+    /// such as `Checkout > Pay > Submit card: ...`.
+    pub step_path_in_messages: bool,
+    /// Whether the path of the step that failed is printed to stderr when a
+    /// test fails.
+    pub print_step_path: bool,
+}
+
+impl AllureConfig {
+    /// Names of the fields whose values differ from `other`'s.
+    ///
+    /// Label providers and listeners can't be compared, so they only count
+    /// as changed when one side has them and the other doesn't.
+    fn changed_fields(&self, other: &Self) -> Vec<&'static str> {
+        let Self {
+            results_dir,
+            clean_results,
+            clean_all,
+            default_timeout,
+            filter,
+            report_filtered,
+            labels,
+            link_patterns,
+            links,
+            parameters,
+            label_provider,
+            listeners,
+            redactor,
+            on_missing_context,
+            on_write_error,
+            step_path_in_messages,
+            print_step_path,
+        } = self;
+        [
+            ("results_dir", *results_dir == other.results_dir),
+            ("clean_results", *clean_results == other.clean_results),
+            ("clean_all", *clean_all == other.clean_all),
+            ("default_timeout", *default_timeout == other.default_timeout),
+            ("filter", *filter == other.filter),
+            ("report_filtered", *report_filtered == other.report_filtered),
+            ("labels", *labels == other.labels),
+            ("link_patterns", *link_patterns == other.link_patterns),
+            ("links", *links == other.links),
+            ("parameters", *parameters == other.parameters),
+            (
+                "label_provider",
+                label_provider.is_some() == other.label_provider.is_some(),
+            ),
+            (
+                "listeners",
+                listeners.is_empty() == other.listeners.is_empty(),
+            ),
+            ("redactor", *redactor == other.redactor),
+            (
+                "on_missing_context",
+                *on_missing_context == other.on_missing_context,
+            ),
+            ("on_write_error", *on_write_error == other.on_write_error),
+            (
+                "step_path_in_messages",
+                *step_path_in_messages == other.step_path_in_messages,
+            ),
+            ("print_step_path", *print_step_path == other.print_step_path),
+        ]
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(name, _)| name)
+        .collect()
+    }
 }
 
 impl Default for AllureConfig {
@@ -47,14 +152,74 @@ impl Default for AllureConfig {
         Self {
             results_dir: crate::writer::DEFAULT_RESULTS_DIR.to_string(),
             clean_results: true,
+            clean_all: false,
+            default_timeout: None,
+            filter: None,
+            report_filtered: false,
+            labels: Vec::new(),
+            link_patterns: BTreeMap::new(),
+            links: Vec::new(),
+            parameters: Vec::new(),
+            label_provider: None,
+            listeners: Listeners::default(),
+            redactor: Redactor::new(),
+            on_missing_context: ErrorPolicy::Ignore,
+            on_write_error: ErrorPolicy::WarnOnce,
+            step_path_in_messages: false,
+            print_step_path: false,
         }
     }
 }
 
+impl AllureConfig {
+    /// Returns a writer for the results directory that redacts secrets.
+    pub(crate) fn writer(&self) -> AllureWriter {
+        AllureWriter::with_results_dir(&self.results_dir).with_redactor(self.redactor.clone())
+    }
+}
+
+/// Computes labels for a test from its full name.
+///
+/// Set with [`AllureConfigBuilder::label_provider`]. Two providers are equal
+/// only if they are clones of each other.
+#[derive(Clone)]
+pub struct LabelProvider(Arc<LabelFn>);
+
+type LabelFn = dyn Fn(&str) -> Vec<(String, String)> + Send + Sync;
+
+impl LabelProvider {
+    /// Wraps a function returning `(name, value)` label pairs.
+    pub fn new(provider: impl Fn(&str) -> Vec<(String, String)> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+
+    /// Returns the labels for the test with the given full name.
+    pub fn labels(&self, full_name: &str) -> Vec<(String, String)> {
+        (self.0)(full_name)
+    }
+}
+
+impl std::fmt::Debug for LabelProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LabelProvider(..)")
+    }
+}
+
+impl PartialEq for LabelProvider {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for LabelProvider {}
+
 /// Builder for configuring the Allure runtime.
+///
+/// Settings made here take precedence over the environment and the
+/// `allure.toml` file; see [`crate::config`].
 #[derive(Debug, Default)]
 pub struct AllureConfigBuilder {
-    config: AllureConfig,
+    config: ConfigLayer,
 }
 
 impl AllureConfigBuilder {
@@ -65,21 +230,241 @@ impl AllureConfigBuilder {
 
     /// Sets the results directory.
     pub fn results_dir(mut self, path: impl Into<String>) -> Self {
-        self.config.results_dir = path.into();
+        self.config.results_dir = Some(path.into());
         self
     }
 
     /// Sets whether to clean the results directory.
+    ///
+    /// Only files written by Allure are removed; see
+    /// [`AllureWriter::clean`](crate::writer::AllureWriter::clean).
     pub fn clean_results(mut self, clean: bool) -> Self {
-        self.config.clean_results = clean;
+        self.config.clean_results = Some(clean);
+        self
+    }
+
+    /// Sets whether cleaning removes everything in the results directory,
+    /// including files Allure did not write.
+    ///
+    /// Directories that look like a project or the home directory are still
+    /// never cleaned.
+    pub fn clean_all(mut self, all: bool) -> Self {
+        self.config.clean_all = Some(all);
+        self
+    }
+
+    /// Adds a label to every test.
+    ///
+    /// Takes precedence over `ALLURE_LABEL_<name>`. A test that sets a label
+    /// of the same name replaces it.
+    pub fn label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.labels.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a link to every test, such as the CI build that ran it.
+    ///
+    /// A test that adds a link with the same name or URL replaces it.
+    pub fn link(mut self, url: impl Into<String>, name: Option<String>) -> Self {
+        self.config.links.push(Link {
+            name,
+            url: url.into(),
+            r#type: None,
+        });
+        self
+    }
+
+    /// Adds a parameter to every test.
+    ///
+    /// A test that sets a parameter of the same name replaces it.
+    pub fn parameter(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.config
+            .parameters
+            .push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Computes labels for every test from its full name, such as a
+    /// component derived from the module path.
+    ///
+    /// Provided labels replace configured labels of the same name, and a
+    /// test that sets a label of the same name replaces them in turn.
+    ///
+    /// ```
+    /// use allure_core::configure;
+    ///
+    /// let builder = configure().label_provider(|full_name| {
+    ///     let module = full_name.split("::").nth(1).unwrap_or_default();
+    ///     vec![("component".to_string(), module.to_string())]
+    /// });
+    /// # drop(builder);
+    /// ```
+    pub fn label_provider(
+        mut self,
+        provider: impl Fn(&str) -> Vec<(String, String)> + Send + Sync + 'static,
+    ) -> Self {
+        self.config.label_provider = Some(LabelProvider::new(provider));
+        self
+    }
+
+    /// Sets the URL pattern for a link type, with `{}` standing for the ID.
+    ///
+    /// Links of that type given as a bare ID, such as
+    /// `#[allure_issue("BILL-42")]` or `#[allure_link("17", type = "github")]`,
+    /// are expanded when the result is written. Takes precedence over
+    /// `ALLURE_LINK_<TYPE>_PATTERN`.
+    ///
+    /// ```
+    /// use allure_core::configure;
+    ///
+    /// let builder = configure()
+    ///     .link_pattern("issue", "https://jira.example.com/browse/{}")
+    ///     .link_pattern("github", "https://github.com/example/app/pull/{}");
+    /// # drop(builder);
+    /// ```
+    pub fn link_pattern(mut self, link_type: impl AsRef<str>, pattern: impl Into<String>) -> Self {
+        self.config
+            .link_patterns
+            .insert(link_type.as_ref().to_lowercase(), pattern.into());
+        self
+    }
+
+    /// Registers a listener called as results are built and written.
+    ///
+    /// Listeners run in registration order; see [`crate::listener`].
+    pub fn listener(mut self, listener: impl AllureListener + 'static) -> Self {
+        self.config.listeners.push(Arc::new(listener));
+        self
+    }
+
+    /// Redacts matches of a regex from results and attachments.
+    ///
+    /// An invalid regex makes [`init`](Self::init) fail.
+    pub fn redact_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.config.redact_patterns.push(pattern.into());
+        self
+    }
+
+    /// Redacts a literal secret from results and attachments.
+    pub fn redact_secret(mut self, secret: impl Into<String>) -> Self {
+        self.config.redact_secrets.push(secret.into());
+        self
+    }
+
+    /// Redacts the value of an environment variable, read at init, such as
+    /// an API token. Unset or empty variables are skipped.
+    pub fn redact_env(mut self, name: impl Into<String>) -> Self {
+        self.config.redact_env.push(name.into());
+        self
+    }
+
+    /// Sets what happens when a runtime function such as [`label`] or
+    /// [`step`] is called outside a test. Calls are ignored by default.
+    ///
+    /// The `try_*` functions, such as [`try_label`], return
+    /// [`AllureError::NoActiveContext`] instead.
+    pub fn on_missing_context(mut self, policy: ErrorPolicy) -> Self {
+        self.config.on_missing_context = Some(policy);
+        self
+    }
+
+    /// Sets what happens when a result, container or attachment cannot be
+    /// written. By default the first failure is printed.
+    pub fn on_write_error(mut self, policy: ErrorPolicy) -> Self {
+        self.config.on_write_error = Some(policy);
+        self
+    }
+
+    /// Makes runtime calls outside a test and write failures panic, so
+    /// that misuse fails the test run instead of losing results silently.
+    ///
+    /// Equivalent to setting both [`on_missing_context`](Self::on_missing_context)
+    /// and [`on_write_error`](Self::on_write_error) to [`ErrorPolicy::Panic`].
+    /// Takes precedence over `ALLURE_STRICT`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.set_strict(strict);
         self
     }
 
-    /// Initializes the Allure runtime with this configuration.
+    /// Sets whether failure messages of tests and steps start with the path
+    /// of the step that failed, such as `Checkout > Pay > Submit card: ...`.
+    pub fn step_path_in_messages(mut self, enabled: bool) -> Self {
+        self.config.step_path_in_messages = Some(enabled);
+        self
+    }
+
+    /// Sets whether the path of the step that failed is printed to stderr
+    /// when a test fails, so that `cargo test` output shows where it died.
+    pub fn print_step_path(mut self, enabled: bool) -> Self {
+        self.config.print_step_path = Some(enabled);
+        self
+    }
+
+    /// Sets a time limit for every `#[allure_test]` body.
+    ///
+    /// Tests that run longer are reported as broken and fail. A per-test
+    /// `#[allure_timeout]` overrides this value. Sync tests whose return
+    /// value isn't `Send` run inline and are not covered.
+    pub fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.config.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Runs only the tests matching a label expression such as
+    /// `tag:smoke && severity >= critical`.
+    ///
+    /// Takes precedence over the `ALLURE_FILTER` environment variable. See
+    /// [`crate::filter`] for the syntax.
+    pub fn filter(mut self, expression: impl Into<String>) -> Self {
+        self.config.filter = Some(expression.into());
+        self
+    }
+
+    /// Sets whether tests excluded by the filter are written as skipped,
+    /// with the filter as the reason. By default they leave no result.
+    pub fn report_filtered(mut self, report: bool) -> Self {
+        self.config.report_filtered = Some(report);
+        self
+    }
+
+    /// Initializes the Allure runtime with this configuration, completed
+    /// from the environment and the configuration file.
+    ///
+    /// Only the first call in a process takes effect; later calls with
+    /// different settings print a warning. `clean_results` cleans the
+    /// directory once per run, shared by every test process of the run
+    /// (see [`crate::run`]).
     pub fn init(self) -> std::io::Result<()> {
-        let writer = AllureWriter::with_results_dir(&self.config.results_dir);
-        writer.init(self.config.clean_results)?;
-        CONFIG.set(self.config).ok();
+        for pattern in &self.config.redact_patterns {
+            if let Err(e) = Redactor::new().pattern(pattern) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid redaction pattern {:?}: {}", pattern, e),
+                ));
+            }
+        }
+        let config = crate::config::resolve(&self.config);
+        let mut first = false;
+        let active = CONFIG.get_or_init(|| {
+            first = true;
+            config.clone()
+        });
+        if !first {
+            let changed = active.changed_fields(&config);
+            if !changed.is_empty() {
+                eprintln!(
+                    "allure: ignoring configure().init() with different settings \
+                     for {}; the runtime was already initialized",
+                    changed.join(", ")
+                );
+            }
+            return Ok(());
+        }
+        crate::run::prepare_results_dir(
+            std::path::Path::new(&active.results_dir),
+            active.clean_results,
+            active.clean_all,
+        )?;
         Ok(())
     }
 }
@@ -89,9 +474,88 @@ pub fn configure() -> AllureConfigBuilder {
     AllureConfigBuilder::new()
 }
 
-/// Gets the current configuration or the default.
+/// Gets the current configuration.
+///
+/// Without a call to [`configure()`], the configuration comes from the
+/// environment and the configuration file. The results directory is then
+/// only cleaned if one of them sets `clean_results` explicitly.
 pub fn get_config() -> AllureConfig {
-    CONFIG.get().cloned().unwrap_or_default()
+    active_config().clone()
+}
+
+fn active_config() -> &'static AllureConfig {
+    CONFIG.get().unwrap_or_else(|| ambient_config())
+}
+
+/// Calls `f` with each registered listener.
+fn notify_listeners(f: impl FnMut(&dyn AllureListener)) {
+    let listeners = &active_config().listeners;
+    if !listeners.is_empty() {
+        listeners.notify(f);
+    }
+}
+
+/// Starts the current test if it was created with
+/// [`TestContext::unstarted`]. Listeners are called outside the context, so
+/// they may call runtime functions themselves.
+pub(crate) fn start_current_test() {
+    let result = with_context(|ctx| {
+        if ctx.started {
+            return None;
+        }
+        ctx.started = true;
+        (!active_config().listeners.is_empty()).then(|| ctx.result.clone())
+    });
+    if let Some(Some(mut result)) = result {
+        notify_listeners(|listener| listener.on_test_start(&mut result));
+        with_context(|ctx| ctx.result = result);
+    }
+}
+
+/// Ends the run, calling [`AllureListener::on_run_end`] on every listener
+/// and writing the run-level container of [`before_all`] fixtures.
+///
+/// The harness calls this after the last test. With libtest, which has no
+/// hook at the end of a run, call it from a `#[ctor::dtor]` function. Only
+/// the first call has an effect.
+pub fn end_run() {
+    static ENDED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+    flush_run_fixtures();
+    if !ENDED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        notify_listeners(|listener| listener.on_run_end());
+    }
+}
+
+/// The configuration used when the runtime was not initialized from code.
+fn ambient_config() -> &'static AllureConfig {
+    static AMBIENT: OnceLock<AllureConfig> = OnceLock::new();
+    AMBIENT.get_or_init(|| {
+        let env = ConfigLayer::from_env();
+        let file = ConfigLayer::from_file();
+        let clean = env.clean_results.or(file.clean_results).unwrap_or(false);
+        let config = crate::config::resolve_layers(&[&env, &file]);
+        let dir = std::path::Path::new(&config.results_dir);
+        if let Err(e) = crate::run::prepare_results_dir(dir, clean, config.clean_all) {
+            eprintln!("allure: failed to prepare {}: {}", config.results_dir, e);
+        }
+        config
+    })
+}
+
+/// Expands links given as a bare ID with the pattern for their link type.
+///
+/// A pattern is a URL with `{}` standing for the ID; links whose URL already
+/// has a scheme are left alone.
+pub(crate) fn expand_links(links: &mut [Link], patterns: &BTreeMap<String, String>) {
+    for link in links {
+        if link.url.contains("://") {
+            continue;
+        }
+        let link_type = link.r#type.unwrap_or_default();
+        if let Some(pattern) = patterns.get(&link_type.as_str().to_lowercase()) {
+            link.url = pattern.replace("{}", &link.url);
+        }
+    }
 }
 
 /// Test context holding the current test result and step stack.
@@ -103,11 +567,141 @@ pub struct TestContext {
     pub step_stack: Vec<StepResult>,
     /// The writer for this context.
     pub writer: AllureWriter,
+    /// UUIDs of sub-test results started from this test.
+    pub subtest_uuids: Vec<String>,
+    /// Names of sub-tests that did not pass.
+    pub failed_subtests: Vec<String>,
+    /// Time limit for the test body while a watchdog is running.
+    pub timeout: Option<Duration>,
+    /// Setup fixtures recorded with [`before`].
+    pub befores: Vec<FixtureResult>,
+    /// Teardown fixtures recorded with [`after`] and [`teardown`].
+    pub afters: Vec<FixtureResult>,
+    /// Teardowns registered with [`teardown`], run when the test finishes.
+    teardowns: Vec<Teardown>,
+    /// Metadata from the configuration that the test has not overridden.
+    inherited: Inherited,
+    /// Path of the innermost step that failed, while the failure unwinds.
+    failure_path: Vec<String>,
+    /// Attachments registered with [`on_failure`] and [`on_step_failure`].
+    failure_attachments: Vec<FailureAttachment>,
+    /// Whether this is a sub-test, grouped in its parent's container.
+    subtest: bool,
+    /// Listener notifications waiting for the context to be released.
+    events: Vec<ListenerEvent>,
+    /// Whether listeners were told that the test started.
+    started: bool,
+}
+
+/// A listener notification recorded while the context was borrowed. It is
+/// sent once the borrow ends, so listeners can call back into the runtime.
+#[derive(Debug)]
+enum ListenerEvent {
+    StepStart(StepResult),
+    StepStop(StepResult),
+    Attachment(Attachment),
+}
+
+/// Sends recorded notifications to the listeners, in order.
+fn send_events(events: Vec<ListenerEvent>) {
+    for event in events {
+        notify_listeners(|listener| match &event {
+            ListenerEvent::StepStart(step) => listener.on_step_start(step),
+            ListenerEvent::StepStop(step) => listener.on_step_stop(step),
+            ListenerEvent::Attachment(attachment) => listener.on_attachment(attachment),
+        });
+    }
+}
+
+/// Names of labels and parameters, and links, added from the configuration.
+#[derive(Debug, Clone, Default)]
+struct Inherited {
+    labels: Vec<String>,
+    links: Vec<Link>,
+    parameters: Vec<String>,
+}
+
+/// Content of an attachment computed by an [`on_failure`] closure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttachmentContent {
+    /// Plain text.
+    Text(String),
+    /// A JSON document.
+    Json(serde_json::Value),
+    /// Bytes of the given content type.
+    Binary(Vec<u8>, ContentType),
+}
+
+impl From<String> for AttachmentContent {
+    fn from(text: String) -> Self {
+        AttachmentContent::Text(text)
+    }
+}
+
+impl From<&str> for AttachmentContent {
+    fn from(text: &str) -> Self {
+        AttachmentContent::Text(text.to_string())
+    }
+}
+
+impl From<serde_json::Value> for AttachmentContent {
+    fn from(value: serde_json::Value) -> Self {
+        AttachmentContent::Json(value)
+    }
+}
+
+impl From<(Vec<u8>, ContentType)> for AttachmentContent {
+    fn from((bytes, content_type): (Vec<u8>, ContentType)) -> Self {
+        AttachmentContent::Binary(bytes, content_type)
+    }
+}
+
+/// An attachment computed only if its test or step fails.
+struct FailureAttachment {
+    name: String,
+    /// Depth of the step stack it was registered at; 0 for the test.
+    depth: usize,
+    body: Box<dyn FnOnce() -> AttachmentContent + Send>,
+}
+
+impl std::fmt::Debug for FailureAttachment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FailureAttachment")
+            .field("name", &self.name)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A teardown closure registered to run when the test finishes.
+struct Teardown {
+    name: String,
+    body: Box<dyn FnOnce() + Send>,
+}
+
+impl std::fmt::Debug for Teardown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Teardown")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
 }
 
 impl TestContext {
     /// Creates a new test context.
     pub fn new(name: impl Into<String>, full_name: impl Into<String>) -> Self {
+        let mut ctx = Self::unstarted(name, full_name);
+        ctx.start();
+        ctx
+    }
+
+    /// Creates a test context without telling listeners that the test
+    /// started, for tests the filter may still exclude.
+    ///
+    /// The test starts once [`crate::filter::exclude_current_test`] keeps
+    /// it, or when it finishes.
+    #[doc(hidden)]
+    pub fn unstarted(name: impl Into<String>, full_name: impl Into<String>) -> Self {
         let config = get_config();
         let uuid = generate_uuid();
         let mut result = TestResult::new(uuid, name.into());
@@ -129,48 +723,174 @@ impl TestContext {
         let thread_id = format!("{:?}", std::thread::current().id());
         result.labels.push(Label::thread(thread_id));
 
+        // Metadata configured for the whole run
+        let full_name = result.full_name.as_deref().unwrap_or_default();
+        let mut labels = config.labels.clone();
+        if let Some(provider) = &config.label_provider {
+            let provided = provider.labels(full_name);
+            labels.retain(|(name, _)| !provided.iter().any(|(own, _)| own == name));
+            labels.extend(provided);
+        }
+        let mut inherited = Inherited::default();
+        for (name, value) in labels {
+            if !inherited.labels.contains(&name) {
+                inherited.labels.push(name.clone());
+            }
+            result.labels.push(Label::new(name, value));
+        }
+        for link in &config.links {
+            result.links.push(link.clone());
+            inherited.links.push(link.clone());
+        }
+        for (name, value) in &config.parameters {
+            result.add_parameter(name, value);
+            inherited.parameters.push(name.clone());
+        }
+
         Self {
             result,
             step_stack: Vec::new(),
-            writer: AllureWriter::with_results_dir(config.results_dir),
+            writer: config.writer(),
+            subtest_uuids: Vec::new(),
+            failed_subtests: Vec::new(),
+            timeout: None,
+            befores: Vec::new(),
+            afters: Vec::new(),
+            teardowns: Vec::new(),
+            inherited,
+            failure_path: Vec::new(),
+            failure_attachments: Vec::new(),
+            subtest: false,
+            events: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Calls [`AllureListener::on_test_start`] unless the listeners were
+    /// already told.
+    fn start(&mut self) {
+        if !self.started {
+            self.started = true;
+            notify_listeners(|listener| listener.on_test_start(&mut self.result));
+        }
+    }
+
+    /// Creates the context for the next attempt of a retried test.
+    ///
+    /// The attempt is built like the first one, then given the metadata and
+    /// setup fixtures the test had when retrying started.
+    fn retry_of(template: &AttemptTemplate) -> TestContext {
+        let full_name = template.result.full_name.clone().unwrap_or_default();
+        let mut next = TestContext::new(template.result.name.clone(), full_name);
+        let fresh = std::mem::replace(&mut next.result, template.result.clone());
+        next.result.uuid = fresh.uuid;
+        next.result.start = fresh.start;
+        next.result.stop = fresh.stop;
+        next.inherited = template.inherited.clone();
+        next.befores = template.befores.clone();
+        next
+    }
+
+    /// Takes over the writer and time limit of the previous attempt. Its
+    /// teardowns and failure attachments registered before retrying started
+    /// belong to the whole test, so they move too.
+    fn continue_from(&mut self, previous: &mut TestContext, template: &AttemptTemplate) {
+        self.writer = previous.writer.clone();
+        self.timeout = previous.timeout;
+        let teardowns = template.teardowns.min(previous.teardowns.len());
+        self.teardowns = previous.teardowns.drain(..teardowns).collect();
+        let attachments = template
+            .failure_attachments
+            .min(previous.failure_attachments.len());
+        self.failure_attachments = previous.failure_attachments.drain(..attachments).collect();
+    }
+
+    /// Creates an empty context that only holds a writer.
+    ///
+    /// Used as a stand-in while the real context is moved elsewhere.
+    fn detached(writer: AllureWriter) -> Self {
+        Self {
+            result: TestResult::new(String::new(), String::new()),
+            step_stack: Vec::new(),
+            writer,
+            subtest_uuids: Vec::new(),
+            failed_subtests: Vec::new(),
+            timeout: None,
+            befores: Vec::new(),
+            afters: Vec::new(),
+            teardowns: Vec::new(),
+            inherited: Inherited::default(),
+            failure_path: Vec::new(),
+            failure_attachments: Vec::new(),
+            subtest: false,
+            events: Vec::new(),
+            started: true,
         }
     }
 
     /// Adds a label to the current test.
+    ///
+    /// Replaces configured labels of the same name.
     pub fn add_label(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.override_label(&name);
         self.result.add_label(name, value);
     }
 
     /// Adds a label using a reserved name.
     pub fn add_label_name(&mut self, name: LabelName, value: impl Into<String>) {
+        self.override_label(name.as_str());
         self.result.add_label_name(name, value);
     }
 
+    /// Removes the configured labels named `name`, once.
+    fn override_label(&mut self, name: &str) {
+        if let Some(pos) = self.inherited.labels.iter().position(|own| own == name) {
+            self.inherited.labels.remove(pos);
+            self.result.labels.retain(|label| label.name != name);
+        }
+    }
+
     /// Adds a link to the current test.
+    ///
+    /// A bare ID is expanded with the configured pattern for its link type
+    /// when the result is written.
     pub fn add_link(&mut self, url: impl Into<String>, name: Option<String>, link_type: LinkType) {
+        let url = url.into();
+        let (overridden, kept) = std::mem::take(&mut self.inherited.links)
+            .into_iter()
+            .partition(|link: &Link| link.url == url || (link.name.is_some() && link.name == name));
+        self.inherited.links = kept;
+        self.result.links.retain(|link| !overridden.contains(link));
         self.result.add_link(url, name, link_type);
     }
 
     /// Adds a parameter to the current test or step.
+    ///
+    /// A test parameter replaces a configured parameter of the same name.
     pub fn add_parameter(&mut self, name: impl Into<String>, value: impl Into<String>) {
-        if let Some(step) = self.step_stack.last_mut() {
-            step.add_parameter(name, value);
-        } else {
-            self.result.add_parameter(name, value);
-        }
+        self.add_parameter_struct(Parameter::new(name, value));
     }
 
     /// Adds a parameter with custom options (hidden/masked/excluded).
     pub fn add_parameter_struct(&mut self, parameter: Parameter) {
         if let Some(step) = self.step_stack.last_mut() {
             step.parameters.push(parameter);
-        } else {
-            self.result.parameters.push(parameter);
+            return;
         }
+        let inherited = &mut self.inherited.parameters;
+        if let Some(pos) = inherited.iter().position(|own| *own == parameter.name) {
+            inherited.remove(pos);
+            self.result
+                .parameters
+                .retain(|own| own.name != parameter.name);
+        }
+        self.result.parameters.push(parameter);
     }
 
     /// Adds an attachment to the current test or step.
     pub fn add_attachment(&mut self, attachment: Attachment) {
+        self.queue_event(|| ListenerEvent::Attachment(attachment.clone()));
         if let Some(step) = self.step_stack.last_mut() {
             step.add_attachment(attachment);
         } else {
@@ -181,22 +901,20 @@ impl TestContext {
     /// Starts a new step.
     pub fn start_step(&mut self, name: impl Into<String>) {
         let step = StepResult::new(name);
+        self.queue_event(|| ListenerEvent::StepStart(step.clone()));
         self.step_stack.push(step);
     }
 
     /// Finishes the current step with the given status.
     pub fn finish_step(&mut self, status: Status, message: Option<String>, trace: Option<String>) {
+        if self.step_stack.is_empty() {
+            return;
+        }
+        self.run_failure_attachments(self.step_stack.len(), status);
+        let message = self.track_failure(status, message);
         if let Some(mut step) = self.step_stack.pop() {
-            match status {
-                Status::Passed => step.pass(),
-                Status::Failed => step.fail(message, trace),
-                Status::Broken => step.broken(message, trace),
-                _ => {
-                    step.status = status;
-                    step.stage = crate::enums::Stage::Finished;
-                    step.stop = crate::model::current_time_ms();
-                }
-            }
+            close_step(&mut step, status, message, trace);
+            self.queue_event(|| ListenerEvent::StepStop(step.clone()));
 
             // Add the finished step to the parent (either another step or the test result)
             if let Some(parent_step) = self.step_stack.last_mut() {
@@ -207,6 +925,88 @@ impl TestContext {
         }
     }
 
+    /// Starts a new step identified by a generated UUID and returns that UUID.
+    ///
+    /// Tracked steps can be finished out of order with
+    /// [`finish_step_by_uuid`](Self::finish_step_by_uuid).
+    pub fn start_tracked_step(&mut self, name: impl Into<String>) -> String {
+        let uuid = generate_uuid();
+        let mut step = StepResult::new(name);
+        step.uuid = Some(uuid.clone());
+        self.queue_event(|| ListenerEvent::StepStart(step.clone()));
+        self.step_stack.push(step);
+        uuid
+    }
+
+    /// Returns the open step with the given UUID, if it is still on the stack.
+    pub fn open_step_mut(&mut self, uuid: &str) -> Option<&mut StepResult> {
+        self.step_stack
+            .iter_mut()
+            .rev()
+            .find(|step| step.uuid.as_deref() == Some(uuid))
+    }
+
+    /// Finishes the open step with the given UUID.
+    ///
+    /// Steps opened after it are still nested inside it, so they are closed
+    /// first as broken. Returns `false` if the step is no longer open.
+    pub fn finish_step_by_uuid(
+        &mut self,
+        uuid: &str,
+        status: Status,
+        message: Option<String>,
+        trace: Option<String>,
+    ) -> bool {
+        let Some(index) = self
+            .step_stack
+            .iter()
+            .rposition(|step| step.uuid.as_deref() == Some(uuid))
+        else {
+            return false;
+        };
+
+        while self.step_stack.len() > index + 1 {
+            self.finish_step(Status::Broken, Some("Step not completed".to_string()), None);
+        }
+        self.finish_step(status, message, trace);
+        true
+    }
+
+    /// Finishes the open step with the given UUID and returns it instead of
+    /// adding it to its parent. Steps opened after it are closed as broken.
+    ///
+    /// Returns `None` if no open step has that UUID.
+    pub fn take_step_by_uuid(
+        &mut self,
+        uuid: &str,
+        status: Status,
+        message: Option<String>,
+        trace: Option<String>,
+    ) -> Option<StepResult> {
+        let index = self
+            .step_stack
+            .iter()
+            .rposition(|step| step.uuid.as_deref() == Some(uuid))?;
+
+        while self.step_stack.len() > index + 1 {
+            self.finish_step(Status::Broken, Some("Step not completed".to_string()), None);
+        }
+        self.run_failure_attachments(self.step_stack.len(), status);
+        let message = self.track_failure(status, message);
+        let mut step = self.step_stack.pop()?;
+        close_step(&mut step, status, message, trace);
+        self.queue_event(|| ListenerEvent::StepStop(step.clone()));
+        Some(step)
+    }
+
+    /// Records a listener notification, if any listener is registered. It
+    /// is sent once the context is released, see [`with_context`].
+    fn queue_event(&mut self, event: impl FnOnce() -> ListenerEvent) {
+        if !active_config().listeners.is_empty() {
+            self.events.push(event());
+        }
+    }
+
     /// Computes and sets the history ID based on the full name and parameters.
     pub fn compute_history_id(&mut self) {
         if let Some(ref full_name) = self.result.full_name {
@@ -215,13 +1015,106 @@ impl TestContext {
         }
     }
 
+    /// Returns the failure deferred until the end of the test, if any.
+    ///
+    /// Failed sub-tests don't stop the test body, so a test that otherwise
+    /// passed still has to be reported as failed once it finishes.
+    pub fn deferred_failure(&self) -> Option<String> {
+        if self.failed_subtests.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{} of {} subtest(s) failed: {}",
+            self.failed_subtests.len(),
+            self.subtest_uuids.len(),
+            self.failed_subtests.join(", ")
+        ))
+    }
+
+    /// Returns the names of the open steps, outermost first.
+    pub fn step_path(&self) -> Vec<String> {
+        self.step_stack
+            .iter()
+            .map(|step| step.name.clone())
+            .collect()
+    }
+
+    /// Records where a step closing with `status` failed, and returns its
+    /// message prefixed with that step path if configured.
+    ///
+    /// Outer steps failing from the same panic keep the innermost path; a
+    /// step that does not fail clears it.
+    fn track_failure(&mut self, status: Status, message: Option<String>) -> Option<String> {
+        if !matches!(status, Status::Failed | Status::Broken) {
+            self.failure_path.clear();
+            return message;
+        }
+        let path = self.step_path();
+        if !self.failure_path.starts_with(&path) {
+            self.failure_path = path;
+        }
+        with_step_path(message, &self.failure_path)
+    }
+
+    /// Returns whether a step, setup fixture or sub-test has failed or
+    /// broken so far.
+    pub fn has_failures(&self) -> bool {
+        fn failed(status: Status) -> bool {
+            matches!(status, Status::Failed | Status::Broken)
+        }
+        fn any_failed(steps: &[StepResult]) -> bool {
+            steps
+                .iter()
+                .any(|step| failed(step.status) || any_failed(&step.steps))
+        }
+        !self.failed_subtests.is_empty()
+            || self.befores.iter().any(|fixture| failed(fixture.status))
+            || any_failed(&self.result.steps)
+            || self.step_stack.iter().any(|step| any_failed(&step.steps))
+    }
+
     /// Finishes the test with the given status and writes the result.
+    ///
+    /// A `Passed` status is turned into `Failed` if a failure was deferred
+    /// (see [`deferred_failure`](Self::deferred_failure)).
     pub fn finish(&mut self, status: Status, message: Option<String>, trace: Option<String>) {
+        let (status, message) = match (status, self.deferred_failure()) {
+            (Status::Passed, Some(deferred)) => (Status::Failed, Some(deferred)),
+            _ => (status, message),
+        };
+        self.start();
+
         // Finish any remaining open steps
         while !self.step_stack.is_empty() {
             self.finish_step(Status::Broken, Some("Step not completed".to_string()), None);
         }
 
+        // A failure caused by a broken setup is not a product defect
+        let setup_failed = self
+            .befores
+            .iter()
+            .any(|fixture| matches!(fixture.status, Status::Failed | Status::Broken));
+        let status = match status {
+            Status::Failed if setup_failed => Status::Broken,
+            status => status,
+        };
+
+        self.run_failure_attachments(0, status);
+
+        let failed = matches!(status, Status::Failed | Status::Broken);
+        let message = if failed && !self.failure_path.is_empty() {
+            if active_config().print_step_path {
+                eprintln!(
+                    "allure: {} failed in step: {}",
+                    self.result.name,
+                    self.failure_path.join(STEP_PATH_SEPARATOR)
+                );
+            }
+            with_step_path(message, &self.failure_path)
+        } else {
+            message
+        };
+
         // Compute history ID before finishing
         self.compute_history_id();
 
@@ -246,53 +1139,226 @@ impl TestContext {
             }
         }
 
+        self.run_teardowns();
+        send_events(std::mem::take(&mut self.events));
+
+        let config = active_config();
+        expand_links(&mut self.result.links, &config.link_patterns);
+        config
+            .listeners
+            .notify(|listener| listener.before_test_write(&mut self.result));
+
         // Write the result
         if let Err(e) = self.writer.write_test_result(&self.result) {
-            eprintln!("Failed to write Allure test result: {}", e);
+            report_write_error(format!("Failed to write Allure test result: {}", e));
+        }
+        config
+            .listeners
+            .notify(|listener| listener.on_test_stop(&self.result));
+        add_run_child(&self.writer, &self.result);
+
+        // Sub-tests are listed in their parent's container
+        if self.subtest {
+            return;
         }
 
-        // Emit a container linking this test (even if no fixtures are present yet)
+        // Emit a container linking this test with its fixtures
         let mut container = TestResultContainer::new(generate_uuid());
         container.children.push(self.result.uuid.clone());
-        container.start = Some(self.result.start);
-        container.stop = Some(self.result.stop);
+        container
+            .children
+            .extend(self.subtest_uuids.iter().cloned());
+        container.befores = self.befores.clone();
+        container.afters = self.afters.clone();
+        container.start = self
+            .befores
+            .iter()
+            .map(|fixture| fixture.start)
+            .chain([self.result.start])
+            .min();
+        container.stop = self
+            .afters
+            .iter()
+            .map(|fixture| fixture.stop)
+            .chain([self.result.stop])
+            .max();
         if let Err(e) = self.writer.write_container(&container) {
-            eprintln!("Failed to write Allure container: {}", e);
+            report_write_error(format!("Failed to write Allure container: {}", e));
+        }
+    }
+
+    /// Runs the registered teardowns, newest first, recording each as an
+    /// after fixture.
+    fn run_teardowns(&mut self) {
+        if self.teardowns.is_empty() {
+            return;
+        }
+        let teardowns = std::mem::take(&mut self.teardowns);
+        self.make_current(|| {
+            for teardown in teardowns.into_iter().rev() {
+                let _ = run_fixture(FixtureKind::After, teardown.name, teardown.body);
+            }
+        });
+    }
+
+    /// Runs `f` with this context made current, so that steps and
+    /// attachments inside it are recorded here even while the context is
+    /// borrowed from its storage.
+    fn make_current<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let detached = TestContext::detached(self.writer.clone());
+        let handle: SharedContext = Arc::new(Mutex::new(Some(std::mem::replace(self, detached))));
+        let previous = set_watched_context(Some(handle.clone()));
+
+        let result = f();
+
+        set_watched_context(previous);
+        let ctx = lock_shared(&handle).take();
+        if let Some(ctx) = ctx {
+            *self = ctx;
+        }
+        result
+    }
+
+    /// Registers an attachment computed only if the test fails or breaks.
+    ///
+    /// See [`on_failure`].
+    pub fn on_failure<C, F>(&mut self, name: impl Into<String>, body: F)
+    where
+        C: Into<AttachmentContent>,
+        F: FnOnce() -> C + Send + 'static,
+    {
+        self.failure_attachments.push(FailureAttachment {
+            name: name.into(),
+            depth: 0,
+            body: Box::new(move || body().into()),
+        });
+    }
+
+    /// Registers an attachment computed only if the current step fails or
+    /// breaks. Outside a step, it is registered for the test.
+    pub fn on_step_failure<C, F>(&mut self, name: impl Into<String>, body: F)
+    where
+        C: Into<AttachmentContent>,
+        F: FnOnce() -> C + Send + 'static,
+    {
+        self.failure_attachments.push(FailureAttachment {
+            name: name.into(),
+            depth: self.step_stack.len(),
+            body: Box::new(move || body().into()),
+        });
+    }
+
+    /// Removes the failure attachments registered at `depth` or deeper and,
+    /// if `status` is a failure, attaches their content to the current step
+    /// or test.
+    fn run_failure_attachments(&mut self, depth: usize, status: Status) {
+        if !self
+            .failure_attachments
+            .iter()
+            .any(|attachment| attachment.depth >= depth)
+        {
+            return;
+        }
+        let (due, kept) = std::mem::take(&mut self.failure_attachments)
+            .into_iter()
+            .partition(|attachment| attachment.depth >= depth);
+        self.failure_attachments = kept;
+        if !matches!(status, Status::Failed | Status::Broken) {
+            return;
+        }
+
+        let due: Vec<FailureAttachment> = due;
+        let contents = self.make_current(|| {
+            due.into_iter()
+                .filter_map(
+                    |attachment| match catch_unwind(AssertUnwindSafe(attachment.body)) {
+                        Ok(content) => Some((attachment.name, content)),
+                        Err(panic) => {
+                            eprintln!(
+                                "allure: failure attachment {:?} panicked: {}",
+                                attachment.name,
+                                panic_message(panic.as_ref(), "no message")
+                            );
+                            None
+                        }
+                    },
+                )
+                .collect::<Vec<_>>()
+        });
+        for (name, content) in contents {
+            match content {
+                AttachmentContent::Text(text) => self.attach_text(name, text),
+                AttachmentContent::Json(value) => self.attach_json(name, &value),
+                AttachmentContent::Binary(bytes, content_type) => {
+                    self.attach_binary(name, &bytes, content_type)
+                }
+            }
         }
     }
 
     /// Creates a text attachment.
     pub fn attach_text(&mut self, name: impl Into<String>, content: impl AsRef<str>) {
-        match self.writer.write_text_attachment(name, content) {
-            Ok(attachment) => self.add_attachment(attachment),
-            Err(e) => eprintln!("Failed to write text attachment: {}", e),
+        if let Err(e) = self.try_attach_text(name, content) {
+            report_write_error(format!("Failed to write text attachment: {}", e));
         }
     }
 
+    /// Creates a text attachment, returning an error if it cannot be written.
+    pub fn try_attach_text(
+        &mut self,
+        name: impl Into<String>,
+        content: impl AsRef<str>,
+    ) -> AllureResult<()> {
+        let attachment = self.writer.write_text_attachment(name, content)?;
+        self.add_attachment(attachment);
+        Ok(())
+    }
+
     /// Creates a JSON attachment.
     pub fn attach_json<T: serde::Serialize>(&mut self, name: impl Into<String>, value: &T) {
-        match self.writer.write_json_attachment(name, value) {
-            Ok(attachment) => self.add_attachment(attachment),
-            Err(e) => eprintln!("Failed to write JSON attachment: {}", e),
+        if let Err(e) = self.try_attach_json(name, value) {
+            report_write_error(format!("Failed to write JSON attachment: {}", e));
         }
     }
 
-    /// Creates a binary attachment.
-    pub fn attach_binary(
+    /// Creates a JSON attachment, returning an error if it cannot be written.
+    pub fn try_attach_json<T: serde::Serialize>(
+        &mut self,
+        name: impl Into<String>,
+        value: &T,
+    ) -> AllureResult<()> {
+        let attachment = self.writer.write_json_attachment(name, value)?;
+        self.add_attachment(attachment);
+        Ok(())
+    }
+
+    /// Creates a binary attachment.
+    pub fn attach_binary(
         &mut self,
         name: impl Into<String>,
         content: &[u8],
         content_type: ContentType,
     ) {
-        match self
-            .writer
-            .write_binary_attachment(name, content, content_type)
-        {
-            Ok(attachment) => self.add_attachment(attachment),
-            Err(e) => eprintln!("Failed to write binary attachment: {}", e),
+        if let Err(e) = self.try_attach_binary(name, content, content_type) {
+            report_write_error(format!("Failed to write binary attachment: {}", e));
         }
     }
 
+    /// Creates a binary attachment, returning an error if it cannot be
+    /// written.
+    pub fn try_attach_binary(
+        &mut self,
+        name: impl Into<String>,
+        content: &[u8],
+        content_type: ContentType,
+    ) -> AllureResult<()> {
+        let attachment = self
+            .writer
+            .write_binary_attachment(name, content, content_type)?;
+        self.add_attachment(attachment);
+        Ok(())
+    }
+
     /// Attaches a file from the filesystem.
     pub fn attach_file(
         &mut self,
@@ -300,16 +1366,93 @@ impl TestContext {
         path: impl AsRef<std::path::Path>,
         content_type: Option<ContentType>,
     ) {
-        match self.writer.copy_file_attachment(name, path, content_type) {
-            Ok(attachment) => self.add_attachment(attachment),
-            Err(e) => eprintln!("Failed to copy file attachment: {}", e),
+        if let Err(e) = self.try_attach_file(name, path, content_type) {
+            report_write_error(format!("Failed to copy file attachment: {}", e));
         }
     }
+
+    /// Attaches a file from the filesystem, returning an error if it cannot
+    /// be copied.
+    pub fn try_attach_file(
+        &mut self,
+        name: impl Into<String>,
+        path: impl AsRef<std::path::Path>,
+        content_type: Option<ContentType>,
+    ) -> AllureResult<()> {
+        let attachment = self.writer.copy_file_attachment(name, path, content_type)?;
+        self.add_attachment(attachment);
+        Ok(())
+    }
 }
 
 // Thread-local storage for synchronous tests
 thread_local! {
     static CURRENT_CONTEXT: RefCell<Option<TestContext>> = const { RefCell::new(None) };
+    static WATCHED_CONTEXT: RefCell<Option<SharedContext>> = const { RefCell::new(None) };
+}
+
+/// Stands in for [`WATCHED_CONTEXT`] once the thread's locals are destroyed,
+/// such as in `#[ctor::dtor]` functions running at process exit.
+static EXITING_CONTEXT: Mutex<Option<SharedContext>> = Mutex::new(None);
+
+/// Locks a shared context, ignoring poisoning from a panicked test body.
+pub(crate) fn lock_shared(handle: &SharedContext) -> MutexGuard<'_, Option<TestContext>> {
+    handle
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Installs a shared context handle on the current thread, returning the
+/// previous one.
+///
+/// The handle takes precedence over every other context slot. It is used by
+/// timeout workers and while teardowns run.
+pub(crate) fn set_watched_context(handle: Option<SharedContext>) -> Option<SharedContext> {
+    let mut handle = Some(handle);
+    WATCHED_CONTEXT
+        .try_with(|c| std::mem::replace(&mut *c.borrow_mut(), handle.take().flatten()))
+        .unwrap_or_else(|_| {
+            let mut exiting = EXITING_CONTEXT
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            std::mem::replace(&mut *exiting, handle.take().flatten())
+        })
+}
+
+/// Returns the shared context handle installed on the current thread.
+fn watched_context() -> Option<SharedContext> {
+    WATCHED_CONTEXT
+        .try_with(|c| c.borrow().clone())
+        .unwrap_or_else(|_| {
+            EXITING_CONTEXT
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone()
+        })
+}
+
+/// Puts a context back into the slot it was taken from.
+///
+/// Inside a tokio task-local scope the context goes back into the task's
+/// handle; otherwise it becomes the thread-local context.
+pub(crate) fn restore_context(ctx: TestContext) {
+    #[cfg(feature = "tokio")]
+    {
+        let mut ctx_opt = Some(ctx);
+        let _ = TOKIO_CONTEXT.try_with(|c| {
+            if let Some(handle) = c.borrow().clone() {
+                let mut guard = lock_shared(&handle);
+                if guard.is_none() {
+                    *guard = ctx_opt.take();
+                }
+            }
+        });
+        if let Some(ctx) = ctx_opt {
+            set_context(ctx);
+        }
+    }
+    #[cfg(not(feature = "tokio"))]
+    set_context(ctx);
 }
 
 /// Sets the current test context for the thread.
@@ -321,6 +1464,11 @@ pub fn set_context(ctx: TestContext) {
 
 /// Takes the current test context, leaving None in its place.
 pub fn take_context() -> Option<TestContext> {
+    let watched = watched_context().and_then(|handle| lock_shared(&handle).take());
+    if watched.is_some() {
+        return watched;
+    }
+
     #[cfg(feature = "tokio")]
     {
         if let Ok(context) = TOKIO_CONTEXT.try_with(|c| {
@@ -336,7 +1484,10 @@ pub fn take_context() -> Option<TestContext> {
         }
     }
 
-    let thread_local = CURRENT_CONTEXT.with(|c| c.borrow_mut().take());
+    let thread_local = CURRENT_CONTEXT
+        .try_with(|c| c.borrow_mut().take())
+        .ok()
+        .flatten();
     if thread_local.is_some() {
         return thread_local;
     }
@@ -355,13 +1506,85 @@ pub fn take_context() -> Option<TestContext> {
     None
 }
 
+/// Executes a function with the current test context, reporting a call
+/// outside a test according to [`AllureConfig::on_missing_context`].
+fn with_active_context<F, R>(function: &str, f: F) -> Option<R>
+where
+    F: FnOnce(&mut TestContext) -> R,
+{
+    let result = with_context(f);
+    if result.is_none() {
+        static WARNED: AtomicBool = AtomicBool::new(false);
+        active_config().on_missing_context.report(&WARNED, || {
+            format!(
+                "`{}` called outside an #[allure_test] function; it was ignored",
+                function
+            )
+        });
+    }
+    result
+}
+
+/// Reports a result, container or attachment that could not be written,
+/// according to [`AllureConfig::on_write_error`].
+fn report_write_error(message: String) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    active_config()
+        .on_write_error
+        .report(&WARNED, move || message);
+}
+
+/// Executes a function with the current test context, returning
+/// [`AllureError::NoActiveContext`] outside a test.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::error::AllureError;
+/// use allure_core::runtime::try_with_context;
+///
+/// let result = try_with_context(|ctx| ctx.result.name.clone());
+/// assert!(matches!(result, Err(AllureError::NoActiveContext)));
+/// ```
+pub fn try_with_context<F, R>(f: F) -> AllureResult<R>
+where
+    F: FnOnce(&mut TestContext) -> R,
+{
+    with_context(f).ok_or(AllureError::NoActiveContext)
+}
+
 /// Executes a function with the current test context.
+///
+/// Listener notifications raised by `f` are sent after the context is
+/// released, so listeners may call runtime functions themselves.
 pub fn with_context<F, R>(f: F) -> Option<R>
+where
+    F: FnOnce(&mut TestContext) -> R,
+{
+    let (result, events) = borrow_context(|ctx| {
+        let result = f(ctx);
+        (result, std::mem::take(&mut ctx.events))
+    })?;
+    send_events(events);
+    Some(result)
+}
+
+/// Executes a function with the current test context, wherever it lives.
+fn borrow_context<F, R>(f: F) -> Option<R>
 where
     F: FnOnce(&mut TestContext) -> R,
 {
     let mut f_opt = Some(f);
 
+    let watched = watched_context().and_then(|handle| {
+        let mut guard = lock_shared(&handle);
+        let ctx = guard.as_mut()?;
+        f_opt.take().map(|func| func(ctx))
+    });
+    if watched.is_some() {
+        return watched;
+    }
+
     #[cfg(feature = "tokio")]
     {
         if let Ok(result) = TOKIO_CONTEXT.try_with(|c| {
@@ -383,7 +1606,7 @@ where
     }
 
     let thread_local = CURRENT_CONTEXT
-        .with(|c| {
+        .try_with(|c| {
             let mut ctx = c.borrow_mut();
             if let Some(ctx) = ctx.as_mut() {
                 if let Some(func) = f_opt.take() {
@@ -392,6 +1615,8 @@ where
             }
             None
         })
+        .ok()
+        .flatten()
         .or_else(|| {
             #[cfg(feature = "tokio")]
             {
@@ -436,542 +1661,1875 @@ where
         }
     };
 
+    let skipped = result.as_ref().err().and_then(|e| skip_reason(e.as_ref()));
+
     // Finish the test context
+    let mut deferred_failure = None;
     if let Some(mut ctx) = take_context() {
-        if is_err {
+        if let Some(reason) = skipped.clone() {
+            ctx.finish(Status::Skipped, Some(reason), None);
+        } else if is_err {
             let trace = capture_trace();
             ctx.finish(Status::Failed, panic_payload, trace);
         } else {
             ctx.finish(Status::Passed, None, None);
+            deferred_failure = ctx.deferred_failure();
         }
     }
 
     // Re-panic if the test failed
     if let Err(e) = result {
-        std::panic::resume_unwind(e);
+        if skipped.is_none() {
+            std::panic::resume_unwind(e);
+        }
+    }
+    if let Some(message) = deferred_failure {
+        panic!("{}", message);
     }
 }
 
-/// Executes a closure with a temporary test context for documentation examples.
+/// Runs a setup fixture and records it in the test's container.
 ///
-/// This function is useful for running doc tests that use runtime functions
-/// like `step()`, `label()`, etc. without needing the full test infrastructure.
-/// No test results are written to disk.
+/// Steps, parameters and attachments inside the body are recorded on the
+/// fixture. If the body panics, the fixture is marked failed, the panic
+/// continues, and the test is reported as broken rather than failed.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, step, epic};
+/// use allure_core::runtime::{with_test_context, before, after};
 ///
 /// with_test_context(|| {
-///     epic("My Epic");
-///     step("Do something", || {
-///         // test code
-///     });
+///     let port = before("Start database", || 5432);
+///     assert_eq!(port, 5432);
+///     after("Stop database", || {});
 /// });
 /// ```
-#[doc(hidden)]
-pub fn with_test_context<F, R>(f: F) -> R
+pub fn before<F, R>(name: impl Into<String>, body: F) -> R
 where
     F: FnOnce() -> R,
 {
-    let ctx = TestContext::new("doctest", "doctest::example");
-    set_context(ctx);
-    let result = f();
-    let _ = take_context(); // cleanup without writing
-    result
+    match run_fixture(FixtureKind::Before, name.into(), body) {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e),
+    }
 }
 
-// === Public API functions ===
-
-/// Adds a label to the current test.
-///
-/// # Example
-///
-/// ```
-/// use allure_core::runtime::{with_test_context, label};
+/// Runs a teardown fixture immediately and records it in the test's container.
 ///
-/// with_test_context(|| {
-///     label("environment", "staging");
-///     label("browser", "chrome");
-/// });
-/// ```
-pub fn label(name: impl Into<String>, value: impl Into<String>) {
-    with_context(|ctx| ctx.add_label(name, value));
+/// Use [`teardown`] to register cleanup that should run when the test ends.
+pub fn after<F, R>(name: impl Into<String>, body: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    match run_fixture(FixtureKind::After, name.into(), body) {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e),
+    }
 }
 
-/// Adds an epic label to the current test.
+/// Registers cleanup to run when the current test finishes.
 ///
-/// Epics represent high-level business capabilities in the BDD hierarchy.
+/// Teardowns run newest first, after the test body, whether it passed or
+/// not. Each one is recorded as an after fixture; a failing teardown is
+/// marked failed without changing the test status. Outside of a test, the
+/// teardown is dropped without running.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, epic};
+/// use allure_core::runtime::{with_test_context, teardown};
 ///
 /// with_test_context(|| {
-///     epic("User Management");
+///     let dir = std::env::temp_dir().join("allure-teardown-example");
+///     std::fs::create_dir_all(&dir).unwrap();
+///     teardown("Remove temp dir", move || {
+///         let _ = std::fs::remove_dir_all(dir);
+///     });
 /// });
 /// ```
-pub fn epic(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Epic, name));
+pub fn teardown<F>(name: impl Into<String>, body: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let name = name.into();
+    with_context(move |ctx| {
+        ctx.teardowns.push(Teardown {
+            name,
+            body: Box::new(body),
+        })
+    });
 }
 
-/// Adds a feature label to the current test.
+#[derive(Clone, Copy)]
+enum FixtureKind {
+    Before,
+    After,
+}
+
+/// Runs a fixture body as a tracked step and moves the step into the
+/// context's befores or afters.
+fn run_fixture<F, R>(kind: FixtureKind, name: String, body: F) -> std::thread::Result<R>
+where
+    F: FnOnce() -> R,
+{
+    let uuid = with_context(|ctx| ctx.start_tracked_step(name));
+    let result = catch_unwind(AssertUnwindSafe(body));
+    if let Some(uuid) = uuid {
+        finish_fixture(kind, &uuid, &result);
+    }
+    result
+}
+
+/// Closes the fixture step started with `uuid` and records it as a fixture.
+fn finish_fixture<R>(kind: FixtureKind, uuid: &str, result: &std::thread::Result<R>) {
+    let (status, message, trace) = match result {
+        Ok(_) => (Status::Passed, None, None),
+        Err(panic) => match skip_reason(panic.as_ref()) {
+            Some(reason) => (Status::Skipped, Some(reason), None),
+            None => (
+                Status::Failed,
+                Some(panic_message(panic.as_ref(), "Fixture panicked")),
+                capture_trace(),
+            ),
+        },
+    };
+
+    with_context(|ctx| {
+        let step = ctx
+            .take_step_by_uuid(uuid, status, message, trace)
+            .or_else(|| {
+                // `skip` closes every open step, so the fixture may already
+                // have been added to the test's steps
+                let last = ctx.result.steps.last()?;
+                (last.uuid.as_deref() == Some(uuid))
+                    .then(|| ctx.result.steps.pop())
+                    .flatten()
+            });
+        if let Some(step) = step {
+            let fixture = FixtureResult::from(step);
+            match kind {
+                FixtureKind::Before => ctx.befores.push(fixture),
+                FixtureKind::After => ctx.afters.push(fixture),
+            }
+        }
+    });
+}
+
+/// Runs an async setup fixture and records it in the test's container.
 ///
-/// Features represent specific functionality under an epic.
+/// The async counterpart of [`before`], used by `#[allure_fixture]` on
+/// async functions.
 ///
 /// # Example
 ///
-/// ```
-/// use allure_core::runtime::{with_test_context, feature};
+/// ```no_run
+/// use allure_core::runtime::before_async;
 ///
-/// with_test_context(|| {
-///     feature("User Registration");
-/// });
+/// async fn database_port() -> u16 {
+///     before_async("Start database", async { 5432 }).await
+/// }
 /// ```
-pub fn feature(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Feature, name));
+pub async fn before_async<F, R>(name: impl Into<String>, fut: F) -> R
+where
+    F: std::future::Future<Output = R>,
+{
+    use std::task::Poll;
+
+    let uuid = with_context(|ctx| ctx.start_tracked_step(name));
+    let mut fut = std::pin::pin!(fut);
+    let result =
+        std::future::poll_fn(
+            |cx| match catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+                Ok(Poll::Ready(value)) => Poll::Ready(Ok(value)),
+                Ok(Poll::Pending) => Poll::Pending,
+                Err(panic) => Poll::Ready(Err(panic)),
+            },
+        )
+        .await;
+    if let Some(uuid) = uuid {
+        finish_fixture(FixtureKind::Before, &uuid, &result);
+    }
+    match result {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e),
+    }
 }
 
-/// Adds a story label to the current test.
+/// A fixture value whose drop is recorded as an after fixture.
 ///
-/// Stories represent user stories under a feature.
+/// Return it from a fixture function to report cleanup that happens when
+/// the test releases the value. The wrapped value is reachable through
+/// `Deref`; a failing drop is marked failed without changing the test
+/// status, like [`teardown`].
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, story};
+/// use allure_core::runtime::{with_test_context, before, FixtureGuard};
+///
+/// fn temp_dir() -> FixtureGuard<Vec<String>> {
+///     let files = before("Create temp dir", Vec::new);
+///     FixtureGuard::new("Remove temp dir", files)
+/// }
 ///
 /// with_test_context(|| {
-///     story("User can register with email");
+///     let files = temp_dir();
+///     assert!(files.is_empty());
 /// });
 /// ```
-pub fn story(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Story, name));
+pub struct FixtureGuard<T> {
+    name: String,
+    value: Option<T>,
 }
 
-/// Adds a suite label to the current test.
-pub fn suite(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Suite, name));
+impl<T> FixtureGuard<T> {
+    /// Wraps `value`, naming the after fixture recorded when it's dropped.
+    pub fn new(name: impl Into<String>, value: T) -> Self {
+        Self {
+            name: name.into(),
+            value: Some(value),
+        }
+    }
+
+    /// Unwraps the value without recording an after fixture.
+    pub fn into_inner(mut self) -> T {
+        self.value
+            .take()
+            .expect("fixture value is present until drop")
+    }
 }
 
-/// Adds a parent suite label to the current test.
-pub fn parent_suite(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::ParentSuite, name));
+impl<T> std::ops::Deref for FixtureGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+            .as_ref()
+            .expect("fixture value is present until drop")
+    }
 }
 
-/// Adds a sub-suite label to the current test.
-pub fn sub_suite(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::SubSuite, name));
+impl<T> std::ops::DerefMut for FixtureGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+            .as_mut()
+            .expect("fixture value is present until drop")
+    }
 }
 
-/// Adds a severity label to the current test.
-///
-/// # Example
-///
-/// ```
-/// use allure_core::runtime::{with_test_context, severity};
-/// use allure_core::Severity;
-///
-/// with_test_context(|| {
-///     severity(Severity::Critical);
-/// });
-/// ```
-pub fn severity(severity: Severity) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Severity, severity.as_str()));
+impl<T: std::fmt::Debug> std::fmt::Debug for FixtureGuard<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixtureGuard")
+            .field("name", &self.name)
+            .field("value", &self.value)
+            .finish()
+    }
 }
 
-/// Adds an owner label to the current test.
-///
-/// # Example
-///
-/// ```
-/// use allure_core::runtime::{with_test_context, owner};
-///
-/// with_test_context(|| {
-///     owner("platform-team");
-/// });
-/// ```
-pub fn owner(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Owner, name));
+impl<T> Drop for FixtureGuard<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            let name = std::mem::take(&mut self.name);
+            // The guard may be dropped while the test is unwinding, so a
+            // failing drop must not panic again
+            let _ = run_fixture(FixtureKind::After, name, move || drop(value));
+        }
+    }
 }
 
-/// Adds a tag label to the current test.
+/// Runs a binary-wide setup fixture, recorded in the run-level container.
+///
+/// Meant for setup done once per test binary, such as in a `#[ctor::ctor]`
+/// function. Every test result written afterwards by the process is added
+/// to the run-level container's children. The container is written once at
+/// the end, by [`after_all`] or [`end_run`]; without a teardown fixture, call
+/// [`end_run`] from a `#[ctor::dtor]` function. Steps and attachments inside
+/// the body belong to the fixture.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, tag};
+/// use allure_core::runtime::before_all;
 ///
-/// with_test_context(|| {
-///     tag("smoke");
-///     tag("regression");
-/// });
+/// let port = before_all("Start server", || 8080);
+/// assert_eq!(port, 8080);
 /// ```
-pub fn tag(name: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::Tag, name));
+pub fn before_all<F, R>(name: impl Into<String>, body: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    match run_run_fixture(FixtureKind::Before, name.into(), body) {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e),
+    }
 }
 
-/// Adds multiple tag labels to the current test.
+/// Runs a binary-wide teardown fixture, recorded in the run-level container.
 ///
-/// # Example
-///
-/// ```
-/// use allure_core::runtime::{with_test_context, tags};
-///
-/// with_test_context(|| {
-///     tags(&["smoke", "regression", "api"]);
-/// });
-/// ```
-pub fn tags(names: &[&str]) {
-    with_context(|ctx| {
-        for name in names {
-            ctx.add_label_name(LabelName::Tag, *name);
-        }
-    });
+/// Meant for teardown done once per test binary, such as in a
+/// `#[ctor::dtor]` function. The run-level container is written right away,
+/// with every test result written so far as its children.
+pub fn after_all<F, R>(name: impl Into<String>, body: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    match run_run_fixture(FixtureKind::After, name.into(), body) {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e),
+    }
 }
 
-/// Adds an Allure ID label to the current test.
-pub fn allure_id(id: impl Into<String>) {
-    with_context(|ctx| ctx.add_label_name(LabelName::AllureId, id));
-}
+/// The run-level container, created by the first [`before_all`] or
+/// [`after_all`].
+static RUN_FIXTURES: Mutex<Option<RunFixtures>> = Mutex::new(None);
+
+/// Fixtures shared by every test of the binary.
+#[derive(Debug)]
+struct RunFixtures {
+    container: TestResultContainer,
+    /// Writer of the latest child, so the container lands next to the tests.
+    writer: Option<AllureWriter>,
+    /// Whether the container changed since it was last written.
+    changed: bool,
+}
+
+impl RunFixtures {
+    fn new() -> Self {
+        Self {
+            container: TestResultContainer::new(generate_uuid()),
+            writer: None,
+            changed: false,
+        }
+    }
+
+    fn add_fixtures(&mut self, befores: Vec<FixtureResult>, afters: Vec<FixtureResult>) {
+        let container = &mut self.container;
+        container.start = befores
+            .iter()
+            .chain(&afters)
+            .map(|fixture| fixture.start)
+            .chain(container.start)
+            .min();
+        container.stop = befores
+            .iter()
+            .chain(&afters)
+            .map(|fixture| fixture.stop)
+            .chain(container.stop)
+            .max();
+        container.befores.extend(befores);
+        container.afters.extend(afters);
+        self.changed = true;
+    }
+
+    fn add_child(&mut self, writer: &AllureWriter, result: &TestResult) {
+        let container = &mut self.container;
+        container.children.push(result.uuid.clone());
+        container.start = container.start.into_iter().chain([result.start]).min();
+        container.stop = container.stop.into_iter().chain([result.stop]).max();
+        self.writer = Some(writer.clone());
+        self.changed = true;
+    }
+
+    /// Writes the container if it changed since it was last written.
+    fn flush(&mut self) {
+        if !std::mem::take(&mut self.changed) {
+            return;
+        }
+        let writer = self
+            .writer
+            .clone()
+            .unwrap_or_else(|| active_config().writer());
+        if let Err(e) = writer.write_container(&self.container) {
+            report_write_error(format!("Failed to write Allure run container: {}", e));
+        }
+    }
+}
+
+fn lock_run_fixtures() -> MutexGuard<'static, Option<RunFixtures>> {
+    RUN_FIXTURES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Adds a written test result to the run-level container, if there is one.
+/// The container is written when the run ends.
+fn add_run_child(writer: &AllureWriter, result: &TestResult) {
+    if let Some(run) = lock_run_fixtures().as_mut() {
+        run.add_child(writer, result);
+    }
+}
+
+/// Writes the run-level container, if there is one and it changed.
+pub(crate) fn flush_run_fixtures() {
+    if let Some(run) = lock_run_fixtures().as_mut() {
+        run.flush();
+    }
+}
+
+/// Runs a binary-wide fixture in a context of its own and moves the
+/// recorded fixture into the run-level container.
+fn run_run_fixture<F, R>(kind: FixtureKind, name: String, body: F) -> std::thread::Result<R>
+where
+    F: FnOnce() -> R,
+{
+    let writer = active_config().writer();
+    let handle: SharedContext = Arc::new(Mutex::new(Some(TestContext::detached(writer))));
+    let previous = set_watched_context(Some(handle.clone()));
+    let result = run_fixture(kind, name, body);
+    set_watched_context(previous);
+
+    let ctx = lock_shared(&handle).take();
+    if let Some(ctx) = ctx {
+        let mut run = lock_run_fixtures();
+        let run = run.get_or_insert_with(RunFixtures::new);
+        run.add_fixtures(ctx.befores, ctx.afters);
+        // Setup often runs before the results directory is configured, so
+        // the container is written from teardown or the end of the run
+        if matches!(kind, FixtureKind::After) {
+            run.flush();
+        }
+    }
+    result
+}
+
+/// Runs a table-driven case as its own Allure test result.
+///
+/// The sub-test gets its own UUID, parameters, history ID and status, and
+/// inherits the labels and links of the enclosing test. A failing sub-test
+/// doesn't stop the enclosing test body; instead, the enclosing test fails
+/// once it finishes. Returns the body's value, or `None` if it panicked.
+///
+/// Outside of a test, the body runs without any tracking.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, subtest};
+///
+/// with_test_context(|| {
+///     for (input, expected) in [("1", 1), ("42", 42)] {
+///         subtest(format!("parse {input}"), [("input", input)], || {
+///             assert_eq!(input.parse::<i32>().unwrap(), expected);
+///         });
+///     }
+/// });
+/// ```
+pub fn subtest<F, R, I, K, V>(name: impl Into<String>, params: I, body: F) -> Option<R>
+where
+    F: FnOnce() -> R,
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: ToString,
+{
+    let name = name.into();
+    let parent = with_context(|parent| {
+        (
+            parent.result.full_name.clone().unwrap_or_default(),
+            parent.writer.clone(),
+            parent.result.labels.clone(),
+            parent.result.links.clone(),
+        )
+    });
+    let Some((parent_name, writer, labels, links)) = parent else {
+        return Some(body());
+    };
+
+    // Created outside of `with_context` so listeners see it start unborrowed
+    let mut child = TestContext::new(name.clone(), format!("{}::{}", parent_name, name));
+    child.writer = writer;
+    child.subtest = true;
+    for label in &labels {
+        if !child.result.labels.contains(label) {
+            child.add_label(&label.name, &label.value);
+        }
+    }
+    for link in links {
+        if !child.result.links.contains(&link) {
+            child.result.links.push(link);
+        }
+    }
+    for (key, value) in params {
+        child.add_parameter(key, value.to_string());
+    }
+
+    // Swap the child in so runtime calls in the body land on the sub-test.
+    let parent = with_context(|ctx| {
+        ctx.subtest_uuids.push(child.result.uuid.clone());
+        std::mem::replace(ctx, child)
+    })
+    .expect("test context disappeared while starting a subtest");
+
+    let result = catch_unwind(AssertUnwindSafe(body));
+
+    let mut child = with_context(|ctx| std::mem::replace(ctx, parent))
+        .expect("test context disappeared while running a subtest");
+    match &result {
+        Ok(_) => child.finish(Status::Passed, None, None),
+        Err(panic) => match skip_reason(panic.as_ref()) {
+            Some(reason) => child.finish(Status::Skipped, Some(reason), None),
+            None => child.finish(
+                Status::Failed,
+                Some(panic_message(panic.as_ref(), "Subtest panicked")),
+                capture_trace(),
+            ),
+        },
+    }
+
+    // Fixtures of the sub-test go into the parent's container with it
+    let failed = !matches!(child.result.status, Status::Passed | Status::Skipped);
+    with_context(|ctx| {
+        ctx.befores.append(&mut child.befores);
+        ctx.afters.append(&mut child.afters);
+        if failed {
+            ctx.failed_subtests.push(name);
+        }
+    });
+    result.ok()
+}
+
+/// Reruns a failing test body, writing each attempt as its own result.
+///
+/// This is the runtime side of `#[allure_retry]`. Every attempt keeps the
+/// test's full name and parameters, so all results share one history ID and
+/// Allure shows the earlier ones as retries. A test that passes after failing
+/// is marked flaky.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, Retry};
+///
+/// with_test_context(|| {
+///     let mut retry = Retry::new(2, 0);
+///     let mut calls = 0;
+///     loop {
+///         calls += 1;
+///         let outcome = std::panic::catch_unwind(|| assert!(calls > 1));
+///         let again = match &outcome {
+///             Ok(_) => retry.complete(),
+///             Err(panic) => retry.retry_after_panic(panic.as_ref()),
+///         };
+///         if !again {
+///             break;
+///         }
+///         retry.wait();
+///     }
+///     assert_eq!(retry.attempt(), 2);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct Retry {
+    retries: u32,
+    delay: Duration,
+    attempt: u32,
+    template: Option<AttemptTemplate>,
+}
+
+/// The state of a test when retrying started, used for every later attempt.
+#[derive(Debug)]
+struct AttemptTemplate {
+    result: TestResult,
+    inherited: Inherited,
+    befores: Vec<FixtureResult>,
+    /// Number of teardowns registered before the first attempt.
+    teardowns: usize,
+    /// Number of failure attachments registered before the first attempt.
+    failure_attachments: usize,
+}
+
+impl Retry {
+    /// Starts tracking attempts for the current test.
+    ///
+    /// `retries` is how many times a failing body is rerun, so the body runs
+    /// at most `retries + 1` times. The current result is used as the
+    /// template for every later attempt.
+    pub fn new(retries: u32, delay_ms: u64) -> Self {
+        Self {
+            retries,
+            delay: Duration::from_millis(delay_ms),
+            attempt: 1,
+            template: with_context(|ctx| AttemptTemplate {
+                result: ctx.result.clone(),
+                inherited: ctx.inherited.clone(),
+                befores: ctx.befores.clone(),
+                teardowns: ctx.teardowns.len(),
+                failure_attachments: ctx.failure_attachments.len(),
+            }),
+        }
+    }
+
+    /// Returns the number of the current attempt, starting at 1.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Handles a panicking attempt. Returns `true` if the body should run again.
+    ///
+    /// Skips are never retried.
+    pub fn retry_after_panic(&mut self, payload: &(dyn std::any::Any + Send)) -> bool {
+        if skip_reason(payload).is_some() {
+            return false;
+        }
+        let message = panic_message(payload, "Test panicked");
+        self.retry_after_error(message)
+    }
+
+    /// Handles an attempt that failed without panicking, e.g. by returning
+    /// `Err`. Returns `true` if the body should run again.
+    pub fn retry_after_error(&mut self, message: impl Into<String>) -> bool {
+        if self.attempt > self.retries {
+            return false;
+        }
+        self.next_attempt(Some(message.into()), capture_trace());
+        true
+    }
+
+    /// Handles an attempt whose body returned normally.
+    ///
+    /// Returns `true` if failed sub-tests require another attempt. Otherwise
+    /// the result is marked flaky when earlier attempts failed.
+    pub fn complete(&mut self) -> bool {
+        let deferred = with_context(|ctx| ctx.deferred_failure()).flatten();
+        if let Some(message) = deferred {
+            if self.attempt <= self.retries {
+                self.next_attempt(Some(message), None);
+                return true;
+            }
+        } else if self.attempt > 1 {
+            flaky();
+        }
+        false
+    }
+
+    /// Sleeps for the configured delay between attempts.
+    pub fn wait(&self) {
+        if !self.delay.is_zero() {
+            std::thread::sleep(self.delay);
+        }
+    }
+
+    /// Waits for the configured delay between attempts in async tests.
+    ///
+    /// With the `tokio` feature this yields to the tokio timer when called
+    /// inside a runtime; otherwise it blocks the current thread.
+    pub async fn wait_async(&self) {
+        if self.delay.is_zero() {
+            return;
+        }
+        #[cfg(feature = "tokio")]
+        if tokio::runtime::Handle::try_current().is_ok() {
+            tokio::time::sleep(self.delay).await;
+            return;
+        }
+        std::thread::sleep(self.delay);
+    }
+
+    /// Writes the current attempt as failed and starts a fresh result.
+    fn next_attempt(&mut self, message: Option<String>, trace: Option<String>) {
+        self.attempt += 1;
+        let Some(template) = &self.template else {
+            return;
+        };
+
+        let mut next = TestContext::retry_of(template);
+        let previous = with_context(|ctx| {
+            next.continue_from(ctx, template);
+            std::mem::replace(ctx, next)
+        });
+        // Finish outside of `with_context` so teardowns can record on it
+        if let Some(mut previous) = previous {
+            previous.finish(Status::Failed, message, trace);
+        }
+    }
+}
+
+/// Executes a closure with a temporary test context for documentation examples.
+///
+/// This function is useful for running doc tests that use runtime functions
+/// like `step()`, `label()`, etc. without needing the full test infrastructure.
+/// No test results are written to disk.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, step, epic};
+///
+/// with_test_context(|| {
+///     epic("My Epic");
+///     step("Do something", || {
+///         // test code
+///     });
+/// });
+/// ```
+#[doc(hidden)]
+pub fn with_test_context<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let ctx = TestContext::new("doctest", "doctest::example");
+    set_context(ctx);
+    let result = f();
+    let _ = take_context(); // cleanup without writing
+    result
+}
+
+// === Public API functions ===
+
+/// Adds a label to the current test.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, label};
+///
+/// with_test_context(|| {
+///     label("environment", "staging");
+///     label("browser", "chrome");
+/// });
+/// ```
+pub fn label(name: impl Into<String>, value: impl Into<String>) {
+    with_active_context("label", |ctx| ctx.add_label(name, value));
+}
+
+/// Adds an epic label to the current test.
+///
+/// Epics represent high-level business capabilities in the BDD hierarchy.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, epic};
+///
+/// with_test_context(|| {
+///     epic("User Management");
+/// });
+/// ```
+pub fn epic(name: impl Into<String>) {
+    with_active_context("epic", |ctx| ctx.add_label_name(LabelName::Epic, name));
+}
+
+/// Adds a feature label to the current test.
+///
+/// Features represent specific functionality under an epic.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, feature};
+///
+/// with_test_context(|| {
+///     feature("User Registration");
+/// });
+/// ```
+pub fn feature(name: impl Into<String>) {
+    with_active_context("feature", |ctx| {
+        ctx.add_label_name(LabelName::Feature, name)
+    });
+}
+
+/// Adds a story label to the current test.
+///
+/// Stories represent user stories under a feature.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, story};
+///
+/// with_test_context(|| {
+///     story("User can register with email");
+/// });
+/// ```
+pub fn story(name: impl Into<String>) {
+    with_active_context("story", |ctx| ctx.add_label_name(LabelName::Story, name));
+}
+
+/// Adds a suite label to the current test.
+pub fn suite(name: impl Into<String>) {
+    with_active_context("suite", |ctx| ctx.add_label_name(LabelName::Suite, name));
+}
+
+/// Adds a parent suite label to the current test.
+pub fn parent_suite(name: impl Into<String>) {
+    with_active_context("parent_suite", |ctx| {
+        ctx.add_label_name(LabelName::ParentSuite, name)
+    });
+}
+
+/// Adds a sub-suite label to the current test.
+pub fn sub_suite(name: impl Into<String>) {
+    with_active_context("sub_suite", |ctx| {
+        ctx.add_label_name(LabelName::SubSuite, name)
+    });
+}
+
+/// Adds a severity label to the current test.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, severity};
+/// use allure_core::Severity;
+///
+/// with_test_context(|| {
+///     severity(Severity::Critical);
+/// });
+/// ```
+pub fn severity(severity: Severity) {
+    with_active_context("severity", |ctx| {
+        ctx.add_label_name(LabelName::Severity, severity.as_str())
+    });
+}
+
+/// Adds an owner label to the current test.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, owner};
+///
+/// with_test_context(|| {
+///     owner("platform-team");
+/// });
+/// ```
+pub fn owner(name: impl Into<String>) {
+    with_active_context("owner", |ctx| ctx.add_label_name(LabelName::Owner, name));
+}
+
+/// Adds a tag label to the current test.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, tag};
+///
+/// with_test_context(|| {
+///     tag("smoke");
+///     tag("regression");
+/// });
+/// ```
+pub fn tag(name: impl Into<String>) {
+    with_active_context("tag", |ctx| ctx.add_label_name(LabelName::Tag, name));
+}
+
+/// Adds multiple tag labels to the current test.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, tags};
+///
+/// with_test_context(|| {
+///     tags(&["smoke", "regression", "api"]);
+/// });
+/// ```
+pub fn tags(names: &[&str]) {
+    with_active_context("tags", |ctx| {
+        for name in names {
+            ctx.add_label_name(LabelName::Tag, *name);
+        }
+    });
+}
+
+/// Adds an Allure ID label to the current test.
+pub fn allure_id(id: impl Into<String>) {
+    with_active_context("allure_id", |ctx| {
+        ctx.add_label_name(LabelName::AllureId, id)
+    });
+}
 
 /// Sets a custom title for the current test.
 ///
-/// This overrides the test name displayed in the Allure report.
+/// This overrides the test name displayed in the Allure report.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, title};
+///
+/// with_test_context(|| {
+///     title("User can login with valid credentials");
+/// });
+/// ```
+pub fn title(name: impl Into<String>) {
+    with_active_context("title", |ctx| ctx.result.name = name.into());
+}
+
+/// Sets the test description (markdown).
+pub fn description(text: impl Into<String>) {
+    with_active_context("description", |ctx| {
+        ctx.result.description = Some(text.into())
+    });
+}
+
+/// Sets the test description (HTML).
+pub fn description_html(html: impl Into<String>) {
+    with_active_context("description_html", |ctx| {
+        ctx.result.description_html = Some(html.into())
+    });
+}
+
+/// Adds an issue link to the current test.
+pub fn issue(url: impl Into<String>, name: Option<String>) {
+    with_active_context("issue", |ctx| ctx.add_link(url, name, LinkType::Issue));
+}
+
+/// Adds a TMS link to the current test.
+pub fn tms(url: impl Into<String>, name: Option<String>) {
+    with_active_context("tms", |ctx| ctx.add_link(url, name, LinkType::Tms));
+}
+
+/// Adds a generic link to the current test.
+pub fn link(url: impl Into<String>, name: Option<String>) {
+    with_active_context("link", |ctx| ctx.add_link(url, name, LinkType::Default));
+}
+
+/// Adds a link of any type, such as a custom `jira` or `github` link, to the
+/// current test.
+///
+/// A bare ID is expanded with the pattern configured for the link type.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::enums::LinkType;
+/// use allure_core::runtime::{with_test_context, typed_link};
+///
+/// with_test_context(|| {
+///     typed_link("1234", Some("PR".to_string()), LinkType::custom("github"));
+/// });
+/// ```
+pub fn typed_link(url: impl Into<String>, name: Option<String>, link_type: LinkType) {
+    with_active_context("typed_link", |ctx| ctx.add_link(url, name, link_type));
+}
+
+/// Adds a parameter to the current test or step.
+///
+/// Parameters are displayed in the Allure report and can be used
+/// to understand what inputs were used for a test run.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, parameter};
+///
+/// with_test_context(|| {
+///     parameter("username", "john_doe");
+///     parameter("count", 42);
+/// });
+/// ```
+pub fn parameter(name: impl Into<String>, value: impl ToString) {
+    with_active_context("parameter", |ctx| {
+        ctx.add_parameter(name, value.to_string())
+    });
+}
+
+/// Adds a parameter hidden from display (value not shown in the report).
+pub fn parameter_hidden(name: impl Into<String>, value: impl ToString) {
+    with_active_context("parameter_hidden", |ctx| {
+        ctx.add_parameter_struct(Parameter::hidden(name, value.to_string()))
+    });
+}
+
+/// Adds a parameter with a masked value (e.g., passwords).
+pub fn parameter_masked(name: impl Into<String>, value: impl ToString) {
+    with_active_context("parameter_masked", |ctx| {
+        ctx.add_parameter_struct(Parameter::masked(name, value.to_string()))
+    });
+}
+
+/// Adds a parameter excluded from history ID calculation.
+pub fn parameter_excluded(name: impl Into<String>, value: impl ToString) {
+    with_active_context("parameter_excluded", |ctx| {
+        ctx.add_parameter_struct(Parameter::excluded(name, value.to_string()))
+    });
+}
+
+/// Executes a step with the given name and body.
+///
+/// Steps are the building blocks of test reports. They provide
+/// a hierarchical view of what the test is doing and can be nested.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, step};
+///
+/// with_test_context(|| {
+///     step("Login to application", || {
+///         step("Enter credentials", || {
+///             // Enter username and password
+///         });
+///         step("Click submit", || {
+///             // Click the submit button
+///         });
+///     });
+/// });
+/// ```
+///
+/// Steps can also return values:
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, step};
+///
+/// with_test_context(|| {
+///     let result = step("Calculate result", || {
+///         2 + 2
+///     });
+///     assert_eq!(result, 4);
+/// });
+/// ```
+pub fn step<F, R>(name: impl Into<String>, body: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let step_name = name.into();
+
+    with_active_context("step", |ctx| ctx.start_step(&step_name));
+
+    let result = catch_unwind(AssertUnwindSafe(body));
+
+    match &result {
+        Ok(_) => {
+            with_context(|ctx| ctx.finish_step(Status::Passed, None, None));
+        }
+        Err(panic_info) => {
+            let message = if let Some(s) = panic_info.downcast_ref::<&str>() {
+                Some(s.to_string())
+            } else if let Some(s) = panic_info.downcast_ref::<String>() {
+                Some(s.clone())
+            } else {
+                Some("Step panicked".to_string())
+            };
+            let trace = capture_trace();
+            with_context(|ctx| ctx.finish_step(Status::Failed, message, trace));
+        }
+    }
+
+    match result {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e),
+    }
+}
+
+/// Starts a step that stays open until the returned guard is finished or dropped.
+///
+/// This is an alternative to [`step`] for code that doesn't fit in a closure,
+/// such as builder chains or functions with many early returns. A guard that
+/// is dropped without an explicit status is recorded as passed, or as failed
+/// if the thread is panicking.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, start_step};
+///
+/// with_test_context(|| {
+///     let connect = start_step("Connect");
+///     connect.parameter("host", "localhost");
+///     connect.attach_text("Handshake", "HELLO");
+///     connect.pass();
+///
+///     let _query = start_step("Run query");
+///     // Passed when `_query` goes out of scope
+/// });
+/// ```
+pub fn start_step(name: impl Into<String>) -> StepGuard {
+    let name = name.into();
+    StepGuard {
+        uuid: with_active_context("start_step", |ctx| ctx.start_tracked_step(name)),
+    }
+}
+
+/// Guard for a step started with [`start_step`].
+///
+/// The guard identifies its step by UUID, so guards dropped out of order
+/// only close their own step (and any steps still nested inside it).
+#[derive(Debug)]
+#[must_use = "dropping the guard immediately finishes the step"]
+pub struct StepGuard {
+    uuid: Option<String>,
+}
+
+impl StepGuard {
+    /// Marks the step as passed.
+    pub fn pass(mut self) {
+        self.finish(Status::Passed, None);
+    }
+
+    /// Marks the step as failed with a message.
+    pub fn fail(mut self, message: impl Into<String>) {
+        self.finish(Status::Failed, Some(message.into()));
+    }
+
+    /// Marks the step as broken with a message.
+    pub fn broken(mut self, message: impl Into<String>) {
+        self.finish(Status::Broken, Some(message.into()));
+    }
+
+    /// Adds a parameter to the step.
+    pub fn parameter(&self, name: impl Into<String>, value: impl ToString) {
+        self.with_step(|_, step| step.add_parameter(name, value.to_string()));
+    }
+
+    /// Attaches text content to the step.
+    pub fn attach_text(&self, name: impl Into<String>, content: impl AsRef<str>) {
+        self.attach_with(|writer| writer.write_text_attachment(name, content));
+    }
+
+    /// Attaches JSON content to the step.
+    pub fn attach_json<T: serde::Serialize>(&self, name: impl Into<String>, value: &T) {
+        self.attach_with(|writer| writer.write_json_attachment(name, value));
+    }
+
+    /// Attaches binary content to the step.
+    pub fn attach_binary(
+        &self,
+        name: impl Into<String>,
+        content: &[u8],
+        content_type: ContentType,
+    ) {
+        self.attach_with(|writer| writer.write_binary_attachment(name, content, content_type));
+    }
+
+    /// Attaches a file from the filesystem to the step.
+    pub fn attach_file(
+        &self,
+        name: impl Into<String>,
+        path: impl AsRef<std::path::Path>,
+        content_type: Option<ContentType>,
+    ) {
+        self.attach_with(|writer| writer.copy_file_attachment(name, path, content_type));
+    }
+
+    fn with_step<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&AllureWriter, &mut StepResult) -> R,
+    {
+        let uuid = self.uuid.as_deref()?;
+        with_context(|ctx| {
+            let writer = ctx.writer.clone();
+            ctx.open_step_mut(uuid).map(|step| f(&writer, step))
+        })
+        .flatten()
+    }
+
+    fn attach_with<F>(&self, write: F)
+    where
+        F: FnOnce(&AllureWriter) -> std::io::Result<Attachment>,
+    {
+        let attached = self.with_step(|writer, step| match write(writer) {
+            Ok(attachment) => {
+                step.add_attachment(attachment.clone());
+                Some(attachment)
+            }
+            Err(e) => {
+                report_write_error(format!("Failed to write step attachment: {}", e));
+                None
+            }
+        });
+        // Notified once the context is released, like other attachments
+        if let Some(attachment) = attached.flatten() {
+            notify_listeners(|listener| listener.on_attachment(&attachment));
+        }
+    }
+
+    fn finish(&mut self, status: Status, message: Option<String>) {
+        if let Some(uuid) = self.uuid.take() {
+            let trace = if status == Status::Passed {
+                None
+            } else {
+                capture_trace()
+            };
+            with_context(|ctx| ctx.finish_step_by_uuid(&uuid, status, message, trace));
+        }
+    }
+}
+
+impl Drop for StepGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.finish(Status::Failed, Some("Step panicked".to_string()));
+        } else {
+            self.finish(Status::Passed, None);
+        }
+    }
+}
+
+/// Logs a step without a body (for simple logging).
+///
+/// This is useful for logging actions that don't have a body,
+/// such as noting an event or state.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, log_step};
+/// use allure_core::Status;
+///
+/// with_test_context(|| {
+///     log_step("Database connection established", Status::Passed);
+///     log_step("Cache was cleared", Status::Passed);
+/// });
+/// ```
+pub fn log_step(name: impl Into<String>, status: Status) {
+    with_active_context("log_step", |ctx| {
+        ctx.start_step(name);
+        ctx.finish_step(status, None, None);
+    });
+}
+
+/// Attaches text content to the current test or step.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, attach_text};
+///
+/// with_test_context(|| {
+///     attach_text("API Response", r#"{"status": "ok"}"#);
+///     attach_text("Log Output", "Test completed successfully");
+/// });
+/// ```
+pub fn attach_text(name: impl Into<String>, content: impl AsRef<str>) {
+    with_active_context("attach_text", |ctx| ctx.attach_text(name, content));
+}
+
+/// Attaches JSON content to the current test or step.
+///
+/// The value is serialized to JSON using serde.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, title};
+/// use allure_core::runtime::{with_test_context, attach_json};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+///     email: String,
+/// }
 ///
 /// with_test_context(|| {
-///     title("User can login with valid credentials");
+///     let user = User {
+///         name: "John".to_string(),
+///         email: "john@example.com".to_string(),
+///     };
+///     attach_json("User Data", &user);
 /// });
 /// ```
-pub fn title(name: impl Into<String>) {
-    with_context(|ctx| ctx.result.name = name.into());
+pub fn attach_json<T: serde::Serialize>(name: impl Into<String>, value: &T) {
+    with_active_context("attach_json", |ctx| ctx.attach_json(name, value));
 }
 
-/// Sets the test description (markdown).
-pub fn description(text: impl Into<String>) {
-    with_context(|ctx| ctx.result.description = Some(text.into()));
+/// Attaches binary content to the current test or step.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, attach_binary};
+/// use allure_core::ContentType;
+///
+/// with_test_context(|| {
+///     let png_data: &[u8] = &[0x89, 0x50, 0x4E, 0x47]; // PNG header
+///     attach_binary("Screenshot", png_data, ContentType::Png);
+/// });
+/// ```
+pub fn attach_binary(name: impl Into<String>, content: &[u8], content_type: ContentType) {
+    with_active_context("attach_binary", |ctx| {
+        ctx.attach_binary(name, content, content_type)
+    });
 }
 
-/// Sets the test description (HTML).
-pub fn description_html(html: impl Into<String>) {
-    with_context(|ctx| ctx.result.description_html = Some(html.into()));
+/// Marks the current test as flaky.
+///
+/// Flaky tests are tests that can fail intermittently due to
+/// external factors like network issues or timing problems.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, flaky};
+///
+/// with_test_context(|| {
+///     flaky();
+///     // Test code that sometimes fails due to network issues
+/// });
+/// ```
+pub fn flaky() {
+    with_active_context("flaky", |ctx| {
+        let details = ctx
+            .result
+            .status_details
+            .get_or_insert_with(Default::default);
+        details.flaky = Some(true);
+    });
 }
 
-/// Adds an issue link to the current test.
-pub fn issue(url: impl Into<String>, name: Option<String>) {
-    with_context(|ctx| ctx.add_link(url, name, LinkType::Issue));
+/// Marks the current test as muted.
+///
+/// Muted tests are tests whose results will not affect the statistics
+/// in the Allure report. The test is still executed and documented,
+/// but won't impact pass/fail metrics.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, muted};
+///
+/// with_test_context(|| {
+///     muted();
+///     // Test code that shouldn't affect statistics
+/// });
+/// ```
+pub fn muted() {
+    with_active_context("muted", |ctx| {
+        let details = ctx
+            .result
+            .status_details
+            .get_or_insert_with(Default::default);
+        details.muted = Some(true);
+    });
 }
 
-/// Adds a TMS link to the current test.
-pub fn tms(url: impl Into<String>, name: Option<String>) {
-    with_context(|ctx| ctx.add_link(url, name, LinkType::Tms));
+/// Marks the current test as having a known issue.
+///
+/// This adds an issue link and marks the test status as having a known issue.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, known_issue};
+///
+/// with_test_context(|| {
+///     known_issue("https://github.com/example/project/issues/123");
+/// });
+/// ```
+pub fn known_issue(issue_id: impl Into<String>) {
+    let id = issue_id.into();
+    with_active_context("known_issue", |ctx| {
+        let details = ctx
+            .result
+            .status_details
+            .get_or_insert_with(Default::default);
+        details.known = Some(true);
+        // Also add as an issue link
+        ctx.add_link(&id, Some(id.clone()), LinkType::Issue);
+    });
 }
 
-/// Adds a generic link to the current test.
-pub fn link(url: impl Into<String>, name: Option<String>) {
-    with_context(|ctx| ctx.add_link(url, name, LinkType::Default));
+/// Panic payload used by [`skip`] to stop the test body.
+///
+/// `run_test`, [`subtest`] and the `#[allure_test]` macro recognize this
+/// payload and record the test as skipped instead of failed.
+#[derive(Debug, Clone)]
+pub struct SkipSignal {
+    /// Why the test was skipped.
+    pub reason: String,
 }
 
-/// Adds a parameter to the current test or step.
+/// Returns the skip reason if a panic payload was raised by [`skip`].
+pub fn skip_reason(payload: &(dyn std::any::Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<SkipSignal>()
+        .map(|signal| signal.reason.clone())
+}
+
+/// Skips the rest of the current test.
 ///
-/// Parameters are displayed in the Allure report and can be used
-/// to understand what inputs were used for a test run.
+/// Open steps are closed as skipped, then the test body is unwound so that
+/// nothing after the call runs. The test is recorded as skipped with the
+/// given reason. Note that libtest has no runtime skip, so `cargo test`
+/// still lists the test as passed.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, parameter};
+/// use allure_core::runtime::{run_test, skip};
+///
+/// run_test("needs_network", "docs::needs_network", || {
+///     skip("network is not available");
+///     #[allow(unreachable_code)]
+///     {
+///         unreachable!("the body stops at skip()");
+///     }
+/// });
+/// ```
+pub fn skip(reason: impl Into<String>) -> ! {
+    let reason = reason.into();
+    with_context(|ctx| {
+        while !ctx.step_stack.is_empty() {
+            ctx.finish_step(Status::Skipped, Some(reason.clone()), None);
+        }
+    });
+    // `resume_unwind` skips the panic hook, so no panic message is printed.
+    std::panic::resume_unwind(Box::new(SkipSignal { reason }))
+}
+
+/// Skips the rest of the current test if `condition` is true.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::{with_test_context, skip_if};
 ///
 /// with_test_context(|| {
-///     parameter("username", "john_doe");
-///     parameter("count", 42);
+///     skip_if(cfg!(not(unix)), "requires a unix host");
 /// });
 /// ```
-pub fn parameter(name: impl Into<String>, value: impl ToString) {
-    with_context(|ctx| ctx.add_parameter(name, value.to_string()));
+pub fn skip_if(condition: bool, reason: impl Into<String>) {
+    if condition {
+        skip(reason);
+    }
 }
 
-/// Adds a parameter hidden from display (value not shown in the report).
-pub fn parameter_hidden(name: impl Into<String>, value: impl ToString) {
-    with_context(|ctx| ctx.add_parameter_struct(Parameter::hidden(name, value.to_string())));
+/// Skips the rest of the current test unless an assumption holds.
+///
+/// Without a message, the reason is `assumption failed: <condition>`.
+///
+/// ```
+/// use allure_core::{assume, runtime::with_test_context};
+///
+/// with_test_context(|| {
+///     assume!(1 + 1 == 2);
+///     assume!(!cfg!(miri), "not supported under {}", "miri");
+/// });
+/// ```
+#[macro_export]
+macro_rules! assume {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::runtime::skip(concat!("assumption failed: ", stringify!($cond)));
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::runtime::skip(format!($($arg)+));
+        }
+    };
 }
 
-/// Adds a parameter with a masked value (e.g., passwords).
-pub fn parameter_masked(name: impl Into<String>, value: impl ToString) {
-    with_context(|ctx| ctx.add_parameter_struct(Parameter::masked(name, value.to_string())));
+/// Sets the display name for the current test.
+///
+/// This overrides the test name that was set when the test context was created.
+pub fn display_name(name: impl Into<String>) {
+    with_active_context("display_name", |ctx| ctx.result.name = name.into());
 }
 
-/// Adds a parameter excluded from history ID calculation.
-pub fn parameter_excluded(name: impl Into<String>, value: impl ToString) {
-    with_context(|ctx| ctx.add_parameter_struct(Parameter::excluded(name, value.to_string())));
+/// Sets the test case ID for the current test.
+///
+/// This is used to link the test to a test case in a test management system.
+pub fn test_case_id(id: impl Into<String>) {
+    with_active_context("test_case_id", |ctx| {
+        ctx.result.test_case_id = Some(id.into())
+    });
 }
 
-/// Executes a step with the given name and body.
+/// Attaches a file from the filesystem to the current test or step.
 ///
-/// Steps are the building blocks of test reports. They provide
-/// a hierarchical view of what the test is doing and can be nested.
+/// The file is copied to the Allure results directory.
+pub fn attach_file(
+    name: impl Into<String>,
+    path: impl AsRef<std::path::Path>,
+    content_type: Option<ContentType>,
+) {
+    with_active_context("attach_file", |ctx| {
+        ctx.attach_file(name, path, content_type)
+    });
+}
+
+/// Starts an attachment written in chunks, for logs too large to hold in
+/// memory.
+///
+/// Content goes straight to a file in the results directory. The
+/// attachment is added to the current step, or the test, when the writer
+/// is finished or dropped. Outside a test, the content is discarded.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, step};
+/// use std::io::Write;
+/// use allure_core::enums::ContentType;
+/// use allure_core::runtime::{attachment_writer, with_test_context};
 ///
 /// with_test_context(|| {
-///     step("Login to application", || {
-///         step("Enter credentials", || {
-///             // Enter username and password
-///         });
-///         step("Click submit", || {
-///             // Click the submit button
-///         });
-///     });
+///     let mut log = attachment_writer("Soak log", ContentType::Text);
+///     for i in 0..3 {
+///         writeln!(log, "iteration {}", i).unwrap();
+///     }
+///     log.finish().unwrap();
 /// });
 /// ```
+pub fn attachment_writer(name: impl Into<String>, content_type: ContentType) -> AttachmentWriter {
+    let stream =
+        with_active_context("attachment_writer", |ctx| ctx.writer.clone()).and_then(|writer| {
+            match writer.start_attachment(name, content_type) {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    report_write_error(format!("Failed to create streamed attachment: {}", e));
+                    None
+                }
+            }
+        });
+    AttachmentWriter { stream, tee: None }
+}
+
+/// Copies everything from `reader` into an attachment and returns the
+/// number of bytes copied.
 ///
-/// Steps can also return values:
+/// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, step};
+/// use allure_core::enums::ContentType;
+/// use allure_core::runtime::{attach_reader, with_test_context};
 ///
 /// with_test_context(|| {
-///     let result = step("Calculate result", || {
-///         2 + 2
-///     });
-///     assert_eq!(result, 4);
+///     let report = std::io::Cursor::new("<testsuite/>");
+///     attach_reader("JUnit report", report, ContentType::Xml).unwrap();
 /// });
 /// ```
-pub fn step<F, R>(name: impl Into<String>, body: F) -> R
-where
-    F: FnOnce() -> R,
-{
-    let step_name = name.into();
+pub fn attach_reader(
+    name: impl Into<String>,
+    mut reader: impl std::io::Read,
+    content_type: ContentType,
+) -> AllureResult<u64> {
+    let mut writer = attachment_writer(name, content_type);
+    let copied = std::io::copy(&mut reader, &mut writer)?;
+    writer.finish()?;
+    Ok(copied)
+}
+
+/// An attachment written in chunks, created with [`attachment_writer`].
+///
+/// Implements [`std::io::Write`]. The attachment is added when
+/// [`finish`](Self::finish) is called or the writer is dropped.
+pub struct AttachmentWriter {
+    stream: Option<AttachmentStream>,
+    tee: Option<Box<dyn std::io::Write + Send>>,
+}
 
-    with_context(|ctx| ctx.start_step(&step_name));
+impl AttachmentWriter {
+    /// Also writes everything to `out`, such as the standard output, so a
+    /// child process's output stays visible while it is attached.
+    ///
+    /// ```
+    /// use allure_core::enums::ContentType;
+    /// use allure_core::runtime::{attachment_writer, with_test_context};
+    ///
+    /// with_test_context(|| {
+    ///     let mut output = std::io::Cursor::new("build ok\n"); // e.g. a ChildStdout
+    ///     let mut log =
+    ///         attachment_writer("Build output", ContentType::Text).tee(std::io::stdout());
+    ///     std::io::copy(&mut output, &mut log).unwrap();
+    ///     log.finish().unwrap();
+    /// });
+    /// ```
+    pub fn tee(mut self, out: impl std::io::Write + Send + 'static) -> Self {
+        self.tee = Some(Box::new(out));
+        self
+    }
 
-    let result = catch_unwind(AssertUnwindSafe(body));
+    /// Completes the file and adds the attachment to the current step or
+    /// test, returning an error if it could not be written.
+    pub fn finish(mut self) -> AllureResult<()> {
+        self.close()
+    }
 
-    match &result {
-        Ok(_) => {
-            with_context(|ctx| ctx.finish_step(Status::Passed, None, None));
+    fn close(&mut self) -> AllureResult<()> {
+        if let Some(tee) = &mut self.tee {
+            tee.flush()?;
         }
-        Err(panic_info) => {
-            let message = if let Some(s) = panic_info.downcast_ref::<&str>() {
-                Some(s.to_string())
-            } else if let Some(s) = panic_info.downcast_ref::<String>() {
-                Some(s.clone())
-            } else {
-                Some("Step panicked".to_string())
-            };
-            let trace = capture_trace();
-            with_context(|ctx| ctx.finish_step(Status::Failed, message, trace));
+        let Some(stream) = self.stream.take() else {
+            return Ok(());
+        };
+        let attachment = stream.finish()?;
+        with_active_context("AttachmentWriter::finish", |ctx| {
+            ctx.add_attachment(attachment)
+        });
+        Ok(())
+    }
+}
+
+impl std::io::Write for AttachmentWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = match &mut self.stream {
+            Some(stream) => stream.write(buf)?,
+            None => buf.len(),
+        };
+        if let Some(tee) = &mut self.tee {
+            tee.write_all(&buf[..written])?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Some(stream) = &mut self.stream {
+            stream.flush()?;
+        }
+        if let Some(tee) = &mut self.tee {
+            tee.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AttachmentWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            report_write_error(format!("Failed to write streamed attachment: {}", e));
         }
     }
+}
 
-    match result {
-        Ok(value) => value,
-        Err(e) => std::panic::resume_unwind(e),
+impl std::fmt::Debug for AttachmentWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AttachmentWriter")
+            .field("stream", &self.stream)
+            .field("tee", &self.tee.is_some())
+            .finish()
     }
 }
 
-/// Logs a step without a body (for simple logging).
+/// Registers an attachment computed only if the current test fails or
+/// breaks, such as a log tail or a database dump that is too expensive to
+/// capture for every passing test.
 ///
-/// This is useful for logging actions that don't have a body,
-/// such as noting an event or state.
+/// The closure runs when the test finishes; its content is attached to the
+/// test. Steps and attachments made inside it are recorded on the test too.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, log_step};
-/// use allure_core::Status;
+/// use allure_core::runtime::{on_failure, with_test_context};
 ///
 /// with_test_context(|| {
-///     log_step("Database connection established", Status::Passed);
-///     log_step("Cache was cleared", Status::Passed);
+///     on_failure("Server log", || std::fs::read_to_string("server.log").unwrap_or_default());
 /// });
 /// ```
-pub fn log_step(name: impl Into<String>, status: Status) {
-    with_context(|ctx| {
-        ctx.start_step(name);
-        ctx.finish_step(status, None, None);
-    });
+pub fn on_failure<C, F>(name: impl Into<String>, body: F)
+where
+    C: Into<AttachmentContent>,
+    F: FnOnce() -> C + Send + 'static,
+{
+    with_active_context("on_failure", |ctx| ctx.on_failure(name, body));
 }
 
-/// Attaches text content to the current test or step.
+/// Registers an attachment computed only if the current step fails or
+/// breaks; its content is attached to that step. Outside a step, this is
+/// the same as [`on_failure`].
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, attach_text};
+/// use allure_core::runtime::{on_step_failure, step, with_test_context};
 ///
 /// with_test_context(|| {
-///     attach_text("API Response", r#"{"status": "ok"}"#);
-///     attach_text("Log Output", "Test completed successfully");
+///     step("Query orders", || {
+///         on_step_failure("Orders table", || serde_json::json!({"rows": 0}));
+///     });
 /// });
 /// ```
-pub fn attach_text(name: impl Into<String>, content: impl AsRef<str>) {
-    with_context(|ctx| ctx.attach_text(name, content));
+pub fn on_step_failure<C, F>(name: impl Into<String>, body: F)
+where
+    C: Into<AttachmentContent>,
+    F: FnOnce() -> C + Send + 'static,
+{
+    with_active_context("on_step_failure", |ctx| ctx.on_step_failure(name, body));
 }
 
-/// Attaches JSON content to the current test or step.
-///
-/// The value is serialized to JSON using serde.
+// === Fallible variants ===
+//
+// These return an error instead of following the configured policies, for
+// helpers that may run outside a test or must know that an attachment was
+// written.
+
+/// Adds a label to the current test, returning
+/// [`AllureError::NoActiveContext`] outside a test.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, attach_json};
-/// use serde::Serialize;
+/// use allure_core::error::AllureError;
+/// use allure_core::runtime::{try_label, with_test_context};
 ///
-/// #[derive(Serialize)]
-/// struct User {
-///     name: String,
-///     email: String,
-/// }
-///
-/// with_test_context(|| {
-///     let user = User {
-///         name: "John".to_string(),
-///         email: "john@example.com".to_string(),
-///     };
-///     attach_json("User Data", &user);
-/// });
+/// assert!(matches!(try_label("layer", "api"), Err(AllureError::NoActiveContext)));
+/// with_test_context(|| assert!(try_label("layer", "api").is_ok()));
 /// ```
-pub fn attach_json<T: serde::Serialize>(name: impl Into<String>, value: &T) {
-    with_context(|ctx| ctx.attach_json(name, value));
+pub fn try_label(name: impl Into<String>, value: impl Into<String>) -> AllureResult<()> {
+    try_with_context(|ctx| ctx.add_label(name, value))
 }
 
-/// Attaches binary content to the current test or step.
-///
-/// # Example
-///
-/// ```
-/// use allure_core::runtime::{with_test_context, attach_binary};
-/// use allure_core::ContentType;
-///
-/// with_test_context(|| {
-///     let png_data: &[u8] = &[0x89, 0x50, 0x4E, 0x47]; // PNG header
-///     attach_binary("Screenshot", png_data, ContentType::Png);
-/// });
-/// ```
-pub fn attach_binary(name: impl Into<String>, content: &[u8], content_type: ContentType) {
-    with_context(|ctx| ctx.attach_binary(name, content, content_type));
+/// Adds a parameter to the current test, returning
+/// [`AllureError::NoActiveContext`] outside a test.
+pub fn try_parameter(name: impl Into<String>, value: impl ToString) -> AllureResult<()> {
+    try_with_context(|ctx| ctx.add_parameter(name, value.to_string()))
 }
 
-/// Marks the current test as flaky.
-///
-/// Flaky tests are tests that can fail intermittently due to
-/// external factors like network issues or timing problems.
+/// Runs `body` as a step of the current test.
 ///
-/// # Example
-///
-/// ```
-/// use allure_core::runtime::{with_test_context, flaky};
-///
-/// with_test_context(|| {
-///     flaky();
-///     // Test code that sometimes fails due to network issues
-/// });
-/// ```
-pub fn flaky() {
-    with_context(|ctx| {
-        let details = ctx
-            .result
-            .status_details
-            .get_or_insert_with(Default::default);
-        details.flaky = Some(true);
-    });
+/// Outside a test, returns [`AllureError::NoActiveContext`] without running
+/// `body`. A panic in `body` is recorded and resumed as with [`step`].
+pub fn try_step<F, R>(name: impl Into<String>, body: F) -> AllureResult<R>
+where
+    F: FnOnce() -> R,
+{
+    try_with_context(|_| ())?;
+    Ok(step(name, body))
 }
 
-/// Marks the current test as muted.
-///
-/// Muted tests are tests whose results will not affect the statistics
-/// in the Allure report. The test is still executed and documented,
-/// but won't impact pass/fail metrics.
+/// Attaches text to the current test or step, returning an error outside a
+/// test or if the attachment cannot be written.
+pub fn try_attach_text(name: impl Into<String>, content: impl AsRef<str>) -> AllureResult<()> {
+    try_with_context(|ctx| ctx.try_attach_text(name, content))?
+}
+
+/// Attaches JSON to the current test or step, returning an error outside a
+/// test or if the attachment cannot be written.
+pub fn try_attach_json<T: serde::Serialize>(
+    name: impl Into<String>,
+    value: &T,
+) -> AllureResult<()> {
+    try_with_context(|ctx| ctx.try_attach_json(name, value))?
+}
+
+/// Attaches binary data to the current test or step, returning an error
+/// outside a test or if the attachment cannot be written.
+pub fn try_attach_binary(
+    name: impl Into<String>,
+    content: &[u8],
+    content_type: ContentType,
+) -> AllureResult<()> {
+    try_with_context(|ctx| ctx.try_attach_binary(name, content, content_type))?
+}
+
+/// Attaches a file to the current test or step, returning an error outside
+/// a test or if the file cannot be copied.
+pub fn try_attach_file(
+    name: impl Into<String>,
+    path: impl AsRef<std::path::Path>,
+    content_type: Option<ContentType>,
+) -> AllureResult<()> {
+    try_with_context(|ctx| ctx.try_attach_file(name, path, content_type))?
+}
+
+// === Introspection ===
+
+/// Identity of the running test, returned by [`current_test`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestInfo {
+    /// UUID of the test result.
+    pub uuid: String,
+    /// Display name of the test.
+    pub name: String,
+    /// Full name of the test, such as `my_crate::tests::login`.
+    pub full_name: Option<String>,
+}
+
+/// Returns the running test, or `None` outside a test.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, muted};
+/// use allure_core::runtime::{current_test, with_test_context};
 ///
+/// assert_eq!(current_test(), None);
 /// with_test_context(|| {
-///     muted();
-///     // Test code that shouldn't affect statistics
+///     let test = current_test().unwrap();
+///     let screenshot = format!("{}.png", test.name);
+///     assert_eq!(screenshot, "doctest.png");
 /// });
 /// ```
-pub fn muted() {
-    with_context(|ctx| {
-        let details = ctx
-            .result
-            .status_details
-            .get_or_insert_with(Default::default);
-        details.muted = Some(true);
-    });
+pub fn current_test() -> Option<TestInfo> {
+    with_context(|ctx| TestInfo {
+        uuid: ctx.result.uuid.clone(),
+        name: ctx.result.name.clone(),
+        full_name: ctx.result.full_name.clone(),
+    })
 }
 
-/// Marks the current test as having a known issue.
-///
-/// This adds an issue link and marks the test status as having a known issue.
+/// Returns the names of the open steps, outermost first. Empty outside a
+/// step.
 ///
 /// # Example
 ///
 /// ```
-/// use allure_core::runtime::{with_test_context, known_issue};
+/// use allure_core::runtime::{current_step_path, step, with_test_context};
 ///
 /// with_test_context(|| {
-///     known_issue("https://github.com/example/project/issues/123");
+///     step("Checkout", || {
+///         step("Pay", || assert_eq!(current_step_path(), ["Checkout", "Pay"]));
+///     });
 /// });
 /// ```
-pub fn known_issue(issue_id: impl Into<String>) {
-    let id = issue_id.into();
-    with_context(|ctx| {
-        let details = ctx
-            .result
-            .status_details
-            .get_or_insert_with(Default::default);
-        details.known = Some(true);
-        // Also add as an issue link
-        ctx.add_link(&id, Some(id.clone()), LinkType::Issue);
-    });
+pub fn current_step_path() -> Vec<String> {
+    with_context(|ctx| ctx.step_path()).unwrap_or_default()
 }
 
-/// Marks the current test as skipped and finalizes the result.
-pub fn skip(reason: impl Into<String>) {
-    let reason = reason.into();
-    if let Some(mut ctx) = take_context() {
-        ctx.finish(Status::Skipped, Some(reason), None);
-    }
+/// Returns the labels set on the running test so far. Empty outside a test.
+pub fn current_labels() -> Vec<Label> {
+    with_context(|ctx| ctx.result.labels.clone()).unwrap_or_default()
 }
 
-/// Sets the display name for the current test.
-///
-/// This overrides the test name that was set when the test context was created.
-pub fn display_name(name: impl Into<String>) {
-    with_context(|ctx| ctx.result.name = name.into());
+/// Returns whether a step, setup fixture or sub-test of the running test
+/// has failed so far, including failed soft assertions. `false` outside a
+/// test.
+pub fn has_failures() -> bool {
+    with_context(|ctx| ctx.has_failures()).unwrap_or(false)
 }
 
-/// Sets the test case ID for the current test.
-///
-/// This is used to link the test to a test case in a test management system.
-pub fn test_case_id(id: impl Into<String>) {
-    with_context(|ctx| ctx.result.test_case_id = Some(id.into()));
+/// Separator between step names in a step path.
+const STEP_PATH_SEPARATOR: &str = " > ";
+
+/// Prefixes a failure message with the step path where the failure
+/// happened, if [`AllureConfig::step_path_in_messages`] is set.
+fn with_step_path(message: Option<String>, path: &[String]) -> Option<String> {
+    if path.is_empty() || !active_config().step_path_in_messages {
+        return message;
+    }
+    let path = path.join(STEP_PATH_SEPARATOR);
+    Some(match message {
+        Some(message) => format!("{}: {}", path, message),
+        None => path,
+    })
 }
 
-/// Attaches a file from the filesystem to the current test or step.
-///
-/// The file is copied to the Allure results directory.
-pub fn attach_file(
-    name: impl Into<String>,
-    path: impl AsRef<std::path::Path>,
-    content_type: Option<ContentType>,
+/// Sets the final status of a step that is being closed.
+fn close_step(
+    step: &mut StepResult,
+    status: Status,
+    message: Option<String>,
+    trace: Option<String>,
 ) {
-    with_context(|ctx| ctx.attach_file(name, path, content_type));
+    match status {
+        Status::Passed => step.pass(),
+        Status::Failed => step.fail(message, trace),
+        Status::Broken => step.broken(message, trace),
+        _ => {
+            step.status = status;
+            if message.is_some() || trace.is_some() {
+                step.status_details = Some(crate::model::StatusDetails {
+                    message,
+                    trace,
+                    ..Default::default()
+                });
+            }
+            step.stage = crate::enums::Stage::Finished;
+            step.stop = crate::model::current_time_ms();
+        }
+    }
+}
+
+/// Extracts the message from a panic payload.
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send), default: &str) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        default.to_string()
+    }
 }
 
 /// Captures a backtrace as a string when available.
@@ -1030,8 +3588,29 @@ mod tests {
             .clean_results(false)
             .config;
 
-        assert_eq!(config.results_dir, "custom-results");
-        assert!(!config.clean_results);
+        assert_eq!(config.results_dir.as_deref(), Some("custom-results"));
+        assert_eq!(config.clean_results, Some(false));
+    }
+
+    #[test]
+    fn test_changed_fields_names_only_differing_settings() {
+        let config = AllureConfig::default();
+        let other = AllureConfig {
+            results_dir: "other-results".to_string(),
+            redactor: Redactor::new().secret("hunter2"),
+            ..AllureConfig::default()
+        };
+        assert!(config.changed_fields(&config.clone()).is_empty());
+        assert_eq!(config.changed_fields(&other), ["results_dir", "redactor"]);
+
+        // Providers are only compared by presence, as closures can't be
+        let provided = |provider| AllureConfig {
+            label_provider: Some(LabelProvider::new(provider)),
+            ..AllureConfig::default()
+        };
+        let owner = |_: &str| vec![("owner".to_string(), "qa".to_string())];
+        assert!(provided(owner).changed_fields(&provided(owner)).is_empty());
+        assert_eq!(config.changed_fields(&provided(owner)), ["label_provider"]);
     }
 
     #[test]
@@ -1092,6 +3671,7 @@ mod tests {
         let config_ref = CONFIG.get_or_init(|| AllureConfig {
             results_dir: desired_dir.to_string_lossy().to_string(),
             clean_results: true,
+            ..Default::default()
         });
         let dir = PathBuf::from(&config_ref.results_dir);
         let _ = std::fs::remove_dir_all(&dir);
@@ -1157,6 +3737,46 @@ mod tests {
         .await;
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_introspection_reads_tokio_task_local() {
+        let ctx = TestContext::new("tokio_ctx", "module::tokio_ctx");
+        with_async_context(ctx, async {
+            step("Outer", || {
+                assert_eq!(current_test().unwrap().name, "tokio_ctx");
+                assert_eq!(current_step_path(), ["Outer"]);
+            });
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_introspection_reports_running_test() {
+        assert_eq!(current_test(), None);
+        assert!(current_step_path().is_empty());
+        assert!(!has_failures());
+
+        with_test_context(|| {
+            let test = current_test().unwrap();
+            assert_eq!(test.name, "doctest");
+            assert_eq!(test.full_name.as_deref(), Some("doctest::example"));
+            label("layer", "api");
+            assert!(current_labels()
+                .iter()
+                .any(|label| label.name == "layer" && label.value == "api"));
+
+            step("Checkout", || {
+                let _pay = start_step("Pay");
+                assert_eq!(current_step_path(), ["Checkout", "Pay"]);
+                assert!(!has_failures());
+                log_step("Submit card", Status::Failed);
+                assert!(has_failures());
+            });
+            assert!(current_step_path().is_empty());
+            assert!(has_failures());
+        });
+    }
+
     #[test]
     fn test_with_test_context_clears_after_use() {
         with_test_context(|| {
@@ -1211,6 +3831,232 @@ mod tests {
             .contains("boom step"));
     }
 
+    #[test]
+    fn test_step_guard_passes_on_drop_and_records_details() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = TestContext::new("guard", "module::guard");
+        ctx.writer = AllureWriter::with_results_dir(temp.path());
+        set_context(ctx);
+
+        {
+            let guard = start_step("Connect");
+            guard.parameter("host", "localhost");
+            guard.attach_text("Handshake", "HELLO");
+        }
+        start_step("Query").fail("no rows");
+
+        let ctx = take_context().unwrap();
+        assert!(ctx.step_stack.is_empty());
+        let connect = &ctx.result.steps[0];
+        assert_eq!(connect.status, Status::Passed);
+        assert_eq!(connect.parameters[0].value, "localhost");
+        assert_eq!(connect.attachments.len(), 1);
+        assert!(connect.uuid.is_some());
+
+        let query = &ctx.result.steps[1];
+        assert_eq!(query.status, Status::Failed);
+        assert_eq!(
+            query.status_details.as_ref().unwrap().message.as_deref(),
+            Some("no rows")
+        );
+    }
+
+    #[test]
+    fn test_step_guard_fails_when_dropped_during_panic() {
+        set_context(TestContext::new("guard_panic", "module::guard_panic"));
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = start_step("Explodes");
+            panic!("boom");
+        });
+        assert!(result.is_err());
+
+        let ctx = take_context().unwrap();
+        assert_eq!(ctx.result.steps[0].status, Status::Failed);
+    }
+
+    #[test]
+    fn test_step_guard_out_of_order_drop_keeps_stack_consistent() {
+        set_context(TestContext::new("guard_order", "module::guard_order"));
+
+        let outer = start_step("Outer");
+        let inner = start_step("Inner");
+        outer.broken("outer gave up");
+        // Inner was already closed as part of Outer, so this is a no-op.
+        inner.pass();
+        log_step("After", Status::Passed);
+
+        let ctx = take_context().unwrap();
+        assert!(ctx.step_stack.is_empty());
+        assert_eq!(ctx.result.steps.len(), 2);
+        let outer = &ctx.result.steps[0];
+        assert_eq!(outer.status, Status::Broken);
+        assert_eq!(outer.steps[0].name, "Inner");
+        assert_eq!(outer.steps[0].status, Status::Broken);
+        assert_eq!(ctx.result.steps[1].name, "After");
+    }
+
+    #[test]
+    fn test_step_guard_without_context_is_noop() {
+        let guard = start_step("Orphan");
+        guard.parameter("k", "v");
+        guard.pass();
+        assert!(take_context().is_none());
+    }
+
+    #[test]
+    fn test_subtest_writes_own_result_and_defers_failure() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = TestContext::new("table", "module::table");
+        ctx.writer = AllureWriter::with_results_dir(temp.path());
+        set_context(ctx);
+        epic("Parsing");
+
+        let ok = subtest("case ok", [("input", "1")], || {
+            parameter("extra", 7);
+            1
+        });
+        let failed = subtest("case bad", [("input", "x")], || -> i32 {
+            panic!("not a number");
+        });
+        assert_eq!(ok, Some(1));
+        assert_eq!(failed, None);
+
+        let mut ctx = take_context().unwrap();
+        assert_eq!(ctx.subtest_uuids.len(), 2);
+        assert_eq!(ctx.failed_subtests, vec!["case bad".to_string()]);
+        assert!(ctx.result.parameters.is_empty());
+
+        ctx.finish(Status::Passed, None, None);
+        assert_eq!(ctx.result.status, Status::Failed);
+        assert_eq!(
+            ctx.result
+                .status_details
+                .as_ref()
+                .unwrap()
+                .message
+                .as_deref(),
+            Some("1 of 2 subtest(s) failed: case bad")
+        );
+
+        let bad_path = temp
+            .path()
+            .join(format!("{}-result.json", ctx.subtest_uuids[1]));
+        let bad: Value = serde_json::from_str(&std::fs::read_to_string(bad_path).unwrap()).unwrap();
+        assert_eq!(bad["status"], "failed");
+        assert_eq!(bad["fullName"], "module::table::case bad");
+        assert_eq!(bad["parameters"][0]["value"], "x");
+        assert!(bad["labels"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|l| l["name"] == "epic" && l["value"] == "Parsing"));
+    }
+
+    #[test]
+    fn test_subtest_without_context_runs_body() {
+        let value = subtest("orphan", Vec::<(String, String)>::new(), || 5);
+        assert_eq!(value, Some(5));
+    }
+
+    #[test]
+    fn test_skip_closes_open_steps_and_unwinds() {
+        set_context(TestContext::new("skip", "module::skip"));
+        with_context(|ctx| {
+            ctx.start_step("outer");
+            ctx.start_step("inner");
+        });
+
+        let outcome = std::panic::catch_unwind(|| {
+            skip_if(false, "not taken");
+            skip("missing fixture");
+        });
+        let payload = outcome.unwrap_err();
+        assert_eq!(
+            skip_reason(payload.as_ref()).as_deref(),
+            Some("missing fixture")
+        );
+        assert_eq!(skip_reason(&"plain panic"), None);
+
+        let ctx = take_context().unwrap();
+        assert!(ctx.step_stack.is_empty());
+        let outer = &ctx.result.steps[0];
+        assert_eq!(outer.status, Status::Skipped);
+        assert_eq!(outer.steps[0].status, Status::Skipped);
+        assert_eq!(
+            outer.steps[0]
+                .status_details
+                .as_ref()
+                .unwrap()
+                .message
+                .as_deref(),
+            Some("missing fixture")
+        );
+    }
+
+    #[test]
+    fn test_skip_in_before_records_skipped_fixture() {
+        set_context(TestContext::new("fixture_skip", "module::fixture_skip"));
+        let outcome = std::panic::catch_unwind(|| {
+            before("Check service", || skip("service down"));
+        });
+        assert!(skip_reason(outcome.unwrap_err().as_ref()).is_some());
+
+        let ctx = take_context().unwrap();
+        assert!(ctx.result.steps.is_empty());
+        assert_eq!(ctx.befores.len(), 1);
+        assert_eq!(ctx.befores[0].status, Status::Skipped);
+    }
+
+    #[test]
+    fn test_failing_teardown_keeps_test_status() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = TestContext::new("teardown_fail", "module::teardown_fail");
+        ctx.writer = AllureWriter::with_results_dir(temp.path());
+        set_context(ctx);
+        teardown("Cleanup", || panic!("cleanup failed"));
+
+        let mut ctx = take_context().unwrap();
+        ctx.finish(Status::Passed, None, None);
+        assert_eq!(ctx.result.status, Status::Passed);
+        assert_eq!(ctx.afters[0].status, Status::Failed);
+        assert!(ctx.teardowns.is_empty());
+    }
+
+    #[test]
+    fn test_run_fixtures_collect_children_and_write_next_to_them() {
+        let temp = tempfile::tempdir().unwrap();
+        let writer = AllureWriter::with_results_dir(temp.path());
+
+        let mut setup = StepResult::new("Start server");
+        setup.pass();
+        let mut run = RunFixtures::new();
+        run.add_fixtures(vec![FixtureResult::from(setup)], Vec::new());
+
+        let mut first = TestResult::new(generate_uuid(), "first".to_string());
+        first.pass();
+        let mut second = TestResult::new(generate_uuid(), "second".to_string());
+        second.pass();
+        run.add_child(&writer, &first);
+        run.add_child(&writer, &second);
+        let path = temp
+            .path()
+            .join(format!("{}-container.json", run.container.uuid));
+        assert!(!path.exists(), "children don't rewrite the container");
+        run.flush();
+        let container: TestResultContainer =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(container.children, [first.uuid, second.uuid]);
+        assert_eq!(container.befores[0].name, "Start server");
+        assert!(container.start <= Some(first.start));
+        assert!(container.stop >= Some(second.stop));
+
+        // Unchanged since the last write
+        std::fs::remove_file(&path).unwrap();
+        run.flush();
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_finish_step_skipped_branch() {
         let mut ctx = TestContext::new("skip_step", "module::skip_step");
@@ -1281,6 +4127,7 @@ mod tests {
         CONFIG.get_or_init(|| AllureConfig {
             results_dir: temp.path().to_string_lossy().to_string(),
             clean_results: true,
+            ..Default::default()
         });
         let mut ctx = TestContext::new("unclosed", "module::unclosed");
         ctx.start_step("still running");
@@ -1302,6 +4149,7 @@ mod tests {
         CONFIG.get_or_init(|| AllureConfig {
             results_dir: temp.path().to_string_lossy().to_string(),
             clean_results: true,
+            ..Default::default()
         });
         let mut ctx = TestContext::new("broken_test", "module::broken_test");
         ctx.finish(Status::Broken, Some("fail".into()), Some("trace".into()));
@@ -0,0 +1,239 @@
+//! Soft assertions that collect failures instead of stopping the test.
+//!
+//! Every failed check is recorded as a failed step with `expected`/`actual`
+//! parameters. When the [`soft`] scope ends, the test fails with a message
+//! listing all collected failures.
+
+use std::fmt::Debug;
+
+use crate::enums::Status;
+use crate::runtime::with_context;
+
+/// Collector for soft assertion failures within a [`soft`] scope.
+#[derive(Debug, Default)]
+pub struct SoftAssertions {
+    failures: Vec<String>,
+}
+
+impl SoftAssertions {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks that a condition holds, recording a failure if it doesn't.
+    ///
+    /// Returns the condition so callers can branch on it.
+    pub fn check(&mut self, condition: bool, name: impl Into<String>) -> bool {
+        if !condition {
+            self.record(name.into(), "condition was false".to_string(), None);
+        }
+        condition
+    }
+
+    /// Checks that two values are equal, recording a failure if they aren't.
+    pub fn check_eq<A, E>(&mut self, name: impl Into<String>, actual: A, expected: E) -> bool
+    where
+        A: PartialEq<E> + Debug,
+        E: Debug,
+    {
+        let equal = actual == expected;
+        if !equal {
+            let expected = format!("{:?}", expected);
+            let actual = format!("{:?}", actual);
+            let message = format!("expected {}, got {}", expected, actual);
+            self.record(name.into(), message, Some((expected, actual)));
+        }
+        equal
+    }
+
+    /// Checks that two values differ, recording a failure if they are equal.
+    pub fn check_ne<A, E>(&mut self, name: impl Into<String>, actual: A, unexpected: E) -> bool
+    where
+        A: PartialEq<E> + Debug,
+        E: Debug,
+    {
+        let differ = actual != unexpected;
+        if !differ {
+            let expected = format!("not {:?}", unexpected);
+            let actual = format!("{:?}", actual);
+            let message = format!("expected {}, got {}", expected, actual);
+            self.record(name.into(), message, Some((expected, actual)));
+        }
+        differ
+    }
+
+    /// Records an unconditional failure.
+    pub fn fail(&mut self, name: impl Into<String>, message: impl Into<String>) {
+        self.record(name.into(), message.into(), None);
+    }
+
+    /// Returns the failure messages collected so far.
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    /// Returns `true` if no check has failed.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn record(&mut self, name: String, message: String, values: Option<(String, String)>) {
+        with_context(|ctx| {
+            ctx.start_step(name.clone());
+            if let Some((expected, actual)) = &values {
+                ctx.add_parameter("expected", expected.clone());
+                ctx.add_parameter("actual", actual.clone());
+            }
+            ctx.finish_step(Status::Failed, Some(message.clone()), None);
+        });
+        self.failures.push(format!("{}: {}", name, message));
+    }
+
+    /// Builds the aggregated failure message, or `None` if every check passed.
+    fn summary(&self) -> Option<String> {
+        if self.failures.is_empty() {
+            return None;
+        }
+        let mut summary = format!("{} soft assertion(s) failed:", self.failures.len());
+        for (index, failure) in self.failures.iter().enumerate() {
+            summary.push_str(&format!("\n  {}. {}", index + 1, failure));
+        }
+        Some(summary)
+    }
+}
+
+/// Runs a block of soft assertions and fails the test if any of them failed.
+///
+/// Checks inside the scope never stop execution. Once the body returns, all
+/// failures are reported together in a single panic message.
+///
+/// # Example
+///
+/// ```
+/// use allure_core::runtime::with_test_context;
+/// use allure_core::soft::soft;
+///
+/// with_test_context(|| {
+///     let status = 200;
+///     let body = "ok";
+///     soft(|s| {
+///         s.check_eq("status", status, 200);
+///         s.check(!body.is_empty(), "body is not empty");
+///     });
+/// });
+/// ```
+pub fn soft<F, R>(body: F) -> R
+where
+    F: FnOnce(&mut SoftAssertions) -> R,
+{
+    let mut assertions = SoftAssertions::new();
+    let value = body(&mut assertions);
+    if let Some(summary) = assertions.summary() {
+        panic!("{}", summary);
+    }
+    value
+}
+
+/// Soft version of `assert!` for use inside a [`soft`] scope.
+///
+/// ```
+/// use allure_core::{soft::soft, soft_assert};
+///
+/// soft(|s| {
+///     soft_assert!(s, 1 + 1 == 2);
+///     soft_assert!(s, "abc".len() == 3, "length of {}", "abc");
+/// });
+/// ```
+#[macro_export]
+macro_rules! soft_assert {
+    ($soft:expr, $cond:expr $(,)?) => {
+        $soft.check($cond, stringify!($cond))
+    };
+    ($soft:expr, $cond:expr, $($arg:tt)+) => {
+        $soft.check($cond, format!($($arg)+))
+    };
+}
+
+/// Soft version of `assert_eq!` for use inside a [`soft`] scope.
+///
+/// ```
+/// use allure_core::{soft::soft, soft_assert_eq};
+///
+/// soft(|s| {
+///     soft_assert_eq!(s, 2 + 2, 4);
+///     soft_assert_eq!(s, "a".repeat(2), "aa", "repeated string");
+/// });
+/// ```
+#[macro_export]
+macro_rules! soft_assert_eq {
+    ($soft:expr, $actual:expr, $expected:expr $(,)?) => {
+        $soft.check_eq(
+            concat!(stringify!($actual), " == ", stringify!($expected)),
+            $actual,
+            $expected,
+        )
+    };
+    ($soft:expr, $actual:expr, $expected:expr, $($arg:tt)+) => {
+        $soft.check_eq(format!($($arg)+), $actual, $expected)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{set_context, take_context, TestContext};
+
+    #[test]
+    fn test_soft_passes_when_all_checks_hold() {
+        let value = soft(|s| {
+            assert!(s.check(true, "truthy"));
+            assert!(s.check_eq("sum", 1 + 1, 2));
+            assert!(s.check_ne("diff", 1, 2));
+            42
+        });
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_soft_collects_all_failures_as_steps() {
+        set_context(TestContext::new("soft", "module::soft"));
+
+        let outcome = std::panic::catch_unwind(|| {
+            soft(|s| {
+                s.check_eq("status", 500, 200);
+                s.check(false, "body present");
+                crate::soft_assert_eq!(s, "a", "b");
+                s.check_eq("ok", 1, 1);
+            });
+        });
+
+        let panic = outcome.unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("3 soft assertion(s) failed:"));
+        assert!(message.contains("status: expected 200, got 500"));
+        assert!(message.contains("body present"));
+        assert!(message.contains("\"a\" == \"b\""));
+
+        let ctx = take_context().unwrap();
+        assert_eq!(ctx.result.steps.len(), 3);
+        let status = &ctx.result.steps[0];
+        assert_eq!(status.name, "status");
+        assert_eq!(status.status, Status::Failed);
+        assert_eq!(status.parameters[0].name, "expected");
+        assert_eq!(status.parameters[0].value, "200");
+        assert_eq!(status.parameters[1].name, "actual");
+        assert_eq!(status.parameters[1].value, "500");
+    }
+
+    #[test]
+    fn test_soft_assert_macro_forms() {
+        let mut s = SoftAssertions::new();
+        crate::soft_assert!(s, 1 > 2);
+        crate::soft_assert!(s, false, "custom {}", 7);
+        assert_eq!(s.failures().len(), 2);
+        assert!(s.failures()[0].starts_with("1 > 2"));
+        assert!(s.failures()[1].starts_with("custom 7"));
+        assert!(!s.is_ok());
+    }
+}
@@ -0,0 +1,167 @@
+//! Allure test plan support.
+//!
+//! Allure TestOps starts a run with `ALLURE_TESTPLAN_PATH` pointing at a
+//! `testplan.json` that lists the tests to run:
+//!
+//! ```json
+//! {
+//!   "version": "1.0",
+//!   "tests": [
+//!     { "id": "1024" },
+//!     { "selector": "api_tests::auth::test_login" }
+//!   ]
+//! }
+//! ```
+//!
+//! A test is selected when its `AS_ID` matches an `id` or its full name
+//! matches a `selector`. `#[allure_test]` functions outside the plan return
+//! before running and write no result. A plan that can't be read or parsed
+//! is reported on stderr and ignored, so every test runs.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::error::{AllureError, AllureResult};
+
+/// Environment variable holding the path of the test plan.
+pub const TESTPLAN_PATH_ENV: &str = "ALLURE_TESTPLAN_PATH";
+
+/// A parsed `testplan.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TestPlan {
+    /// Format version, `"1.0"` in current plans.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// The tests to run.
+    pub tests: Vec<TestPlanEntry>,
+}
+
+/// A test selected by a test plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TestPlanEntry {
+    /// Allure test case ID (`AS_ID`).
+    #[serde(default, deserialize_with = "deserialize_id")]
+    pub id: Option<String>,
+    /// Full name of the test.
+    #[serde(default)]
+    pub selector: Option<String>,
+}
+
+impl TestPlan {
+    /// Parses a test plan from JSON.
+    pub fn parse(json: &str) -> AllureResult<Self> {
+        let plan: TestPlan = serde_json::from_str(json)?;
+        if let Some(index) = plan
+            .tests
+            .iter()
+            .position(|entry| entry.id.is_none() && entry.selector.is_none())
+        {
+            return Err(AllureError::configuration(format!(
+                "test plan entry {} has neither an `id` nor a `selector`",
+                index
+            )));
+        }
+        Ok(plan)
+    }
+
+    /// Reads and parses the test plan at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> AllureResult<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns true if the plan selects the test with this full name and ID.
+    pub fn selects(&self, full_name: &str, allure_id: Option<&str>) -> bool {
+        self.tests.iter().any(|entry| {
+            entry.selector.as_deref() == Some(full_name)
+                || (allure_id.is_some() && entry.id.as_deref() == allure_id)
+        })
+    }
+}
+
+/// Accepts numeric IDs as well as strings.
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Text(String),
+        Number(u64),
+    }
+
+    Ok(Option::<Id>::deserialize(deserializer)?.map(|id| match id {
+        Id::Text(text) => text,
+        Id::Number(number) => number.to_string(),
+    }))
+}
+
+/// Returns the test plan of this run, loaded once from `ALLURE_TESTPLAN_PATH`.
+pub fn active() -> Option<&'static TestPlan> {
+    static PLAN: OnceLock<Option<TestPlan>> = OnceLock::new();
+    PLAN.get_or_init(|| {
+        let path = PathBuf::from(std::env::var_os(TESTPLAN_PATH_ENV)?);
+        match TestPlan::from_path(&path) {
+            Ok(plan) => Some(plan),
+            Err(e) => {
+                eprintln!(
+                    "allure: ignoring test plan {} from {}: {}; running all tests",
+                    path.display(),
+                    TESTPLAN_PATH_ENV,
+                    e
+                );
+                None
+            }
+        }
+    })
+    .as_ref()
+}
+
+/// Returns true if the active test plan, if any, selects the test.
+pub fn is_selected(full_name: &str, allure_id: Option<&str>) -> bool {
+    active().map_or(true, |plan| plan.selects(full_name, allure_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_selects_by_id_or_selector() {
+        let plan = TestPlan::parse(
+            r#"{
+                "version": "1.0",
+                "tests": [
+                    { "id": "1024", "selector": "api::test_other" },
+                    { "id": 7 },
+                    { "selector": "api::auth::test_login" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(plan.selects("api::auth::test_login", None));
+        assert!(plan.selects("api::renamed", Some("1024")));
+        assert!(plan.selects("api::numeric", Some("7")));
+        assert!(!plan.selects("api::auth::test_logout", Some("99")));
+        assert!(!plan.selects("api::auth::test_logout", None));
+    }
+
+    #[test]
+    fn test_malformed_plans_explain_the_problem() {
+        let err = TestPlan::parse(r#"{ "tests": [ { "id": "1" }, {} ] }"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Configuration error: test plan entry 1 has neither an `id` nor a `selector`"
+        );
+
+        let err = TestPlan::parse(r#"{ "tests": { "id": "1" } }"#).unwrap_err();
+        assert!(matches!(err, AllureError::Serialization(_)));
+        assert!(err.to_string().contains("line 1 column"), "{}", err);
+
+        let err = TestPlan::from_path("/nonexistent/testplan.json").unwrap_err();
+        assert!(matches!(err, AllureError::Io(_)));
+    }
+}
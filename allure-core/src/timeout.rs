@@ -0,0 +1,505 @@
+//! Time limits for test bodies.
+//!
+//! Sync bodies run on a worker thread while the test thread acts as a
+//! watchdog; async bodies race against a timer. When the limit is hit, the
+//! step in progress is marked broken as the culprit, the remaining open
+//! steps are closed, the result is written as broken and the test fails.
+//!
+//! A limit set inside an already watched body (for example a per-test
+//! `#[allure_timeout]` under the configured default) replaces the running
+//! limit instead of starting a second watchdog. Limits are measured from
+//! the start of the outermost watchdog.
+//!
+//! Bodies without a limit run inline on the test thread. All async timers
+//! share one background thread.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::pin::{pin, Pin};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::enums::Status;
+use crate::runtime::{
+    get_config, lock_shared, restore_context, set_watched_context, take_context, with_context,
+    SharedContext, TestContext,
+};
+
+/// Runs a sync test body with a time limit.
+///
+/// The body runs on a worker thread that shares the current test context.
+/// If it doesn't finish in time, the test is written as broken with a
+/// `timed out after Xms` message and this function panics with the same
+/// message. The hung worker is left behind so the rest of the run can go on.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use allure_core::timeout::run_with_timeout;
+///
+/// let value = run_with_timeout(Duration::from_secs(5), || 40 + 2);
+/// assert_eq!(value, 42);
+/// ```
+pub fn run_with_timeout<F, R>(timeout: Duration, body: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    if extend_watch(timeout) {
+        return body();
+    }
+
+    let handle: SharedContext = Arc::new(Mutex::new(take_context()));
+    if let Some(ctx) = lock_shared(&handle).as_mut() {
+        ctx.timeout = Some(timeout);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let worker_handle = handle.clone();
+    let mut builder = std::thread::Builder::new();
+    if let Some(name) = std::thread::current().name() {
+        builder = builder.name(name.to_string());
+    }
+    builder
+        .spawn(move || {
+            set_watched_context(Some(worker_handle));
+            let outcome = catch_unwind(AssertUnwindSafe(body));
+            set_watched_context(None);
+            let _ = sender.send(outcome);
+        })
+        .expect("failed to spawn test worker thread");
+
+    let started = Instant::now();
+    let outcome = loop {
+        let remaining = current_limit(&handle, timeout).saturating_sub(started.elapsed());
+        match receiver.recv_timeout(remaining) {
+            Ok(outcome) => break outcome,
+            Err(RecvTimeoutError::Timeout) => {
+                // The body may have raised its own limit in the meantime
+                let limit = current_limit(&handle, timeout);
+                if started.elapsed() >= limit {
+                    let message = timeout_message(limit);
+                    if let Some(mut ctx) = lock_shared(&handle).take() {
+                        time_out(&mut ctx, &message);
+                    }
+                    panic!("{}", message);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                panic!("test worker thread exited without reporting a result")
+            }
+        }
+    };
+
+    if let Some(mut ctx) = lock_shared(&handle).take() {
+        ctx.timeout = None;
+        restore_context(ctx);
+    }
+    match outcome {
+        Ok(value) => value,
+        Err(panic) => resume_unwind(panic),
+    }
+}
+
+/// Runs an async test body with a time limit.
+///
+/// The future is raced against a timer. If the timer wins, the test is
+/// written as broken with a `timed out after Xms` message and this function
+/// panics with the same message. Only futures that yield can be interrupted;
+/// a body that blocks its thread is out of reach.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use allure_core::timeout::with_timeout_async;
+///
+/// async fn fetch_status() -> u16 {
+///     200
+/// }
+///
+/// async fn test_status() {
+///     let status = with_timeout_async(Duration::from_secs(5), fetch_status()).await;
+///     assert_eq!(status, 200);
+/// }
+/// ```
+pub async fn with_timeout_async<F: Future>(timeout: Duration, fut: F) -> F::Output {
+    if extend_watch(timeout) {
+        return fut.await;
+    }
+
+    with_context(|ctx| ctx.timeout = Some(timeout));
+    let _reset = ResetTimeout;
+    let started = Instant::now();
+    let mut fut = pin!(fut);
+    loop {
+        let limit = context_limit().unwrap_or(timeout);
+        let race = Race {
+            fut: fut.as_mut(),
+            timer: Timer::new(limit.saturating_sub(started.elapsed())),
+        };
+        if let Some(output) = race.await {
+            return output;
+        }
+
+        // The body may have raised its own limit in the meantime
+        let limit = context_limit().unwrap_or(timeout);
+        if started.elapsed() >= limit {
+            let message = timeout_message(limit);
+            if let Some(mut ctx) = take_context() {
+                time_out(&mut ctx, &message);
+            }
+            panic!("{}", message);
+        }
+    }
+}
+
+/// Runs a sync test body under the configured default timeout, if any.
+///
+/// Used by `#[allure_test]`; see [`AllureConfigBuilder::default_timeout`].
+///
+/// [`AllureConfigBuilder::default_timeout`]: crate::runtime::AllureConfigBuilder::default_timeout
+pub fn run_with_default_timeout<F, R>(body: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    match get_config().default_timeout {
+        Some(timeout) => run_with_timeout(timeout, body),
+        None => body(),
+    }
+}
+
+/// A sync test body waiting to run under the configured default timeout.
+///
+/// Used by `#[allure_test]` so that the worker thread, and with it the
+/// `Send + 'static` bounds, only come into play when a default timeout is
+/// configured. Call `run_body` on a reference with [`RunOnWorker`] and
+/// [`RunInline`] in scope: bodies whose output can cross threads go through
+/// [`run_with_default_timeout`], the others always run inline. The choice is
+/// made before inference finishes, so the body's return type must be spelled
+/// out.
+#[doc(hidden)]
+pub struct DefaultTimeout<F>(Cell<Option<F>>);
+
+impl<F> DefaultTimeout<F> {
+    /// Wraps a test body.
+    pub fn new(body: F) -> Self {
+        Self(Cell::new(Some(body)))
+    }
+
+    fn take(&self) -> F {
+        self.0.take().expect("test body runs once")
+    }
+}
+
+/// Runs a [`DefaultTimeout`] body under the default timeout.
+#[doc(hidden)]
+pub trait RunOnWorker {
+    /// Value returned by the body.
+    type Output;
+
+    /// Runs the body.
+    fn run_body(&self) -> Self::Output;
+}
+
+impl<F, R> RunOnWorker for DefaultTimeout<F>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    type Output = R;
+
+    fn run_body(&self) -> R {
+        run_with_default_timeout(self.take())
+    }
+}
+
+/// Runs a [`DefaultTimeout`] body inline, for outputs that can't be sent
+/// back from a worker thread.
+#[doc(hidden)]
+pub trait RunInline {
+    /// Value returned by the body.
+    type Output;
+
+    /// Runs the body.
+    fn run_body(&self) -> Self::Output;
+}
+
+impl<F, R> RunInline for &DefaultTimeout<F>
+where
+    F: FnOnce() -> R,
+{
+    type Output = R;
+
+    fn run_body(&self) -> R {
+        self.take()()
+    }
+}
+
+/// Runs an async test body under the configured default timeout, if any.
+///
+/// Used by `#[allure_test]`; see [`AllureConfigBuilder::default_timeout`].
+///
+/// [`AllureConfigBuilder::default_timeout`]: crate::runtime::AllureConfigBuilder::default_timeout
+pub async fn with_default_timeout_async<F: Future>(fut: F) -> F::Output {
+    match get_config().default_timeout {
+        Some(timeout) => with_timeout_async(timeout, fut).await,
+        None => fut.await,
+    }
+}
+
+/// Replaces the limit of a running watchdog. Returns `false` if the current
+/// test isn't being watched.
+fn extend_watch(timeout: Duration) -> bool {
+    with_context(|ctx| match ctx.timeout {
+        Some(_) => {
+            ctx.timeout = Some(timeout);
+            true
+        }
+        None => false,
+    })
+    .unwrap_or(false)
+}
+
+fn current_limit(handle: &SharedContext, fallback: Duration) -> Duration {
+    lock_shared(handle)
+        .as_ref()
+        .and_then(|ctx| ctx.timeout)
+        .unwrap_or(fallback)
+}
+
+fn context_limit() -> Option<Duration> {
+    with_context(|ctx| ctx.timeout).flatten()
+}
+
+fn timeout_message(limit: Duration) -> String {
+    format!("timed out after {}ms", limit.as_millis())
+}
+
+/// Writes a timed-out test: the innermost open step is the culprit.
+fn time_out(ctx: &mut TestContext, message: &str) {
+    if !ctx.step_stack.is_empty() {
+        ctx.finish_step(Status::Broken, Some(message.to_string()), None);
+    }
+    ctx.timeout = None;
+    ctx.finish(Status::Broken, Some(message.to_string()), None);
+}
+
+/// Clears the watchdog limit when an async body finishes or panics.
+struct ResetTimeout;
+
+impl Drop for ResetTimeout {
+    fn drop(&mut self) {
+        with_context(|ctx| ctx.timeout = None);
+    }
+}
+
+/// Resolves to `Some(output)` if the future finishes first, `None` if the
+/// timer expires first.
+struct Race<'a, F: Future> {
+    fut: Pin<&'a mut F>,
+    timer: Timer,
+}
+
+impl<F: Future> Future for Race<'_, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = self.fut.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        if self.timer.poll_expired(cx) {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+#[derive(Default)]
+struct TimerState {
+    expired: bool,
+    waker: Option<Waker>,
+}
+
+type SharedTimerState = Arc<Mutex<TimerState>>;
+
+/// Runtime-agnostic timer served by the shared timer thread.
+struct Timer {
+    state: SharedTimerState,
+}
+
+impl Timer {
+    fn new(duration: Duration) -> Self {
+        let state = SharedTimerState::default();
+        let timers = timers();
+        lock(&timers.pending).push((Instant::now() + duration, state.clone()));
+        timers.changed.notify_one();
+        Self { state }
+    }
+
+    fn poll_expired(&self, cx: &mut Context<'_>) -> bool {
+        let mut state = lock(&self.state);
+        if !state.expired {
+            state.waker = Some(cx.waker().clone());
+        }
+        state.expired
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let timers = timers();
+        lock(&timers.pending).retain(|(_, state)| !Arc::ptr_eq(state, &self.state));
+        timers.changed.notify_one();
+    }
+}
+
+/// Deadlines of all running timers, served by one background thread.
+struct Timers {
+    pending: Mutex<Vec<(Instant, SharedTimerState)>>,
+    changed: Condvar,
+}
+
+fn timers() -> &'static Timers {
+    static TIMERS: OnceLock<Timers> = OnceLock::new();
+    TIMERS.get_or_init(|| {
+        std::thread::Builder::new()
+            .name("allure-timer".to_string())
+            .spawn(|| timers().run())
+            .expect("failed to spawn timer thread");
+        Timers {
+            pending: Mutex::new(Vec::new()),
+            changed: Condvar::new(),
+        }
+    })
+}
+
+impl Timers {
+    fn run(&self) {
+        let mut pending = lock(&self.pending);
+        loop {
+            let now = Instant::now();
+            let mut wakers = Vec::new();
+            pending.retain(|(deadline, state)| {
+                if *deadline > now {
+                    return true;
+                }
+                let mut state = lock(state);
+                state.expired = true;
+                wakers.extend(state.waker.take());
+                false
+            });
+            if !wakers.is_empty() {
+                drop(pending);
+                wakers.into_iter().for_each(Waker::wake);
+                pending = lock(&self.pending);
+                continue;
+            }
+
+            pending = match pending.iter().map(|(deadline, _)| *deadline).min() {
+                Some(next) => {
+                    self.changed
+                        .wait_timeout(pending, next.saturating_duration_since(now))
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(pending)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            };
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{set_context, step};
+
+    #[test]
+    fn test_run_with_timeout_returns_value_and_restores_context() {
+        set_context(TestContext::new("quick", "module::quick"));
+        let value = run_with_timeout(Duration::from_secs(5), || {
+            step("inside worker", || {});
+            7
+        });
+        assert_eq!(value, 7);
+
+        let ctx = take_context().expect("context should be restored");
+        assert_eq!(ctx.result.steps[0].name, "inside worker");
+        assert_eq!(ctx.timeout, None);
+    }
+
+    #[test]
+    fn test_run_with_timeout_marks_step_in_progress_as_culprit() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut ctx = TestContext::new("hung", "module::hung");
+        ctx.writer = crate::writer::AllureWriter::with_results_dir(temp.path());
+        set_context(ctx);
+
+        let outcome = catch_unwind(|| {
+            run_with_timeout(Duration::from_millis(50), || {
+                step("Wait for lock", || {
+                    std::thread::sleep(Duration::from_secs(5))
+                });
+            })
+        });
+        let panic = outcome.unwrap_err();
+        assert_eq!(
+            panic.downcast_ref::<String>().unwrap(),
+            "timed out after 50ms"
+        );
+        assert!(take_context().is_none());
+
+        let file = std::fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.to_string_lossy().ends_with("-result.json"))
+            .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(file).unwrap()).unwrap();
+        assert_eq!(json["status"], "broken");
+        assert_eq!(json["statusDetails"]["message"], "timed out after 50ms");
+        assert_eq!(json["steps"][0]["name"], "Wait for lock");
+        assert_eq!(json["steps"][0]["status"], "broken");
+        assert_eq!(
+            json["steps"][0]["statusDetails"]["message"],
+            "timed out after 50ms"
+        );
+    }
+
+    #[test]
+    fn test_default_timeout_runs_inline_without_limit() {
+        let caller = std::thread::current().id();
+        let sendable = DefaultTimeout::new(move || std::thread::current().id());
+        assert_eq!(sendable.run_body(), caller);
+
+        let local = DefaultTimeout::new(|| std::rc::Rc::new(7));
+        assert_eq!(*(&local).run_body(), 7);
+    }
+
+    #[test]
+    fn test_nested_limit_replaces_running_watchdog() {
+        set_context(TestContext::new("nested", "module::nested"));
+        let value = run_with_timeout(Duration::from_millis(20), || {
+            run_with_timeout(Duration::from_secs(5), || {
+                std::thread::sleep(Duration::from_millis(60));
+                "done"
+            })
+        });
+        assert_eq!(value, "done");
+        assert!(take_context().is_some());
+    }
+}
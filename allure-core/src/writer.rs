@@ -6,14 +6,35 @@ use std::path::{Path, PathBuf};
 
 use crate::enums::ContentType;
 use crate::model::{Attachment, Category, TestResult, TestResultContainer};
+use crate::redact::Redactor;
+
+/// Returns whether attachments of this content type are text, and so are
+/// redacted.
+fn is_textual(content_type: ContentType) -> bool {
+    matches!(
+        content_type,
+        ContentType::Text
+            | ContentType::Json
+            | ContentType::Xml
+            | ContentType::Html
+            | ContentType::Csv
+            | ContentType::Tsv
+            | ContentType::Css
+            | ContentType::Uri
+    )
+}
 
 /// Default directory for Allure results.
 pub const DEFAULT_RESULTS_DIR: &str = "allure-results";
 
+/// Length past which a streamed line is written in pieces.
+const MAX_PENDING_LINE: usize = 64 * 1024;
+
 /// Writer for Allure test result files.
 #[derive(Debug, Clone)]
 pub struct AllureWriter {
     results_dir: PathBuf,
+    redactor: Redactor,
 }
 
 impl AllureWriter {
@@ -26,20 +47,58 @@ impl AllureWriter {
     pub fn with_results_dir(path: impl AsRef<Path>) -> Self {
         Self {
             results_dir: path.as_ref().to_path_buf(),
+            redactor: Redactor::new(),
         }
     }
 
+    /// Sets the redactor applied to results and attachments before they are
+    /// written, see [`crate::redact`].
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
     /// Returns the results directory path.
     pub fn results_dir(&self) -> &Path {
         &self.results_dir
     }
 
     /// Initializes the results directory, optionally cleaning it first.
+    ///
+    /// Cleaning only removes Allure's own files, see [`AllureWriter::clean`].
     pub fn init(&self, clean: bool) -> io::Result<()> {
-        if clean && self.results_dir.exists() {
-            fs::remove_dir_all(&self.results_dir)?;
-        }
         fs::create_dir_all(&self.results_dir)?;
+        if clean {
+            self.clean(false)?;
+        }
+        Ok(())
+    }
+
+    /// Removes previous results from the results directory.
+    ///
+    /// Only files written by Allure (see [`is_allure_file`]) are removed,
+    /// unless `all` is set, in which case every entry goes except the files
+    /// that coordinate cleaning between the processes of a run. Either way,
+    /// the filesystem root, the home directory and directories holding a
+    /// `Cargo.toml` or `.git` are never cleaned.
+    pub fn clean(&self, all: bool) -> io::Result<()> {
+        check_cleanable(&self.results_dir)?;
+        for entry in fs::read_dir(&self.results_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name == crate::run::MARKER_FILE || name == crate::run::LOCK_FILE {
+                continue;
+            }
+            let is_dir = entry.file_type()?.is_dir();
+            if !all && (is_dir || !name.to_str().is_some_and(is_allure_file)) {
+                continue;
+            }
+            if is_dir {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
         Ok(())
     }
 
@@ -52,11 +111,15 @@ impl AllureWriter {
     }
 
     /// Writes a test result to a JSON file.
+    ///
+    /// Secrets are redacted and masked parameters replaced in the file.
     pub fn write_test_result(&self, result: &TestResult) -> io::Result<PathBuf> {
         self.ensure_dir()?;
         let filename = format!("{}-result.json", result.uuid);
         let path = self.results_dir.join(&filename);
-        let json = serde_json::to_string_pretty(result)
+        let mut result = result.clone();
+        self.redactor.redact_result(&mut result);
+        let json = serde_json::to_string_pretty(&result)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         fs::write(&path, json)?;
         Ok(path)
@@ -67,7 +130,11 @@ impl AllureWriter {
         self.ensure_dir()?;
         let filename = format!("{}-container.json", container.uuid);
         let path = self.results_dir.join(&filename);
-        let json = serde_json::to_string_pretty(container)
+        let mut container = container.clone();
+        for fixture in container.befores.iter_mut().chain(&mut container.afters) {
+            self.redactor.redact_fixture(fixture);
+        }
+        let json = serde_json::to_string_pretty(&container)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         fs::write(&path, json)?;
         Ok(path)
@@ -83,7 +150,7 @@ impl AllureWriter {
         let uuid = uuid::Uuid::new_v4().to_string();
         let filename = format!("{}-attachment.txt", uuid);
         let path = self.results_dir.join(&filename);
-        fs::write(&path, content.as_ref())?;
+        fs::write(&path, self.redactor.redact(content.as_ref()).as_bytes())?;
         Ok(Attachment::new(
             name,
             filename,
@@ -101,7 +168,10 @@ impl AllureWriter {
         let uuid = uuid::Uuid::new_v4().to_string();
         let filename = format!("{}-attachment.json", uuid);
         let path = self.results_dir.join(&filename);
-        let json = serde_json::to_string_pretty(value)
+        let mut value = serde_json::to_value(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.redactor.redact_json(&mut value);
+        let json = serde_json::to_string_pretty(&value)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         fs::write(&path, json)?;
         Ok(Attachment::new(
@@ -112,6 +182,9 @@ impl AllureWriter {
     }
 
     /// Writes a binary attachment and returns the Attachment reference.
+    ///
+    /// Textual content, such as `Text`, `Json` or `Csv`, is redacted when it
+    /// is valid UTF-8.
     pub fn write_binary_attachment(
         &self,
         name: impl Into<String>,
@@ -122,7 +195,12 @@ impl AllureWriter {
         let uuid = uuid::Uuid::new_v4().to_string();
         let filename = format!("{}-attachment.{}", uuid, content_type.extension());
         let path = self.results_dir.join(&filename);
-        fs::write(&path, content)?;
+        match std::str::from_utf8(content) {
+            Ok(text) if is_textual(content_type) => {
+                fs::write(&path, self.redactor.redact(text).as_bytes())?
+            }
+            _ => fs::write(&path, content)?,
+        }
         Ok(Attachment::new(
             name,
             filename,
@@ -146,7 +224,38 @@ impl AllureWriter {
         Ok(Attachment::new(name, filename, Some(mime_type.into())))
     }
 
+    /// Creates an attachment file to be written in chunks, for content too
+    /// large to hold in memory.
+    ///
+    /// Textual content is redacted line by line, so a secret spanning a
+    /// line break is not replaced. Lines longer than 64 KiB are flushed in
+    /// pieces. Secrets and pattern matches up to 1 KiB are never split
+    /// across pieces.
+    ///
+    /// Call [`AttachmentStream::finish`] to get the Attachment reference.
+    pub fn start_attachment(
+        &self,
+        name: impl Into<String>,
+        content_type: ContentType,
+    ) -> io::Result<AttachmentStream> {
+        self.ensure_dir()?;
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let filename = format!("{}-attachment.{}", uuid, content_type.extension());
+        let file = File::create(self.results_dir.join(&filename))?;
+        let redactor =
+            (is_textual(content_type) && !self.redactor.is_empty()).then(|| self.redactor.clone());
+        Ok(AttachmentStream {
+            file: io::BufWriter::new(file),
+            attachment: Attachment::new(name, filename, Some(content_type.as_mime().to_string())),
+            redactor,
+            pending: Vec::new(),
+        })
+    }
+
     /// Copies a file as an attachment and returns the Attachment reference.
+    ///
+    /// Textual files, by `content_type` or else by extension, are redacted
+    /// when they are valid UTF-8; other files are copied as they are.
     pub fn copy_file_attachment(
         &self,
         name: impl Into<String>,
@@ -163,11 +272,23 @@ impl AllureWriter {
         let uuid = uuid::Uuid::new_v4().to_string();
         let filename = format!("{}-attachment.{}", uuid, extension);
         let dest_path = self.results_dir.join(&filename);
-        fs::copy(source, &dest_path)?;
 
         let mime = content_type
             .map(|ct| ct.as_mime().to_string())
             .or_else(|| guess_mime_type(extension));
+        let textual = match content_type {
+            Some(content_type) => is_textual(content_type),
+            None => mime.as_deref().is_some_and(is_textual_mime),
+        };
+        if textual && !self.redactor.is_empty() {
+            let content = fs::read(source)?;
+            match std::str::from_utf8(&content) {
+                Ok(text) => fs::write(&dest_path, self.redactor.redact(text).as_bytes())?,
+                Err(_) => fs::write(&dest_path, &content)?,
+            }
+        } else {
+            fs::copy(source, &dest_path)?;
+        }
 
         Ok(Attachment::new(name, filename, mime))
     }
@@ -198,6 +319,95 @@ impl AllureWriter {
     }
 }
 
+/// An attachment file written in chunks, created with
+/// [`AllureWriter::start_attachment`].
+///
+/// Dropping it without calling [`finish`](Self::finish) leaves the file
+/// incomplete and unreferenced.
+#[derive(Debug)]
+pub struct AttachmentStream {
+    file: io::BufWriter<File>,
+    attachment: Attachment,
+    redactor: Option<Redactor>,
+    /// Text after the last line break, held back until the line is complete.
+    pending: Vec<u8>,
+}
+
+impl AttachmentStream {
+    /// Returns the reference to the attachment being written.
+    pub fn attachment(&self) -> &Attachment {
+        &self.attachment
+    }
+
+    /// Writes the remaining content and returns the Attachment reference.
+    pub fn finish(mut self) -> io::Result<Attachment> {
+        let pending = std::mem::take(&mut self.pending);
+        self.write_redacted(&pending)?;
+        self.file.flush()?;
+        Ok(self.attachment)
+    }
+
+    /// Writes all of an unfinished line but the end a secret could still
+    /// continue from.
+    fn write_long_line(&mut self) -> io::Result<()> {
+        let Some(redactor) = &self.redactor else {
+            return Ok(());
+        };
+        let keep = redactor.max_match_len().saturating_sub(1);
+        let mut at = self.pending.len().saturating_sub(keep);
+        // A chunk may end in the middle of a character
+        let text = match std::str::from_utf8(&self.pending) {
+            Ok(text) => Some(text),
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&self.pending[..e.valid_up_to()]).ok()
+            }
+            Err(_) => None,
+        };
+        if let Some(text) = text {
+            at = at.min(text.len());
+            while !text.is_char_boundary(at) {
+                at -= 1;
+            }
+            at = redactor.split_point(text, at);
+        }
+        if at == 0 {
+            return Ok(());
+        }
+        let rest = self.pending.split_off(at);
+        let line = std::mem::replace(&mut self.pending, rest);
+        self.write_redacted(&line)
+    }
+
+    fn write_redacted(&mut self, chunk: &[u8]) -> io::Result<()> {
+        match (&self.redactor, std::str::from_utf8(chunk)) {
+            (Some(redactor), Ok(text)) => self.file.write_all(redactor.redact(text).as_bytes()),
+            _ => self.file.write_all(chunk),
+        }
+    }
+}
+
+impl Write for AttachmentStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.redactor.is_none() {
+            return self.file.write(buf);
+        }
+        self.pending.extend_from_slice(buf);
+        if let Some(end) = self.pending.iter().rposition(|&byte| byte == b'\n') {
+            let rest = self.pending.split_off(end + 1);
+            let lines = std::mem::replace(&mut self.pending, rest);
+            self.write_redacted(&lines)?;
+        }
+        if self.pending.len() > MAX_PENDING_LINE {
+            self.write_long_line()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
 impl Default for AllureWriter {
     fn default() -> Self {
         Self::new()
@@ -241,6 +451,11 @@ fn guess_mime_type(extension: &str) -> Option<String> {
     }
 }
 
+/// Whether a guessed MIME type is text that may hold secrets.
+fn is_textual_mime(mime: &str) -> bool {
+    mime.starts_with("text/") || mime == "application/json" || mime == "application/xml"
+}
+
 /// Generates a new UUID v4 string.
 pub fn generate_uuid() -> String {
     uuid::Uuid::new_v4().to_string()
@@ -265,6 +480,44 @@ pub fn compute_history_id(full_name: &str, parameters: &[crate::model::Parameter
     format!("{:x}", hasher.finalize())
 }
 
+/// Returns whether a file name is one Allure writes into a results directory.
+pub fn is_allure_file(name: &str) -> bool {
+    matches!(
+        name,
+        "environment.properties" | "categories.json" | "executor.json"
+    ) || name.ends_with("-result.json")
+        || name.ends_with("-container.json")
+        || name
+            .rsplit_once('.')
+            .is_some_and(|(stem, _)| stem.ends_with("-attachment"))
+}
+
+/// Fails if `dir` is a directory that must never be cleaned.
+pub(crate) fn check_cleanable(dir: &Path) -> io::Result<()> {
+    let dir = dir.canonicalize()?;
+    let refuse = |reason: &str| {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("refusing to clean {}: {}", dir.display(), reason),
+        ))
+    };
+    if dir.parent().is_none() {
+        return refuse("it is the filesystem root");
+    }
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .and_then(|home| PathBuf::from(home).canonicalize().ok());
+    if home.as_deref() == Some(dir.as_path()) {
+        return refuse("it is the home directory");
+    }
+    for marker in ["Cargo.toml", ".git"] {
+        if dir.join(marker).exists() {
+            return refuse(&format!("it contains {}", marker));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,6 +540,60 @@ mod tests {
         fs::remove_dir_all(&dir).ok();
     }
 
+    #[test]
+    fn test_clean_removes_only_allure_files() {
+        let dir = temp_dir();
+        let writer = AllureWriter::with_results_dir(&dir);
+        writer.init(false).unwrap();
+        let allure_files = [
+            "a1-result.json",
+            "b2-container.json",
+            "c3-attachment.txt",
+            "d4-attachment.png",
+            "environment.properties",
+            "categories.json",
+            "executor.json",
+        ];
+        for name in allure_files {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        fs::write(dir.join("notes.md"), "keep").unwrap();
+        fs::write(dir.join("result.json"), "keep").unwrap();
+        fs::create_dir(dir.join("history")).unwrap();
+
+        writer.init(true).unwrap();
+        for name in allure_files {
+            assert!(!dir.join(name).exists(), "{} should be removed", name);
+        }
+        assert!(dir.join("notes.md").exists());
+        assert!(dir.join("result.json").exists());
+        assert!(dir.join("history").exists());
+
+        writer.clean(true).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_clean_refuses_project_directories() {
+        for marker in ["Cargo.toml", ".git"] {
+            let dir = temp_dir();
+            fs::create_dir_all(dir.join(marker)).unwrap();
+            fs::write(dir.join("a1-result.json"), "{}").unwrap();
+
+            let writer = AllureWriter::with_results_dir(&dir);
+            let err = writer.init(true).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+            assert!(err.to_string().contains(marker), "{}", err);
+            assert!(writer.clean(true).is_err());
+            assert!(dir.join("a1-result.json").exists());
+            fs::remove_dir_all(&dir).ok();
+        }
+
+        let root = AllureWriter::with_results_dir("/");
+        assert!(root.clean(false).unwrap_err().to_string().contains("root"));
+    }
+
     #[test]
     fn test_write_test_result() {
         let dir = temp_dir();
@@ -371,6 +678,65 @@ mod tests {
         fs::remove_dir_all(&dir).ok();
     }
 
+    #[test]
+    fn test_copy_file_attachment_redacts_text_files() {
+        let dir = temp_dir();
+        let writer =
+            AllureWriter::with_results_dir(&dir).with_redactor(Redactor::new().secret("hunter2"));
+        let source = tempfile::tempdir().unwrap();
+
+        let log = source.path().join("server.log");
+        fs::write(&log, "password=hunter2\n").unwrap();
+        let attachment = writer.copy_file_attachment("Log", &log, None).unwrap();
+        assert_eq!(attachment.r#type.as_deref(), Some("text/plain"));
+        let content = fs::read_to_string(dir.join(&attachment.source)).unwrap();
+        assert_eq!(content, "password=[REDACTED]\n");
+
+        let image = source.path().join("screen.png");
+        fs::write(&image, "hunter2").unwrap();
+        let attachment = writer.copy_file_attachment("Screen", &image, None).unwrap();
+        assert_eq!(fs::read(dir.join(&attachment.source)).unwrap(), b"hunter2");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_start_attachment_streams_and_redacts_by_line() {
+        let dir = temp_dir();
+        let writer =
+            AllureWriter::with_results_dir(&dir).with_redactor(Redactor::new().secret("hunter2"));
+
+        let mut stream = writer.start_attachment("Log", ContentType::Text).unwrap();
+        stream.write_all(b"login with hun").unwrap();
+        stream.write_all(b"ter2\nretry ").unwrap();
+        stream.write_all(b"hunter2").unwrap();
+        let attachment = stream.finish().unwrap();
+        assert_eq!(attachment.name, "Log");
+        assert_eq!(attachment.r#type.as_deref(), Some("text/plain"));
+        let content = fs::read_to_string(dir.join(&attachment.source)).unwrap();
+        assert_eq!(content, "login with [REDACTED]\nretry [REDACTED]");
+
+        // A line without breaks is written in pieces, redacted across them
+        let mut stream = writer.start_attachment("Dump", ContentType::Text).unwrap();
+        let filler = "x".repeat(1000);
+        for _ in 0..200 {
+            stream.write_all(filler.as_bytes()).unwrap();
+            stream.write_all(b"hunter2").unwrap();
+            assert!(stream.pending.len() <= MAX_PENDING_LINE + 1007);
+        }
+        let attachment = stream.finish().unwrap();
+        let content = fs::read_to_string(dir.join(&attachment.source)).unwrap();
+        assert_eq!(content, format!("{}[REDACTED]", filler).repeat(200));
+
+        let mut stream = writer.start_attachment("Image", ContentType::Png).unwrap();
+        stream.write_all(b"hunter2").unwrap();
+        let attachment = stream.finish().unwrap();
+        assert!(attachment.source.ends_with(".png"));
+        assert_eq!(fs::read(dir.join(&attachment.source)).unwrap(), b"hunter2");
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_write_environment() {
         let dir = temp_dir();
@@ -4,8 +4,13 @@
 //!
 //! - `#[allure_test]` - Wraps a test function with Allure tracking
 //! - `#[step]` - Marks a function as an Allure step
+//! - `#[allure_fixture]` - Records a fixture function as a setup fixture
+//! - `#[allure_before_all]` / `#[allure_after_all]` - Record binary-wide setup and teardown
 //! - `#[allure_suite]` - Groups tests in a module under a suite
 //! - Metadata annotations: `#[epic]`, `#[feature]`, `#[story]`, `#[severity]`, etc.
+//! - `#[allure_skip_if]` - Skips a test at runtime based on an environment variable
+//! - `#[allure_retry]` - Reruns a failing test, reporting each attempt
+//! - `#[allure_timeout]` - Fails a test that runs too long, reporting it as broken
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
@@ -86,14 +91,41 @@ fn is_result_return(output: &ReturnType) -> bool {
     }
 }
 
+/// Checks if the return type is `Result<(), E>`, so a skipped test can return `Ok(())`
+fn is_unit_result_return(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let Type::Path(type_path) = ty.as_ref() else {
+        return false;
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return false;
+    };
+    if segment.ident != "Result" {
+        return false;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => matches!(
+            args.args.first(),
+            Some(syn::GenericArgument::Type(Type::Tuple(tuple))) if tuple.elems.is_empty()
+        ),
+        _ => false,
+    }
+}
+
 fn ignore_async_block(
     has_ignore: bool,
     ignore_reason_expr: &proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     quote! {
-        if ::std::hint::black_box(#has_ignore) {
+        if ::std::hint::black_box(#has_ignore)
+            && !::allure_core::harness::runs_ignored_tests()
+        {
             let reason = #ignore_reason_expr.unwrap_or_else(|| "Ignored test".to_string());
-            ::allure_core::runtime::skip(reason);
+            if let Some(mut ctx) = take_context() {
+                ctx.finish(Status::Skipped, Some(reason), None);
+            }
             return Ok(None);
         }
     }
@@ -105,7 +137,9 @@ fn ignore_finish_block(
     return_stmt: proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     quote! {
-        if ::std::hint::black_box(#has_ignore) {
+        if ::std::hint::black_box(#has_ignore)
+            && !::allure_core::harness::runs_ignored_tests()
+        {
             let reason = #ignore_reason_expr.unwrap_or_else(|| "Ignored test".to_string());
             if let Some(mut ctx) = take_context() {
                 ctx.finish(Status::Skipped, Some(reason), None);
@@ -115,6 +149,321 @@ fn ignore_finish_block(
     }
 }
 
+/// Metadata that the attributes before `#[allure_test]` declared with literals.
+#[derive(Default)]
+struct StaticMetadata {
+    title: Option<String>,
+    labels: Vec<(String, String)>,
+    links: Vec<(String, String, Option<String>)>,
+    allure_id: Option<String>,
+    flaky: bool,
+    muted: bool,
+}
+
+/// Collects the metadata from the runtime calls that metadata attributes
+/// injected into the body.
+///
+/// Attribute macros expand from the outside in, so by the time
+/// `#[allure_test]` runs the attributes above it have turned into
+/// `::allure_core::runtime::*` calls, possibly wrapped by `#[allure_timeout]`
+/// closures or `#[allure_retry]` loops.
+fn collect_static_metadata(block: &syn::Block) -> StaticMetadata {
+    let mut metadata = StaticMetadata::default();
+    visit_block(block, &mut metadata);
+    metadata
+}
+
+fn visit_block(block: &syn::Block, metadata: &mut StaticMetadata) {
+    for stmt in &block.stmts {
+        match stmt {
+            syn::Stmt::Expr(expr, _) => visit_expr(expr, metadata),
+            syn::Stmt::Local(local) => {
+                if let Some(init) = &local.init {
+                    visit_expr(&init.expr, metadata);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn visit_expr(expr: &syn::Expr, metadata: &mut StaticMetadata) {
+    match expr {
+        syn::Expr::Block(expr) => visit_block(&expr.block, metadata),
+        syn::Expr::Async(expr) => visit_block(&expr.block, metadata),
+        syn::Expr::Loop(expr) => visit_block(&expr.body, metadata),
+        syn::Expr::Closure(expr) => visit_expr(&expr.body, metadata),
+        syn::Expr::Await(expr) => visit_expr(&expr.base, metadata),
+        syn::Expr::Paren(expr) => visit_expr(&expr.expr, metadata),
+        syn::Expr::Call(call) => match runtime_function(&call.func) {
+            Some(function) => record_runtime_call(&function, &call.args, metadata),
+            None => call.args.iter().for_each(|arg| visit_expr(arg, metadata)),
+        },
+        _ => {}
+    }
+}
+
+/// Name of the function when `func` is a `::allure_core::runtime::<name>` path.
+fn runtime_function(func: &syn::Expr) -> Option<String> {
+    let syn::Expr::Path(path) = func else {
+        return None;
+    };
+    let segments: Vec<String> = path
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    match segments.as_slice() {
+        [krate, module, function]
+            if path.path.leading_colon.is_some()
+                && krate == "allure_core"
+                && module == "runtime" =>
+        {
+            Some(function.clone())
+        }
+        _ => None,
+    }
+}
+
+fn record_runtime_call(
+    function: &str,
+    args: &Punctuated<syn::Expr, Token![,]>,
+    metadata: &mut StaticMetadata,
+) {
+    let first = args.first();
+    match function {
+        "epic" | "feature" | "story" | "suite" | "parent_suite" | "sub_suite" | "owner" | "tag" => {
+            let name = match function {
+                "parent_suite" => "parentSuite",
+                "sub_suite" => "subSuite",
+                other => other,
+            };
+            if let Some(value) = first.and_then(string_literal) {
+                metadata.labels.push((name.to_string(), value));
+            }
+        }
+        "severity" => {
+            if let Some(syn::Expr::Path(path)) = first {
+                if let Some(variant) = path.path.segments.last() {
+                    let value = variant.ident.to_string().to_lowercase();
+                    metadata.labels.push(("severity".to_string(), value));
+                }
+            }
+        }
+        "allure_id" => metadata.allure_id = first.and_then(string_literal),
+        "flaky" => metadata.flaky = true,
+        "muted" => metadata.muted = true,
+        "title" => metadata.title = first.and_then(string_literal),
+        "issue" | "tms" | "link" | "typed_link" => {
+            // The name is either `None` or `Some("...".to_string())`
+            let name = match args.iter().nth(1) {
+                Some(syn::Expr::Call(call)) => match call.args.first() {
+                    Some(syn::Expr::MethodCall(method)) => string_literal(&method.receiver),
+                    _ => None,
+                },
+                _ => None,
+            };
+            // A typed link's type is `LinkType::custom("...")`
+            let link_type = match (function, args.iter().nth(2)) {
+                ("typed_link", Some(syn::Expr::Call(call))) => {
+                    call.args.first().and_then(string_literal)
+                }
+                ("typed_link", _) => None,
+                (function, _) => Some(function.to_string()),
+            };
+            if let (Some(url), Some(link_type)) = (first.and_then(string_literal), link_type) {
+                metadata.links.push((link_type, url, name));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn string_literal(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s.value()),
+        _ => None,
+    }
+}
+
+/// Statement that ends a test which must not run, or `None` if it can't.
+///
+/// Excluded tests must still pass under libtest: `#[should_panic]` tests
+/// panic with the expected message, and tests returning a value other than
+/// `()` or `Result<(), E>` always run.
+fn early_exit(
+    native_attrs: &NativeTestAttrs,
+    output: &ReturnType,
+    reason: &str,
+) -> Option<proc_macro2::TokenStream> {
+    if native_attrs.has_should_panic {
+        let message = native_attrs
+            .should_panic_expected
+            .as_deref()
+            .unwrap_or(reason);
+        Some(quote! { panic!("{}", #message); })
+    } else if matches!(output, ReturnType::Default) {
+        Some(quote! { return; })
+    } else if is_unit_result_return(output) {
+        Some(quote! { return Ok(()); })
+    } else {
+        None
+    }
+}
+
+/// Returns early from a test that the Allure test plan doesn't select.
+fn testplan_check(
+    fn_name: &str,
+    metadata: &StaticMetadata,
+    native_attrs: &NativeTestAttrs,
+    output: &ReturnType,
+) -> proc_macro2::TokenStream {
+    let Some(exit) = early_exit(native_attrs, output, "excluded by the Allure test plan") else {
+        return quote! {};
+    };
+    let allure_id = match &metadata.allure_id {
+        Some(id) => quote! { Some(#id) },
+        None => quote! { None },
+    };
+
+    quote! {
+        // Tests outside the Allure test plan neither run nor write a result
+        if !::allure_core::testplan::is_selected(
+            concat!(module_path!(), "::", #fn_name),
+            #allure_id,
+        ) {
+            #exit
+        }
+    }
+}
+
+/// Labels and statuses that the filter sees before the body adds them.
+fn static_filter_labels(metadata: &StaticMetadata) -> proc_macro2::TokenStream {
+    let mut labels: Vec<_> = metadata
+        .labels
+        .iter()
+        .map(|(name, value)| quote! { (#name, #value) })
+        .collect();
+    if metadata.flaky {
+        labels.push(quote! { ("status", "flaky") });
+    }
+    if metadata.muted {
+        labels.push(quote! { ("status", "muted") });
+    }
+    quote! { &[#(#labels),*] }
+}
+
+/// Submits the test and its static metadata to the registry.
+///
+/// Tests that take arguments or generics can't be run from the registry,
+/// and async tests with a runtime attribute (such as `#[tokio::test]`) only
+/// run through that attribute; both are registered without a runner.
+fn registration(
+    input: &ItemFn,
+    runner: &syn::Ident,
+    test_name: &str,
+    metadata: &StaticMetadata,
+    native_attrs: &NativeTestAttrs,
+    has_test_attr: bool,
+) -> proc_macro2::TokenStream {
+    let sig = &input.sig;
+    let is_async = sig.asyncness.is_some();
+    let runnable =
+        sig.inputs.is_empty() && sig.generics.params.is_empty() && !(is_async && has_test_attr);
+
+    let fn_name_str = sig.ident.to_string();
+    let display_name = metadata.title.as_deref().unwrap_or(test_name);
+    let labels = metadata
+        .labels
+        .iter()
+        .map(|(name, value)| quote! { (#name, #value) });
+    let links = metadata.links.iter().map(|(link_type, url, name)| {
+        // Built-in names give the built-in types
+        let link_type = quote! { ::allure_core::enums::LinkType::custom(#link_type) };
+        let name = match name {
+            Some(name) => quote! { Some(#name) },
+            None => quote! { None },
+        };
+        quote! {
+            ::allure_core::registry::LinkDescriptor {
+                link_type: #link_type,
+                url: #url,
+                name: #name,
+            }
+        }
+    });
+    let allure_id = match &metadata.allure_id {
+        Some(id) => quote! { Some(#id) },
+        None => quote! { None },
+    };
+    // Spanned so that `line!()` points at the test function
+    let line = quote::quote_spanned! { sig.ident.span()=> line!() };
+
+    let has_ignore = native_attrs.has_ignore;
+    let ignore_reason = match &native_attrs.ignore_reason {
+        Some(reason) => quote! { Some(#reason) },
+        None => quote! { None },
+    };
+    let should_panic = match (
+        native_attrs.has_should_panic,
+        &native_attrs.should_panic_expected,
+    ) {
+        (false, _) => quote! { ::allure_core::registry::ShouldPanic::No },
+        (true, None) => quote! { ::allure_core::registry::ShouldPanic::Yes },
+        (true, Some(expected)) => {
+            quote! { ::allure_core::registry::ShouldPanic::YesWithMessage(#expected) }
+        }
+    };
+
+    let run = if runnable {
+        // Sync tests run through their runner; async tests are registered directly
+        let call = if is_async {
+            let fn_name = &sig.ident;
+            quote! { ::allure_core::harness::block_on(#fn_name()) }
+        } else {
+            quote! { #runner() }
+        };
+        let body = if is_result_return(&sig.output) && !native_attrs.has_should_panic {
+            quote! {
+                match #call {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(format!("{:?}", e)),
+                }
+            }
+        } else {
+            quote! {
+                let _ = #call;
+                Ok(())
+            }
+        };
+        quote! { Some(|| { #body }) }
+    } else {
+        quote! { None }
+    };
+
+    quote! {
+        ::allure_core::inventory::submit! {
+            ::allure_core::registry::TestDescriptor {
+                name: #fn_name_str,
+                module_path: module_path!(),
+                display_name: #display_name,
+                labels: &[#(#labels),*],
+                links: &[#(#links),*],
+                allure_id: #allure_id,
+                file: file!(),
+                line: #line,
+                ignore: #has_ignore,
+                ignore_reason: #ignore_reason,
+                should_panic: #should_panic,
+                run: #run,
+            }
+        }
+    }
+}
+
 fn capture_panic_trace_expr() -> proc_macro2::TokenStream {
     quote! {{
         let bt = std::backtrace::Backtrace::force_capture();
@@ -195,12 +544,50 @@ fn expand_allure_test(
                 .is_some_and(|seg| seg.ident == "test")
     });
 
+    // Outside of libtest (`harness = false`) the function must survive so
+    // that the harness can run it from the registry
     let test_attr = if has_test_attr {
         quote! {}
     } else {
         quote! { #[test] }
     };
 
+    // The body lives in a hidden runner that the `#[test]` function calls.
+    // Without libtest (`harness = false`) the `#[test]` function is removed,
+    // but the runner stays and is registered for the Allure harness.
+    let runner = quote::format_ident!("__allure_run_{}", fn_name);
+    let runner_attrs = attrs.iter().filter(|attr| {
+        [
+            "allow", "warn", "deny", "forbid", "expect", "cfg", "cfg_attr",
+        ]
+        .iter()
+        .any(|lint| attr.path().is_ident(lint))
+    });
+    let static_metadata = collect_static_metadata(block);
+    let register = registration(
+        &input,
+        &runner,
+        &test_name,
+        &static_metadata,
+        &native_attrs,
+        has_test_attr,
+    );
+    let plan_check = testplan_check(&fn_name_str, &static_metadata, &native_attrs, output);
+    let filter_labels = static_filter_labels(&static_metadata);
+    let filter_check = match early_exit(&native_attrs, output, "excluded by the Allure filter") {
+        Some(exit) => quote! {
+            if ::allure_core::filter::exclude_current_test(#filter_labels) {
+                #exit
+            }
+        },
+        None => quote! {},
+    };
+    let filter_check_async = quote! {
+        if ::allure_core::filter::exclude_current_test(#filter_labels) {
+            return Ok(None);
+        }
+    };
+
     let setup_metadata = generate_metadata_setup(&metadata);
 
     // Check if this test returns a Result type
@@ -219,29 +606,52 @@ fn expand_allure_test(
     let ignore_result_return =
         ignore_finish_block(has_ignore, &ignore_reason_expr, quote!(return Ok(());));
     let ignore_sync = ignore_finish_block(has_ignore, &ignore_reason_expr, quote!(return;));
+    // Result tests can only report a runtime skip as success when there is no value to return
+    let skip_result_return = if is_unit_result_return(output) {
+        quote!(return Ok(());)
+    } else {
+        quote!(std::panic::resume_unwind(panic);)
+    };
+
+    // Async tests without a runtime attribute can only run under the harness
+    let async_test_attr = if has_test_attr {
+        quote! {}
+    } else {
+        quote! { #[allow(dead_code)] }
+    };
 
     if is_async {
         // For async tests - wrap in catch_unwind to handle panics
         let expanded = quote! {
             #(#attrs)*
-            #test_attr
+            #async_test_attr
             #visibility #sig {
+                #register
+
+                #plan_check
+
                 use ::allure_core::runtime::{take_context, TestContext};
                 use ::allure_core::enums::Status;
                 use ::allure_core::futures::FutureExt;
 
                 // Build full name at runtime using module_path!()
                 let full_name = concat!(module_path!(), "::", #fn_name_str);
-                let ctx = TestContext::new(#test_name, full_name);
+                let ctx = TestContext::unstarted(#test_name, full_name);
+
+                // Failures deferred by subtests are reported once the context is closed
+                let mut deferred_failure = None;
 
                 // Run inside a task-local context so spawned tasks inherit it
                 let test_outcome = ::allure_core::runtime::with_async_context(ctx, async {
                     #setup_metadata
 
+                    #filter_check_async
+
                     #ignore_async
 
-                    // Run the async body with panic catching
-                    let test_body = async #block;
+                    // Run the async body with panic catching, under the default timeout
+                    let test_body =
+                        ::allure_core::timeout::with_default_timeout_async(async #block);
                     let panic_result =
                         std::panic::AssertUnwindSafe(test_body).catch_unwind().await;
 
@@ -250,10 +660,18 @@ fn expand_allure_test(
                             // Test completed successfully (if context still exists)
                             if let Some(mut ctx) = take_context() {
                                 ctx.finish(Status::Passed, None, None);
+                                deferred_failure = ctx.deferred_failure();
                             }
                             Ok(Some(result))
                         }
                         Err(panic) => {
+                            // Skipped at runtime - not a failure
+                            if let Some(reason) = ::allure_core::runtime::skip_reason(&*panic) {
+                                if let Some(mut ctx) = take_context() {
+                                    ctx.finish(Status::Skipped, Some(reason), None);
+                                }
+                                return Ok(None);
+                            }
                             // Test panicked
                             let panic_msg = if let Some(s) = panic.downcast_ref::<&str>() {
                                 Some(s.to_string())
@@ -272,6 +690,10 @@ fn expand_allure_test(
                 })
                 .await;
 
+                if let Some(message) = deferred_failure {
+                    panic!("{}", message);
+                }
+
                 match test_outcome {
                     Ok(Some(result)) => result,
                     Ok(None) => return,
@@ -306,57 +728,87 @@ fn expand_allure_test(
             #(#attrs)*
             #test_attr
             #visibility fn #fn_name #generics () #output {
-                use ::allure_core::runtime::{set_context, take_context, TestContext};
-                use ::allure_core::enums::Status;
+                #runner()
+            }
 
-                // Build full name at runtime using module_path!()
-                let full_name = concat!(module_path!(), "::", #fn_name_str);
-                let ctx = TestContext::new(#test_name, full_name);
-                set_context(ctx);
+            #(#runner_attrs)*
+            #[cfg(test)]
+            #[doc(hidden)]
+            fn #runner #generics () #output {
+                #register
 
-                #setup_metadata
+                // Nested under the test's own name so lints treat the body as test code.
+                fn #fn_name #generics () #output {
+                    #plan_check
 
-                #ignore_should_panic
+                    use ::allure_core::runtime::{set_context, take_context, TestContext};
+                    use ::allure_core::enums::Status;
 
-                // Run the test body and catch panics
-                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #block));
+                    // Build full name at runtime using module_path!()
+                    let full_name = concat!(module_path!(), "::", #fn_name_str);
+                    let ctx = TestContext::unstarted(#test_name, full_name);
+                    set_context(ctx);
 
-                // Extract panic message
-                let panic_msg = match &result {
-                    Ok(_) => None,
-                    Err(e) => {
-                        if let Some(s) = e.downcast_ref::<&str>() {
-                            Some(s.to_string())
-                        } else if let Some(s) = e.downcast_ref::<String>() {
-                            Some(s.clone())
-                        } else {
-                            Some("Test panicked".to_string())
+                    #setup_metadata
+
+                    #filter_check
+
+                    #ignore_should_panic
+
+                    // Run the test body under the default timeout and catch panics
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        use ::allure_core::timeout::{RunInline as _, RunOnWorker as _};
+                        (&::allure_core::timeout::DefaultTimeout::new(move || #output #block)).run_body()
+                    }));
+
+                    // Skipped at runtime - record it, then let the unwind reach the harness
+                    if let Err(e) = &result {
+                        if let Some(reason) = ::allure_core::runtime::skip_reason(&**e) {
+                            if let Some(mut ctx) = take_context() {
+                                ctx.finish(Status::Skipped, Some(reason), None);
+                            }
                         }
                     }
-                };
 
-                // For should_panic tests: panic = pass, no panic = fail
-                let (status, message) = match &result {
-                    Err(_) => {
-                        #expected_check
-                    }
-                    Ok(_) => {
-                        // No panic occurred - this is a failure for should_panic tests
-                        (Status::Failed, Some("Test did not panic as expected".to_string()))
+                    // Extract panic message
+                    let panic_msg = match &result {
+                        Ok(_) => None,
+                        Err(e) => {
+                            if let Some(s) = e.downcast_ref::<&str>() {
+                                Some(s.to_string())
+                            } else if let Some(s) = e.downcast_ref::<String>() {
+                                Some(s.clone())
+                            } else {
+                                Some("Test panicked".to_string())
+                            }
+                        }
+                    };
+
+                    // For should_panic tests: panic = pass, no panic = fail
+                    let (status, message) = match &result {
+                        Err(_) => {
+                            #expected_check
+                        }
+                        Ok(_) => {
+                            // No panic occurred - this is a failure for should_panic tests
+                            (Status::Failed, Some("Test did not panic as expected".to_string()))
+                        }
+                    };
+
+                    // Finish the test context with appropriate status
+                    if let Some(mut ctx) = take_context() {
+                        let panic_trace = #panic_trace_expr;
+                        ctx.finish(status, message, panic_trace);
                     }
-                };
 
-                // Finish the test context with appropriate status
-                if let Some(mut ctx) = take_context() {
-                    let panic_trace = #panic_trace_expr;
-                    ctx.finish(status, message, panic_trace);
+                    // Re-panic or panic to match test framework expectations
+                    match result {
+                        Err(e) => std::panic::resume_unwind(e),
+                        Ok(_) => panic!("Test did not panic as expected"),
+                    }
                 }
 
-                // Re-panic or panic to match test framework expectations
-                match result {
-                    Err(e) => std::panic::resume_unwind(e),
-                    Ok(_) => panic!("Test did not panic as expected"),
-                }
+                #fn_name()
             }
         })
     } else if returns_result {
@@ -365,54 +817,87 @@ fn expand_allure_test(
             #(#attrs)*
             #test_attr
             #visibility fn #fn_name #generics () #output {
-                use ::allure_core::runtime::{set_context, take_context, TestContext};
-                use ::allure_core::enums::Status;
+                #runner()
+            }
 
-                // Build full name at runtime using module_path!()
-                let full_name = concat!(module_path!(), "::", #fn_name_str);
-                let ctx = TestContext::new(#test_name, full_name);
-                set_context(ctx);
+            #(#runner_attrs)*
+            #[cfg(test)]
+            #[doc(hidden)]
+            fn #runner #generics () #output {
+                #register
 
-                #setup_metadata
+                // Nested under the test's own name so lints treat the body as test code.
+                fn #fn_name #generics () #output {
+                    #plan_check
 
-                #ignore_result_return
+                    use ::allure_core::runtime::{set_context, take_context, TestContext};
+                    use ::allure_core::enums::Status;
 
-                // Run the test body, catching panics
-                let panic_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #block));
+                    // Build full name at runtime using module_path!()
+                    let full_name = concat!(module_path!(), "::", #fn_name_str);
+                    let ctx = TestContext::unstarted(#test_name, full_name);
+                    set_context(ctx);
 
-                match panic_result {
-                    Ok(Ok(value)) => {
-                        // Result::Ok - test passed
-                        if let Some(mut ctx) = take_context() {
-                            ctx.finish(Status::Passed, None, None);
+                    #setup_metadata
+
+                    #filter_check
+
+                    #ignore_result_return
+
+                    // Run the test body under the default timeout, catching panics
+                    let panic_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        use ::allure_core::timeout::{RunInline as _, RunOnWorker as _};
+                        (&::allure_core::timeout::DefaultTimeout::new(move || #output #block)).run_body()
+                    }));
+
+                    match panic_result {
+                        Ok(Ok(value)) => {
+                            // Result::Ok - test passed
+                            let mut deferred_failure = None;
+                            if let Some(mut ctx) = take_context() {
+                                ctx.finish(Status::Passed, None, None);
+                                deferred_failure = ctx.deferred_failure();
+                            }
+                            if let Some(message) = deferred_failure {
+                                panic!("{}", message);
+                            }
+                            Ok(value)
                         }
-                        Ok(value)
-                    }
-                    Ok(Err(e)) => {
-                        // Result::Err - test failed via error return
-                        let error_msg = format!("{:?}", e);
-                        if let Some(mut ctx) = take_context() {
-                            let panic_trace = #panic_trace_expr;
-                            ctx.finish(Status::Failed, Some(error_msg), panic_trace);
+                        Ok(Err(e)) => {
+                            // Result::Err - test failed via error return
+                            let error_msg = format!("{:?}", e);
+                            if let Some(mut ctx) = take_context() {
+                                let panic_trace = #panic_trace_expr;
+                                ctx.finish(Status::Failed, Some(error_msg), panic_trace);
+                            }
+                            Err(e)
                         }
-                        Err(e)
-                    }
-                    Err(panic) => {
-                        // Panic - test failed via panic
-                        let panic_msg = if let Some(s) = panic.downcast_ref::<&str>() {
-                            Some(s.to_string())
-                        } else if let Some(s) = panic.downcast_ref::<String>() {
-                            Some(s.clone())
-                        } else {
-                            Some("Test panicked".to_string())
-                        };
-                        if let Some(mut ctx) = take_context() {
-                            let panic_trace = #panic_trace_expr;
-                            ctx.finish(Status::Failed, panic_msg, panic_trace);
+                        Err(panic) => {
+                            // Skipped at runtime - not a failure
+                            if let Some(reason) = ::allure_core::runtime::skip_reason(&*panic) {
+                                if let Some(mut ctx) = take_context() {
+                                    ctx.finish(Status::Skipped, Some(reason), None);
+                                }
+                                #skip_result_return
+                            }
+                            // Panic - test failed via panic
+                            let panic_msg = if let Some(s) = panic.downcast_ref::<&str>() {
+                                Some(s.to_string())
+                            } else if let Some(s) = panic.downcast_ref::<String>() {
+                                Some(s.clone())
+                            } else {
+                                Some("Test panicked".to_string())
+                            };
+                            if let Some(mut ctx) = take_context() {
+                                let panic_trace = #panic_trace_expr;
+                                ctx.finish(Status::Failed, panic_msg, panic_trace);
+                            }
+                            std::panic::resume_unwind(panic);
                         }
-                        std::panic::resume_unwind(panic);
                     }
                 }
+
+                #fn_name()
             }
         };
 
@@ -423,50 +908,88 @@ fn expand_allure_test(
             #(#attrs)*
             #test_attr
             #visibility fn #fn_name #generics () #output {
-                use ::allure_core::runtime::{set_context, take_context, TestContext};
-                use ::allure_core::enums::Status;
+                #runner()
+            }
 
-                // Build full name at runtime using module_path!()
-                let full_name = concat!(module_path!(), "::", #fn_name_str);
-                let ctx = TestContext::new(#test_name, full_name);
-                set_context(ctx);
+            #(#runner_attrs)*
+            #[cfg(test)]
+            #[doc(hidden)]
+            fn #runner #generics () #output {
+                #register
 
-                #setup_metadata
+                // Nested under the test's own name so lints treat the body as test code.
+                fn #fn_name #generics () #output {
+                    #plan_check
 
-                #ignore_sync
+                    use ::allure_core::runtime::{set_context, take_context, TestContext};
+                    use ::allure_core::enums::Status;
 
-                // Run the test body once and capture result
-                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #block));
+                    // Build full name at runtime using module_path!()
+                    let full_name = concat!(module_path!(), "::", #fn_name_str);
+                    let ctx = TestContext::unstarted(#test_name, full_name);
+                    set_context(ctx);
 
-                // Extract panic message before taking context
-                let panic_msg = match &result {
-                    Ok(_) => None,
-                    Err(e) => {
-                        if let Some(s) = e.downcast_ref::<&str>() {
-                            Some(s.to_string())
-                        } else if let Some(s) = e.downcast_ref::<String>() {
-                            Some(s.clone())
-                        } else {
-                            Some("Test panicked".to_string())
+                    #setup_metadata
+
+                    #filter_check
+
+                    #ignore_sync
+
+                    // Run the test body once under the default timeout and capture result
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        use ::allure_core::timeout::{RunInline as _, RunOnWorker as _};
+                        (&::allure_core::timeout::DefaultTimeout::new(move || #output #block)).run_body()
+                    }));
+
+                    // Skipped at runtime - not a failure
+                    if let Err(e) = &result {
+                        if let Some(reason) = ::allure_core::runtime::skip_reason(&**e) {
+                            if let Some(mut ctx) = take_context() {
+                                ctx.finish(Status::Skipped, Some(reason), None);
+                            }
+                            return;
                         }
                     }
-                };
 
-                // Finish the test context with appropriate status
-                if let Some(mut ctx) = take_context() {
-                    match &result {
-                        Ok(_) => ctx.finish(Status::Passed, None, None),
-                        Err(_) => {
-                            let panic_trace = #panic_trace_expr;
-                            ctx.finish(Status::Failed, panic_msg, panic_trace)
+                    // Extract panic message before taking context
+                    let panic_msg = match &result {
+                        Ok(_) => None,
+                        Err(e) => {
+                            if let Some(s) = e.downcast_ref::<&str>() {
+                                Some(s.to_string())
+                            } else if let Some(s) = e.downcast_ref::<String>() {
+                                Some(s.clone())
+                            } else {
+                                Some("Test panicked".to_string())
+                            }
+                        }
+                    };
+
+                    // Finish the test context with appropriate status
+                    let mut deferred_failure = None;
+                    if let Some(mut ctx) = take_context() {
+                        match &result {
+                            Ok(_) => {
+                                ctx.finish(Status::Passed, None, None);
+                                deferred_failure = ctx.deferred_failure();
+                            }
+                            Err(_) => {
+                                let panic_trace = #panic_trace_expr;
+                                ctx.finish(Status::Failed, panic_msg, panic_trace)
+                            }
                         }
                     }
-                }
 
-                // Re-panic if test failed to propagate to test framework
-                if let Err(e) = result {
-                    std::panic::resume_unwind(e);
+                    // Re-panic if test failed to propagate to test framework
+                    if let Err(e) = result {
+                        std::panic::resume_unwind(e);
+                    }
+                    if let Some(message) = deferred_failure {
+                        panic!("{}", message);
+                    }
                 }
+
+                #fn_name()
             }
         };
 
@@ -705,6 +1228,158 @@ fn expand_step(
     }
 }
 
+/// Attribute macro that records a fixture function as a setup fixture.
+///
+/// When the fixture is evaluated inside an `#[allure_test]`, it's written
+/// to the test's container as a `before` fixture, with its own steps and
+/// parameters. Works on plain setup functions and rstest `#[fixture]`s;
+/// place it before `#[fixture]`.
+///
+/// Return a `FixtureGuard` to also record the value's drop as an `after`
+/// fixture.
+///
+/// # Examples
+///
+/// ```no_run
+/// use allure_core::runtime::FixtureGuard;
+/// use allure_macros::allure_fixture;
+///
+/// struct Database;
+///
+/// #[allure_fixture]
+/// fn database() -> Database {
+///     Database
+/// }
+///
+/// #[allure_fixture("Create user {name}")]
+/// fn user(name: &str) -> String {
+///     name.to_string()
+/// }
+///
+/// #[allure_fixture("Create temp dir")]
+/// fn temp_dir() -> FixtureGuard<std::path::PathBuf> {
+///     FixtureGuard::new("Remove temp dir", std::env::temp_dir())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn allure_fixture(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let custom_name = if attr.is_empty() {
+        None
+    } else {
+        Some(parse_macro_input!(attr as syn::LitStr).value())
+    };
+
+    let fixture_name = custom_name.unwrap_or_else(|| input.sig.ident.to_string());
+    let visibility = &input.vis;
+    let attrs = &input.attrs;
+    let block = &input.block;
+    let sig = &input.sig;
+
+    let name = if fixture_name.contains('{') {
+        let param_captures = generate_param_captures(&sig.inputs);
+        quote! {
+            {
+                let mut name = #fixture_name.to_string();
+                #param_captures
+                name
+            }
+        }
+    } else {
+        quote! { #fixture_name }
+    };
+    let body = if sig.asyncness.is_some() {
+        quote! { ::allure_core::runtime::before_async(#name, async move #block).await }
+    } else {
+        quote! { ::allure_core::runtime::before(#name, || #block) }
+    };
+
+    let expanded = quote! {
+        #(#attrs)*
+        #visibility #sig {
+            #body
+        }
+    };
+
+    expanded.into()
+}
+
+/// Attribute macro that records a function as a binary-wide setup fixture.
+///
+/// The fixture goes into a run-level container whose children are all
+/// test results written by the test binary afterwards. Combine it with
+/// `#[ctor::ctor]` to run the setup once before the tests.
+///
+/// # Examples
+///
+/// ```ignore
+/// use allure_macros::allure_before_all;
+///
+/// #[ctor::ctor]
+/// #[allure_before_all("Start test server")]
+/// fn start_server() {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn allure_before_all(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let custom_name = if attr.is_empty() {
+        None
+    } else {
+        Some(parse_macro_input!(attr as syn::LitStr).value())
+    };
+    expand_run_fixture(input, custom_name, quote! { before_all })
+}
+
+/// Attribute macro that records a function as a binary-wide teardown fixture.
+///
+/// Combine it with `#[ctor::dtor]` to run the teardown once after the
+/// tests; see [`macro@allure_before_all`].
+///
+/// # Examples
+///
+/// ```ignore
+/// use allure_macros::allure_after_all;
+///
+/// #[ctor::dtor]
+/// #[allure_after_all("Stop test server")]
+/// fn stop_server() {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn allure_after_all(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let custom_name = if attr.is_empty() {
+        None
+    } else {
+        Some(parse_macro_input!(attr as syn::LitStr).value())
+    };
+    expand_run_fixture(input, custom_name, quote! { after_all })
+}
+
+fn expand_run_fixture(
+    input: ItemFn,
+    custom_name: Option<String>,
+    runtime_fn: proc_macro2::TokenStream,
+) -> TokenStream {
+    let fixture_name = custom_name.unwrap_or_else(|| input.sig.ident.to_string());
+    let visibility = &input.vis;
+    let attrs = &input.attrs;
+    let block = &input.block;
+    let sig = &input.sig;
+
+    let expanded = quote! {
+        #(#attrs)*
+        #visibility #sig {
+            ::allure_core::runtime::#runtime_fn(#fixture_name, || #block)
+        }
+    };
+
+    expanded.into()
+}
+
 fn generate_param_captures(inputs: &Punctuated<FnArg, Token![,]>) -> proc_macro2::TokenStream {
     let mut captures = quote! {};
 
@@ -1020,6 +1695,281 @@ pub fn allure_flaky(_attr: TokenStream, item: TokenStream) -> TokenStream {
     expanded.into()
 }
 
+/// Fails a test that runs longer than the given number of milliseconds.
+///
+/// Sync tests run on a worker thread watched by the test thread; async tests
+/// race against a timer. On expiry the step in progress is marked broken,
+/// the result is written as broken with a `timed out after Xms` message, and
+/// the test fails. This overrides `configure().default_timeout(...)`. Sync
+/// test bodies must be `Send + 'static`. Like other metadata attributes, this
+/// must come before `#[allure_test]`.
+///
+/// # Example
+///
+/// ```no_run
+/// use allure_macros::{allure_test, allure_timeout};
+///
+/// #[allure_timeout(500)]
+/// #[allure_test]
+/// fn test_acquire_lock() { }
+/// ```
+#[proc_macro_attribute]
+pub fn allure_timeout(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let millis = parse_macro_input!(attr as syn::LitInt);
+    let millis = match millis.base10_parse::<u64>() {
+        Ok(millis) => millis,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let visibility = &input.vis;
+    let attrs = &input.attrs;
+    let block = &input.block;
+    let sig = &input.sig;
+
+    let limit = quote! { ::std::time::Duration::from_millis(#millis) };
+    let body = if sig.asyncness.is_some() {
+        quote! { ::allure_core::timeout::with_timeout_async(#limit, async move #block).await }
+    } else {
+        quote! { ::allure_core::timeout::run_with_timeout(#limit, move || #block) }
+    };
+
+    let expanded = quote! {
+        #(#attrs)*
+        #visibility #sig {
+            #body
+        }
+    };
+
+    expanded.into()
+}
+
+/// Reruns a failing test and reports every attempt as its own result.
+///
+/// `times` is how many times the body is rerun after a failure; `delay_ms`
+/// is the pause between attempts. All attempts share one history ID, so
+/// Allure shows the earlier ones as retries. The last attempt decides the
+/// cargo outcome, and a test that passes after failing is marked flaky.
+/// Like other metadata attributes, this must come before `#[allure_test]`.
+///
+/// # Example
+///
+/// ```no_run
+/// use allure_macros::{allure_test, allure_retry};
+///
+/// #[allure_retry(times = 3, delay_ms = 200)]
+/// #[allure_test]
+/// fn test_remote_service() { }
+///
+/// #[allure_retry(2)]
+/// #[allure_test]
+/// fn test_with_two_retries() { }
+/// ```
+#[proc_macro_attribute]
+pub fn allure_retry(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let args = parse_macro_input!(attr as RetryArgs);
+
+    let visibility = &input.vis;
+    let attrs = &input.attrs;
+    let block = &input.block;
+    let sig = &input.sig;
+    let times = args.times;
+    let delay_ms = args.delay_ms;
+
+    // Result-returning tests also retry on `Err`
+    let check_value = if is_result_return(&sig.output) {
+        quote! {
+            match value {
+                Ok(_) => retry.complete(),
+                Err(e) => retry.retry_after_error(format!("{:?}", e)),
+            }
+        }
+    } else {
+        quote! { retry.complete() }
+    };
+
+    let (run_attempt, wait) = if sig.asyncness.is_some() {
+        (
+            quote! {
+                ::allure_core::futures::FutureExt::catch_unwind(
+                    std::panic::AssertUnwindSafe(async #block),
+                )
+                .await
+            },
+            quote! { retry.wait_async().await },
+        )
+    } else {
+        (
+            quote! { std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #block)) },
+            quote! { retry.wait() },
+        )
+    };
+
+    let expanded = quote! {
+        #(#attrs)*
+        #visibility #sig {
+            let mut retry = ::allure_core::runtime::Retry::new(#times, #delay_ms);
+            loop {
+                let outcome = #run_attempt;
+                let again = match &outcome {
+                    Ok(value) => #check_value,
+                    Err(panic) => retry.retry_after_panic(&**panic),
+                };
+                if again {
+                    #wait;
+                    continue;
+                }
+                match outcome {
+                    Ok(value) => break value,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                }
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+/// Arguments for `#[allure_retry]`: (n) or (times = n, delay_ms = n)
+struct RetryArgs {
+    times: u32,
+    delay_ms: u64,
+}
+
+impl Parse for RetryArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::LitInt) {
+            let times: syn::LitInt = input.parse()?;
+            return Ok(RetryArgs {
+                times: times.base10_parse()?,
+                delay_ms: 0,
+            });
+        }
+
+        let pairs = Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated(input)?;
+        let mut times = None;
+        let mut delay_ms = 0;
+
+        for pair in pairs {
+            let value = match &pair.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: Lit::Int(i), ..
+                }) => i,
+                other => return Err(syn::Error::new_spanned(other, "Expected integer literal")),
+            };
+            if pair.path.is_ident("times") {
+                times = Some(value.base10_parse()?);
+            } else if pair.path.is_ident("delay_ms") {
+                delay_ms = value.base10_parse()?;
+            } else {
+                return Err(syn::Error::new_spanned(
+                    pair.path,
+                    "Expected `times` or `delay_ms`",
+                ));
+            }
+        }
+
+        let times = times.ok_or_else(|| input.error("Missing `times = N` argument"))?;
+        Ok(RetryArgs { times, delay_ms })
+    }
+}
+
+/// Skips a test at runtime when an environment variable is set.
+///
+/// The variable counts as set when it is present and non-empty, or when it
+/// equals `value` if one is given. Like other metadata attributes, this must
+/// come before `#[allure_test]`.
+///
+/// # Example
+///
+/// ```no_run
+/// use allure_macros::{allure_test, allure_skip_if};
+///
+/// #[allure_skip_if(env = "CI", reason = "needs a display")]
+/// #[allure_test]
+/// fn test_screenshot() { }
+///
+/// #[allure_skip_if(env = "DB_BACKEND", value = "sqlite")]
+/// #[allure_test]
+/// fn test_row_locking() { }
+/// ```
+#[proc_macro_attribute]
+pub fn allure_skip_if(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let args = parse_macro_input!(attr as SkipIfArgs);
+
+    let visibility = &input.vis;
+    let attrs = &input.attrs;
+    let block = &input.block;
+    let sig = &input.sig;
+
+    let env = &args.env;
+    let (matches, default_reason) = match &args.value {
+        Some(value) => (
+            quote! { value == #value },
+            format!("skipped because {}={}", env, value),
+        ),
+        None => (
+            quote! { !value.is_empty() },
+            format!("skipped because {} is set", env),
+        ),
+    };
+    let reason = args.reason.unwrap_or(default_reason);
+
+    let expanded = quote! {
+        #(#attrs)*
+        #visibility #sig {
+            if let Ok(value) = ::std::env::var(#env) {
+                ::allure_core::runtime::skip_if(#matches, #reason);
+            }
+            #block
+        }
+    };
+
+    expanded.into()
+}
+
+/// Arguments for `#[allure_skip_if]`: (env = "...", value = "...", reason = "...")
+struct SkipIfArgs {
+    env: String,
+    value: Option<String>,
+    reason: Option<String>,
+}
+
+impl Parse for SkipIfArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let pairs = Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated(input)?;
+        let mut env = None;
+        let mut value = None;
+        let mut reason = None;
+
+        for pair in pairs {
+            let text = match &pair.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => s.value(),
+                other => return Err(syn::Error::new_spanned(other, "Expected string literal")),
+            };
+            if pair.path.is_ident("env") {
+                env = Some(text);
+            } else if pair.path.is_ident("value") {
+                value = Some(text);
+            } else if pair.path.is_ident("reason") {
+                reason = Some(text);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    pair.path,
+                    "Expected `env`, `value` or `reason`",
+                ));
+            }
+        }
+
+        let env = env.ok_or_else(|| input.error("Missing `env = \"...\"` argument"))?;
+        Ok(SkipIfArgs { env, value, reason })
+    }
+}
+
 /// Adds an issue link to a test.
 #[proc_macro_attribute]
 pub fn allure_issue(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -1033,6 +1983,9 @@ pub fn allure_tms(attr: TokenStream, item: TokenStream) -> TokenStream {
 }
 
 /// Adds a generic link to a test.
+///
+/// A `type = "..."` argument gives the link a custom type, whose URL pattern
+/// expands a bare ID, e.g. `#[allure_link("ABC-123", type = "jira")]`.
 #[proc_macro_attribute]
 pub fn allure_link(attr: TokenStream, item: TokenStream) -> TokenStream {
     link_attr("link", attr, item)
@@ -1102,10 +2055,10 @@ fn metadata_attr(meta_type: &str, attr: TokenStream, item: TokenStream) -> Token
 fn link_attr(link_type: &str, attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemFn);
 
-    // Parse either a single string or a tuple (url, name)
-    let (url, name) = if let Ok(lit) = syn::parse::<Lit>(attr.clone()) {
+    // Parse either a single string or a tuple (url, name, type = "...")
+    let (url, name, custom_type) = if let Ok(lit) = syn::parse::<Lit>(attr.clone()) {
         match lit {
-            Lit::Str(s) => (s.value(), None),
+            Lit::Str(s) => (s.value(), None, None),
             _ => {
                 return syn::Error::new(lit.span(), "Expected string literal")
                     .to_compile_error()
@@ -1118,8 +2071,19 @@ fn link_attr(link_type: &str, attr: TokenStream, item: TokenStream) -> TokenStre
             Ok(args) => args,
             Err(e) => return e.to_compile_error().into(),
         };
-        (args.url, args.name)
+        if let Some(custom_type) = &args.link_type {
+            if link_type != "link" {
+                return syn::Error::new(
+                    custom_type.span(),
+                    "`type` is only supported by #[allure_link]",
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+        (args.url, args.name, args.link_type.map(|t| t.value()))
     };
+    let link_type = custom_type.as_deref().unwrap_or(link_type);
 
     let visibility = &input.vis;
     let attrs = &input.attrs;
@@ -1134,7 +2098,13 @@ fn link_attr(link_type: &str, attr: TokenStream, item: TokenStream) -> TokenStre
         "issue" => quote! { ::allure_core::runtime::issue(#url, #name_opt); },
         "tms" => quote! { ::allure_core::runtime::tms(#url, #name_opt); },
         "link" => quote! { ::allure_core::runtime::link(#url, #name_opt); },
-        _ => quote! {},
+        custom => quote! {
+            ::allure_core::runtime::typed_link(
+                #url,
+                #name_opt,
+                ::allure_core::enums::LinkType::custom(#custom),
+            );
+        },
     };
 
     let expanded = quote! {
@@ -1148,10 +2118,12 @@ fn link_attr(link_type: &str, attr: TokenStream, item: TokenStream) -> TokenStre
     expanded.into()
 }
 
-/// Arguments for link attributes: (url) or (url, name)
+/// Arguments for link attributes: (url), (url, name), optionally followed by
+/// `type = "..."`
 struct LinkArgs {
     url: String,
     name: Option<String>,
+    link_type: Option<syn::LitStr>,
 }
 
 impl Parse for LinkArgs {
@@ -1167,11 +2139,22 @@ impl Parse for LinkArgs {
             }
         };
 
-        let name = if input.peek(Token![,]) {
+        let mut name = None;
+        let mut link_type = None;
+        while input.peek(Token![,]) {
             input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            if input.peek(Token![type]) {
+                input.parse::<Token![type]>()?;
+                input.parse::<Token![=]>()?;
+                link_type = Some(input.parse::<syn::LitStr>()?);
+                continue;
+            }
             let name_lit: Lit = input.parse()?;
             match name_lit {
-                Lit::Str(s) => Some(s.value()),
+                Lit::Str(s) if name.is_none() && link_type.is_none() => name = Some(s.value()),
                 _ => {
                     return Err(syn::Error::new(
                         name_lit.span(),
@@ -1179,11 +2162,13 @@ impl Parse for LinkArgs {
                     ))
                 }
             }
-        } else {
-            None
-        };
+        }
 
-        Ok(LinkArgs { url: url_str, name })
+        Ok(LinkArgs {
+            url: url_str,
+            name,
+            link_type,
+        })
     }
 }
 
@@ -1267,6 +2252,39 @@ mod tests {
         assert!(!is_result_return(&ReturnType::Default));
     }
 
+    #[test]
+    fn is_unit_result_return_only_matches_unit_ok() {
+        let unit: ReturnType = parse_quote!(-> Result<(), String>);
+        let value: ReturnType = parse_quote!(-> Result<u32, String>);
+        assert!(is_unit_result_return(&unit));
+        assert!(!is_unit_result_return(&value));
+        assert!(!is_unit_result_return(&ReturnType::Default));
+    }
+
+    #[test]
+    fn retry_args_parse_both_forms() {
+        let short: RetryArgs = syn::parse2(quote!(2)).unwrap();
+        assert_eq!((short.times, short.delay_ms), (2, 0));
+
+        let named: RetryArgs = syn::parse2(quote!(times = 3, delay_ms = 200)).unwrap();
+        assert_eq!((named.times, named.delay_ms), (3, 200));
+
+        assert!(syn::parse2::<RetryArgs>(quote!(delay_ms = 5)).is_err());
+        assert!(syn::parse2::<RetryArgs>(quote!(times = "3")).is_err());
+    }
+
+    #[test]
+    fn skip_if_args_parse_env_value_and_reason() {
+        let args: SkipIfArgs =
+            syn::parse2(quote!(env = "CI", value = "true", reason = "flaky on CI")).unwrap();
+        assert_eq!(args.env, "CI");
+        assert_eq!(args.value.as_deref(), Some("true"));
+        assert_eq!(args.reason.as_deref(), Some("flaky on CI"));
+
+        assert!(syn::parse2::<SkipIfArgs>(quote!(reason = "no env")).is_err());
+        assert!(syn::parse2::<SkipIfArgs>(quote!(env = "CI", when = "x")).is_err());
+    }
+
     #[test]
     fn generate_param_captures_includes_all_idents() {
         let inputs: Punctuated<FnArg, Token![,]> = parse_quote!(first: i32, second: &str);
@@ -1284,6 +2302,14 @@ mod tests {
         let single_args: LinkArgs = syn::parse2(quote! { "http://tms" }).unwrap();
         assert_eq!(single_args.url, "http://tms");
         assert!(single_args.name.is_none());
+
+        let typed: LinkArgs = syn::parse2(quote! { "1234", "PR", type = "github" }).unwrap();
+        assert_eq!(typed.name.as_deref(), Some("PR"));
+        assert_eq!(typed.link_type.unwrap().value(), "github");
+
+        let unnamed: LinkArgs = syn::parse2(quote! { "ABC-1", type = "jira" }).unwrap();
+        assert!(unnamed.name.is_none());
+        assert_eq!(unnamed.link_type.unwrap().value(), "jira");
     }
 
     #[test]
@@ -1411,4 +2437,48 @@ mod tests {
         assert!(rendered.contains("Value"));
         assert!(rendered.contains("finish_step"));
     }
+
+    #[test]
+    fn collect_static_metadata_sees_through_wrappers() {
+        let block: syn::Block = parse_quote!({
+            ::allure_core::runtime::epic("Payments");
+            {
+                ::allure_core::runtime::severity(::allure_core::Severity::Blocker);
+                {
+                    ::allure_core::timeout::run_with_timeout(limit, move || {
+                        ::allure_core::runtime::parent_suite("API");
+                        {
+                            ::allure_core::runtime::issue("BUG-1", Some("Refunds".to_string()));
+                            {
+                                ::allure_core::runtime::title("Refund a card payment");
+                                {
+                                    epic("not an attribute");
+                                }
+                            }
+                        }
+                    })
+                }
+            }
+        });
+        let metadata = collect_static_metadata(&block);
+
+        assert_eq!(
+            metadata.labels,
+            vec![
+                ("epic".to_string(), "Payments".to_string()),
+                ("severity".to_string(), "blocker".to_string()),
+                ("parentSuite".to_string(), "API".to_string()),
+            ]
+        );
+        assert_eq!(
+            metadata.links,
+            vec![(
+                "issue".to_string(),
+                "BUG-1".to_string(),
+                Some("Refunds".to_string())
+            )]
+        );
+        assert_eq!(metadata.title.as_deref(), Some("Refund a card payment"));
+        assert_eq!(metadata.allure_id, None);
+    }
 }
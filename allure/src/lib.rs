@@ -120,11 +120,12 @@ pub use allure_core as __private;
 
 // Re-export all proc macros
 pub use allure_macros::{
-    allure_description, allure_description_html, allure_epic, allure_epics, allure_feature,
-    allure_features, allure_flaky, allure_id, allure_issue, allure_link, allure_owner,
-    allure_parent_suite, allure_severity, allure_step, allure_step_fn, allure_stories,
-    allure_story, allure_sub_suite, allure_suite, allure_suite_label, allure_tag, allure_tags,
-    allure_test, allure_title, allure_tms,
+    allure_after_all, allure_before_all, allure_description, allure_description_html, allure_epic,
+    allure_epics, allure_feature, allure_features, allure_fixture, allure_flaky, allure_id,
+    allure_issue, allure_link, allure_owner, allure_parent_suite, allure_retry, allure_severity,
+    allure_skip_if, allure_step, allure_step_fn, allure_stories, allure_story, allure_sub_suite,
+    allure_suite, allure_suite_label, allure_tag, allure_tags, allure_test, allure_timeout,
+    allure_title, allure_tms,
 };
 
 /// Prelude module for convenient imports.
@@ -133,27 +134,47 @@ pub use allure_macros::{
 pub mod prelude {
     // Proc macros
     pub use allure_macros::{
-        allure_description, allure_description_html, allure_epic, allure_epics, allure_feature,
-        allure_features, allure_flaky, allure_id, allure_issue, allure_link, allure_owner,
-        allure_parent_suite, allure_severity, allure_step, allure_step_fn, allure_stories,
-        allure_story, allure_sub_suite, allure_suite, allure_suite_label, allure_tag, allure_tags,
-        allure_test, allure_title, allure_tms,
+        allure_after_all, allure_before_all, allure_description, allure_description_html,
+        allure_epic, allure_epics, allure_feature, allure_features, allure_fixture, allure_flaky,
+        allure_id, allure_issue, allure_link, allure_owner, allure_parent_suite, allure_retry,
+        allure_severity, allure_skip_if, allure_step, allure_step_fn, allure_stories, allure_story,
+        allure_sub_suite, allure_suite, allure_suite_label, allure_tag, allure_tags, allure_test,
+        allure_timeout, allure_title, allure_tms,
     };
 
     // Core types
     pub use allure_core::{
-        Attachment, Category, Label, Link, Parameter, Severity, Status, StepResult, TestResult,
+        Attachment, Category, Label, Link, LinkType, Parameter, Severity, Status, StepResult,
+        TestResult,
     };
 
     // Runtime functions
     pub use allure_core::{
-        allure_id, attach_binary, attach_file, attach_json, attach_text, configure, description,
-        description_html, display_name, epic, feature, flaky, issue, known_issue, label, link,
-        log_step, owner, parameter, parameter_excluded, parameter_hidden, parameter_masked,
-        parent_suite, run_test, severity, skip, step, story, sub_suite, suite, tag, tags,
-        test_case_id, title, tms, with_test_context,
+        after, after_all, allure_id, attach_binary, attach_file, attach_json, attach_text, before,
+        before_all, configure, current_labels, current_step_path, current_test, description,
+        description_html, display_name, epic, feature, flaky, has_failures, issue, known_issue,
+        label, link, log_step, on_failure, on_step_failure, owner, parameter, parameter_excluded,
+        parameter_hidden, parameter_masked, parent_suite, run_test, severity, skip, skip_if,
+        start_step, step, story, sub_suite, subtest, suite, tag, tags, teardown, test_case_id,
+        title, tms, try_attach_binary, try_attach_file, try_attach_json, try_attach_text,
+        try_label, try_parameter, try_step, typed_link, with_test_context,
     };
 
+    // Errors
+    pub use allure_core::{AllureError, AllureResult, ErrorPolicy};
+
+    // Fixtures
+    pub use allure_core::{before_async, FixtureGuard};
+
+    // Failure attachments
+    pub use allure_core::AttachmentContent;
+
+    // Skip and assume
+    pub use allure_core::assume;
+
+    // Soft assertions
+    pub use allure_core::{soft, soft_assert, soft_assert_eq, SoftAssertions};
+
     // Attachment module
     pub use allure_core::attachment;
 
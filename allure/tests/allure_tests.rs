@@ -6,17 +6,24 @@
 //! IMPORTANT: Metadata attributes (#[allure_epic], #[allure_feature], etc.) must come
 //! BEFORE #[allure_test] due to Rust's proc macro processing order.
 
+#![allow(clippy::assertions_on_constants)]
+
 // Use allure_macros directly for attribute macros
 use allure_macros::{
-    allure_description, allure_epic, allure_feature, allure_flaky, allure_owner, allure_severity,
-    allure_suite_label, allure_tag, allure_test,
+    allure_description, allure_epic, allure_feature, allure_flaky, allure_id, allure_issue,
+    allure_owner, allure_retry, allure_severity, allure_skip_if, allure_suite_label, allure_tag,
+    allure_tags, allure_test, allure_timeout, allure_title,
 };
+use allure_rs::LinkType;
 
 // Runtime functions from prelude
 use allure_rs::prelude::{
-    bdd, configure, description, display_name, flaky, parameter, step, test_case_id,
+    after, assume, bdd, before, configure, description, display_name, flaky, parameter, skip, step,
+    subtest, teardown, test_case_id,
 };
 
+use std::sync::atomic::{AtomicU32, Ordering};
+
 // Initialize Allure once before tests run
 // Note: Tests run from the crate directory (allure/), so use parent path
 #[ctor::ctor]
@@ -158,3 +165,148 @@ fn test_display_name_example() {
         assert!(true);
     });
 }
+
+#[allure_epic("Data Processing")]
+#[allure_feature("Parsing")]
+#[allure_test]
+fn test_table_driven_subtests() {
+    let cases = [
+        ("zero", "0", 0),
+        ("positive", "42", 42),
+        ("negative", "-7", -7),
+    ];
+    for (name, input, expected) in cases {
+        subtest(name, [("input", input)], || {
+            step("Parse input", || {
+                assert_eq!(input.parse::<i32>().unwrap(), expected);
+            });
+        });
+    }
+}
+
+#[allure_feature("Skipping")]
+#[allure_test]
+fn test_runtime_skip_stops_body() {
+    step("Check prerequisites", || {
+        skip("prerequisite service is not running");
+    });
+    #[allow(unreachable_code)]
+    {
+        panic!("body must not continue after skip()");
+    }
+}
+
+#[allure_feature("Skipping")]
+#[allure_test]
+fn test_failed_assumption_skips() {
+    assume!(1 + 1 == 3, "arithmetic is broken on this host");
+    panic!("body must not continue after a failed assumption");
+}
+
+#[allure_feature("Skipping")]
+#[allure_test]
+fn test_result_test_can_skip() -> Result<(), String> {
+    assume!(cfg!(target_os = "none"));
+    Err("body must not continue after a failed assumption".to_string())
+}
+
+#[allure_skip_if(env = "CARGO_MANIFEST_DIR", reason = "always set under cargo")]
+#[allure_feature("Skipping")]
+#[allure_test]
+fn test_skip_if_env_attribute() {
+    panic!("skipped by attribute before the body runs");
+}
+
+static RETRIED_CALLS: AtomicU32 = AtomicU32::new(0);
+
+#[allure_retry(times = 2, delay_ms = 10)]
+#[allure_feature("Retries")]
+#[allure_test]
+fn test_retry_passes_on_third_attempt() {
+    let call = RETRIED_CALLS.fetch_add(1, Ordering::SeqCst) + 1;
+    step("Call unstable service", || {
+        assert!(call >= 3, "service unavailable on attempt {}", call);
+    });
+}
+
+static RETRIED_RESULT_CALLS: AtomicU32 = AtomicU32::new(0);
+
+#[allure_retry(1)]
+#[allure_feature("Retries")]
+#[allure_test]
+fn test_retry_result_test_on_err() -> Result<(), String> {
+    match RETRIED_RESULT_CALLS.fetch_add(1, Ordering::SeqCst) {
+        0 => Err("connection reset".to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[allure_feature("Fixtures")]
+#[allure_test]
+fn test_runtime_fixtures() {
+    let mut store = before("Open store", || {
+        step("Create schema", || {});
+        vec!["seed"]
+    });
+    teardown("Close store", || {
+        step("Flush", || {});
+    });
+
+    step("Insert row", || store.push("row"));
+    assert_eq!(store.len(), 2);
+
+    after("Verify store", || {
+        assert!(store.contains(&"seed"));
+    });
+}
+
+const REGISTERED_TEST_LINE: u32 = line!() + 9;
+
+#[allure_title("Registered with metadata")]
+#[allure_id("42")]
+#[allure_epic("Registry")]
+#[allure_tags("smoke", "api")]
+#[allure_issue("JIRA-7", "Listing")]
+#[allure_timeout(5_000)]
+#[allure_test]
+fn test_registered_with_metadata() {
+    step("Described without running", || {});
+}
+
+#[test]
+fn test_registry_describes_tests_statically() {
+    let tests = allure_rs::registry::tests();
+    let test = tests
+        .iter()
+        .find(|test| test.name == "test_registered_with_metadata")
+        .expect("test should be registered");
+
+    assert_eq!(test.display_name, "Registered with metadata");
+    assert_eq!(
+        test.full_name(),
+        "allure_tests::test_registered_with_metadata"
+    );
+    assert_eq!(test.allure_id, Some("42"));
+    assert_eq!(test.labels_named("epic").collect::<Vec<_>>(), ["Registry"]);
+    assert_eq!(
+        test.labels_named("tag").collect::<Vec<_>>(),
+        ["smoke", "api"]
+    );
+    assert_eq!(test.links.len(), 1);
+    assert_eq!(test.links[0].link_type, LinkType::Issue);
+    assert_eq!(test.links[0].url, "JIRA-7");
+    assert_eq!(test.links[0].name, Some("Listing"));
+    assert!(test.file.ends_with("allure_tests.rs"));
+    assert_eq!(test.line, REGISTERED_TEST_LINE);
+    assert!(test.run.is_some());
+
+    let login = tests
+        .iter()
+        .find(|test| test.name == "test_user_login")
+        .expect("test should be registered");
+    assert_eq!(login.display_name, "test_user_login");
+    assert_eq!(
+        login.labels_named("severity").collect::<Vec<_>>(),
+        ["critical"]
+    );
+}
@@ -3,6 +3,8 @@
 //! These tests verify that Allure macros and runtime work correctly
 //! with async test frameworks like tokio.
 
+#![allow(clippy::assertions_on_constants)]
+
 use allure_rs::prelude::*;
 use tempfile::TempDir;
 
@@ -234,3 +236,54 @@ async fn test_async_parallel_tasks() {
         assert_eq!(results, vec![0, 2, 4]);
     });
 }
+
+// =============================================================================
+// Retries in async tests
+// =============================================================================
+
+static ASYNC_RETRY_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[tokio::test]
+#[allure_retry(times = 1, delay_ms = 5)]
+#[allure_feature("Retries")]
+#[allure_test]
+async fn test_async_retry_reruns_body() {
+    let call = ASYNC_RETRY_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+    assert!(call >= 1, "first attempt fails");
+}
+
+#[cfg(feature = "async")]
+static ASYNC_BODY_RETRY_CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[cfg(feature = "async")]
+#[allure_retry(times = 2, delay_ms = 5)]
+#[allure_feature("Retries")]
+#[allure_test]
+#[tokio::test]
+async fn test_async_body_retry_with_tokio_delay() {
+    let call = ASYNC_BODY_RETRY_CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+    assert!(call >= 2, "attempt {} fails", call + 1);
+}
+
+// =============================================================================
+// Async fixtures
+// =============================================================================
+
+#[allure_fixture("Open connection")]
+async fn connection() -> u16 {
+    step("Resolve host", || {});
+    tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
+    5432
+}
+
+#[tokio::test]
+#[allure_feature("Fixtures")]
+#[allure_test]
+async fn test_async_fixture_function() {
+    let _dir = setup_results_dir();
+
+    let port = connection().await;
+    step("Verify port", || assert_eq!(port, 5432));
+}
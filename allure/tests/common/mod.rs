@@ -0,0 +1,69 @@
+//! Helpers shared by the test binaries that configure the Allure runtime.
+//!
+//! Each binary writes its results to a temporary directory of its own,
+//! removed when the binary exits.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+static RESULTS_DIR: OnceLock<TempDir> = OnceLock::new();
+
+/// Returns the results directory of this test binary.
+pub fn results_dir() -> &'static Path {
+    RESULTS_DIR
+        .get_or_init(|| TempDir::new().expect("Failed to create temp dir"))
+        .path()
+}
+
+// Statics are never dropped, so the directory is removed on exit instead
+#[ctor::dtor]
+fn remove_results_dir() {
+    if let Some(dir) = RESULTS_DIR.get() {
+        let _ = fs::remove_dir_all(dir.path());
+    }
+}
+
+/// Reads the JSON files in the results directory whose names end with
+/// `suffix`, skipping any still being written.
+fn read_files(suffix: &str) -> Vec<Value> {
+    fs::read_dir(results_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(suffix))
+        .filter_map(|path| serde_json::from_str(&fs::read_to_string(path).ok()?).ok())
+        .collect()
+}
+
+/// Reads all result files from the results directory.
+pub fn read_results() -> Vec<Value> {
+    read_files("-result.json")
+}
+
+/// Reads all container files from the results directory.
+pub fn read_containers() -> Vec<Value> {
+    read_files("-container.json")
+}
+
+/// Returns the result of the test called `name`, if one was written.
+pub fn find_result(name: &str) -> Option<Value> {
+    read_results()
+        .into_iter()
+        .find(|result| result["name"] == name)
+}
+
+/// Reads the result of the test called `name`.
+pub fn read_result(name: &str) -> Value {
+    find_result(name).expect("result should be written")
+}
+
+/// Reads the content of an attachment of a result or step.
+pub fn read_attachment(attachment: &Value) -> String {
+    let source = attachment["source"].as_str().unwrap();
+    fs::read_to_string(results_dir().join(source)).unwrap()
+}
@@ -0,0 +1,99 @@
+//! Tests for configuration from the environment.
+//!
+//! This binary never calls `configure()`: the results directory, a global
+//! label and the link patterns all come from `ALLURE_*` variables set before
+//! any test runs.
+
+mod common;
+
+use std::path::PathBuf;
+
+use allure_macros::{allure_issue, allure_link, allure_test};
+use allure_rs::enums::LinkType;
+use allure_rs::registry;
+use allure_rs::runtime::{get_config, known_issue, tms};
+
+use common::{read_result, results_dir};
+
+#[ctor::ctor]
+fn init() {
+    std::env::set_var("ALLURE_RESULTS_DIR", results_dir());
+    std::env::set_var("ALLURE_CLEAN_RESULTS", "true");
+    std::env::set_var("ALLURE_LABEL_layer", "integration");
+    std::env::set_var(
+        "ALLURE_LINK_ISSUE_PATTERN",
+        "https://jira.example.com/browse/{}",
+    );
+    std::env::set_var(
+        "ALLURE_LINK_GITHUB_PATTERN",
+        "https://github.com/example/billing/pull/{}",
+    );
+}
+
+#[allure_issue("BILL-42")]
+#[allure_test]
+fn test_configured_from_env() {}
+
+#[allure_link("17", "PR", type = "github")]
+#[allure_test]
+fn test_with_expanded_links() {
+    known_issue("BILL-7");
+    tms("TC-1", None);
+    tms("https://tms.example.com/TC-2", None);
+}
+
+#[test]
+fn test_env_configures_results_labels_and_links() {
+    let config = get_config();
+    assert_eq!(PathBuf::from(&config.results_dir), results_dir());
+    assert!(config.clean_results);
+
+    test_configured_from_env();
+
+    let result = read_result("test_configured_from_env");
+
+    let labels = result["labels"].as_array().unwrap();
+    assert!(labels
+        .iter()
+        .any(|label| label["name"] == "layer" && label["value"] == "integration"));
+    assert_eq!(
+        result["links"][0]["url"],
+        "https://jira.example.com/browse/BILL-42"
+    );
+    assert_eq!(result["links"][0]["type"], "issue");
+}
+
+#[test]
+fn test_link_patterns_expand_bare_ids_by_type() {
+    test_with_expanded_links();
+
+    let result = read_result("test_with_expanded_links");
+    let links: Vec<(&str, &str)> = result["links"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|link| {
+            (
+                link["type"].as_str().unwrap(),
+                link["url"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        links,
+        [
+            ("github", "https://github.com/example/billing/pull/17"),
+            ("issue", "https://jira.example.com/browse/BILL-7"),
+            ("tms", "TC-1"),
+            ("tms", "https://tms.example.com/TC-2"),
+        ]
+    );
+    assert_eq!(result["statusDetails"]["known"], true);
+
+    let registered = registry::tests()
+        .into_iter()
+        .find(|test| test.name == "test_with_expanded_links")
+        .unwrap();
+    assert_eq!(registered.links[0].link_type, LinkType::custom("github"));
+    assert_eq!(registered.links[0].url, "17");
+}
@@ -0,0 +1,94 @@
+//! Tests for attachments computed only when a test or step fails.
+
+mod common;
+
+use std::panic::catch_unwind;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use allure_rs::enums::ContentType;
+use allure_rs::prelude::{
+    configure, on_failure, on_step_failure, run_test, step, AttachmentContent,
+};
+use serde_json::Value;
+
+use common::{read_attachment, read_result, results_dir};
+
+static EVALUATED: AtomicUsize = AtomicUsize::new(0);
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .init();
+}
+
+fn attachment_names(item: &Value) -> Vec<&str> {
+    item["attachments"]
+        .as_array()
+        .map(|attachments| {
+            attachments
+                .iter()
+                .map(|attachment| attachment["name"].as_str().unwrap())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_passing_test_skips_failure_attachments() {
+    run_test("passing", "failure_attachment_tests::passing", || {
+        on_failure("Log tail", || {
+            EVALUATED.fetch_add(1, Ordering::SeqCst);
+            "never"
+        });
+        step("Load", || {
+            on_step_failure("Rows", || {
+                EVALUATED.fetch_add(1, Ordering::SeqCst);
+                "never"
+            })
+        });
+    });
+
+    let result = read_result("passing");
+    assert_eq!(result["status"], "passed");
+    assert!(attachment_names(&result).is_empty());
+    assert!(attachment_names(&result["steps"][0]).is_empty());
+    assert_eq!(EVALUATED.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_failing_test_attaches_to_failing_step_and_test() {
+    let outcome = catch_unwind(|| {
+        run_test("failing", "failure_attachment_tests::failing", || {
+            on_failure("Log tail", || {
+                step("Read log", || ());
+                String::from("error: connection reset")
+            });
+            step("Seed", || {
+                on_step_failure("Seed rows", || serde_json::json!({"rows": 3}));
+            });
+            step("Pay", || {
+                on_step_failure("Screenshot", || {
+                    AttachmentContent::from((vec![0x89, b'P', b'N', b'G'], ContentType::Png))
+                });
+                panic!("card declined");
+            });
+        });
+    });
+    assert!(outcome.is_err());
+
+    let result = read_result("failing");
+    assert_eq!(result["status"], "failed");
+    assert_eq!(attachment_names(&result), ["Log tail"]);
+    assert_eq!(result["steps"][2]["name"], "Read log");
+
+    let seed = &result["steps"][0];
+    assert!(attachment_names(seed).is_empty());
+    let pay = &result["steps"][1];
+    assert_eq!(attachment_names(pay), ["Screenshot"]);
+    assert_eq!(pay["attachments"][0]["type"], "image/png");
+
+    let log = read_attachment(&result["attachments"][0]);
+    assert_eq!(log, "error: connection reset");
+}
@@ -0,0 +1,68 @@
+//! Tests for label-expression filtering.
+//!
+//! The binary runs with `tag:smoke && severity >= critical && !status:flaky`
+//! and reports excluded tests as skipped.
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use allure_macros::{allure_flaky, allure_severity, allure_tag, allure_test};
+use allure_rs::prelude::configure;
+
+use common::{read_result, results_dir};
+
+const FILTER: &str = "tag:smoke && severity >= critical && !status:flaky";
+
+static SELECTED_RUNS: AtomicU32 = AtomicU32::new(0);
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .filter(FILTER)
+        .report_filtered(true)
+        .init();
+}
+
+#[allure_tag("smoke")]
+#[allure_severity("blocker")]
+#[allure_test]
+fn test_selected_by_filter() {
+    SELECTED_RUNS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[allure_tag("smoke")]
+#[allure_test]
+fn test_excluded_by_severity() {
+    panic!("tests excluded by the filter must not run");
+}
+
+#[allure_flaky]
+#[allure_tag("smoke")]
+#[allure_severity("critical")]
+#[allure_test]
+fn test_excluded_as_flaky() -> Result<(), String> {
+    Err("tests excluded by the filter must not run".to_string())
+}
+
+#[test]
+fn test_filter_skips_and_reports_excluded_tests() {
+    let before = SELECTED_RUNS.load(Ordering::SeqCst);
+    test_selected_by_filter();
+    assert!(SELECTED_RUNS.load(Ordering::SeqCst) > before);
+
+    test_excluded_by_severity();
+    assert_eq!(test_excluded_as_flaky(), Ok(()));
+
+    for name in ["test_excluded_by_severity", "test_excluded_as_flaky"] {
+        let result = read_result(name);
+        assert_eq!(result["status"], "skipped");
+        assert_eq!(
+            result["statusDetails"]["message"],
+            format!("excluded by filter: {}", FILTER)
+        );
+    }
+    assert_eq!(read_result("test_selected_by_filter")["status"], "passed");
+}
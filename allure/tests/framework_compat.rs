@@ -4,17 +4,31 @@
 //! - rstest for parameterized tests and fixtures
 //! - test-case for parameterized tests
 
+mod common;
+
 use allure_rs::prelude::*;
+use allure_rs::runtime::try_with_context;
+use allure_rs::writer::AllureWriter;
 use rstest::{fixture, rstest};
 use tempfile::TempDir;
 
-/// Helper to set up test results directory
-fn setup_results_dir() -> TempDir {
-    let dir = tempfile::tempdir().unwrap();
-    let _ = allure_rs::configure()
-        .results_dir(dir.path().to_str().unwrap())
+use common::results_dir;
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
         .clean_results(true)
         .init();
+}
+
+/// Helper to set up test results directory
+///
+/// The directory lives in the binary's results directory, which also holds
+/// anything written after the test drops it.
+fn setup_results_dir() -> TempDir {
+    let dir = tempfile::tempdir_in(results_dir()).unwrap();
+    try_with_context(|ctx| ctx.writer = AllureWriter::with_results_dir(dir.path())).unwrap();
     dir
 }
 
@@ -64,6 +78,38 @@ fn test_rstest_with_multiple_fixtures(user_name: String, user_email: String) {
     });
 }
 
+/// Fixture recorded as a setup fixture of the tests that use it
+#[allure_fixture("Load configuration")]
+#[fixture]
+fn app_config(user_name: String) -> Vec<(String, String)> {
+    step("Read defaults", || {});
+    vec![("owner".to_string(), user_name)]
+}
+
+/// Fixture whose drop is recorded as a teardown fixture
+#[allure_fixture]
+#[fixture]
+fn workspace() -> FixtureGuard<TempDir> {
+    FixtureGuard::new("Remove workspace", tempfile::tempdir().unwrap())
+}
+
+#[rstest]
+#[allure_epic("Framework Compatibility")]
+#[allure_feature("rstest Integration")]
+#[allure_story("Reported fixtures")]
+#[allure_test]
+fn test_rstest_with_allure_fixtures(
+    app_config: Vec<(String, String)>,
+    workspace: FixtureGuard<TempDir>,
+) {
+    let _dir = setup_results_dir();
+
+    step("Verify fixture values", || {
+        assert_eq!(app_config[0].1, "test_user");
+        assert!(workspace.path().exists());
+    });
+}
+
 #[rstest]
 #[case(1, 2, 3)]
 #[case(2, 3, 5)]
@@ -208,7 +254,7 @@ fn test_nested_steps_with_testcase(action: &str, resource: &str) {
     });
 
     step("Execute action", || {
-        step(&format!("Perform {} on {}", action, resource), || {
+        step(format!("Perform {} on {}", action, resource), || {
             // Simulate action
         });
     });
@@ -0,0 +1,91 @@
+//! Tests for labels, links and parameters configured for every test.
+
+mod common;
+
+use allure_macros::{allure_test, allure_tms};
+use allure_rs::prelude::configure;
+use allure_rs::runtime::{label, link, parameter};
+use serde_json::Value;
+
+use common::{read_result, results_dir};
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .label("layer", "integration")
+        .label("component", "billing")
+        .link(
+            "https://ci.example.com/builds/7",
+            Some("CI build".to_string()),
+        )
+        .parameter("build", 7)
+        .label_provider(|full_name| {
+            let module = full_name.rsplit_once("::").map_or("", |(module, _)| module);
+            vec![("subSuite".to_string(), module.to_string())]
+        })
+        .init();
+}
+
+#[allure_tms("TC-1")]
+#[allure_test]
+fn test_inherits_global_metadata() {}
+
+#[allure_test]
+fn test_overrides_global_metadata() {
+    label("layer", "e2e");
+    link(
+        "https://ci.example.com/builds/8",
+        Some("CI build".to_string()),
+    );
+    parameter("build", 8);
+}
+
+fn values(result: &Value, field: &str, name: &str, key: &str) -> Vec<String> {
+    result[field]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry["name"] == name)
+        .map(|entry| entry[key].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_global_metadata_is_applied_and_overridden() {
+    test_inherits_global_metadata();
+    test_overrides_global_metadata();
+
+    let inherited = read_result("test_inherits_global_metadata");
+    assert_eq!(
+        values(&inherited, "labels", "layer", "value"),
+        ["integration"]
+    );
+    assert_eq!(
+        values(&inherited, "labels", "component", "value"),
+        ["billing"]
+    );
+    assert_eq!(
+        values(&inherited, "labels", "subSuite", "value"),
+        ["global_metadata_tests"]
+    );
+    assert_eq!(
+        values(&inherited, "links", "CI build", "url"),
+        ["https://ci.example.com/builds/7"]
+    );
+    assert_eq!(inherited["links"].as_array().unwrap().len(), 2);
+    assert_eq!(values(&inherited, "parameters", "build", "value"), ["7"]);
+
+    let overridden = read_result("test_overrides_global_metadata");
+    assert_eq!(values(&overridden, "labels", "layer", "value"), ["e2e"]);
+    assert_eq!(
+        values(&overridden, "labels", "component", "value"),
+        ["billing"]
+    );
+    assert_eq!(
+        values(&overridden, "links", "CI build", "url"),
+        ["https://ci.example.com/builds/8"]
+    );
+    assert_eq!(values(&overridden, "parameters", "build", "value"), ["8"]);
+}
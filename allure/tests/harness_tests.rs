@@ -0,0 +1,73 @@
+//! Tests run by the Allure harness instead of libtest.
+//!
+//! This target sets `harness = false`, so `main` below discovers the
+//! `#[allure_test]` functions from the registry and runs them.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use allure_macros::{allure_feature, allure_test};
+use allure_rs::harness::Harness;
+use allure_rs::prelude::{configure, step};
+
+static SERVER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[allure_feature("Harness")]
+#[allure_test]
+fn test_runs_after_before_all_hook() {
+    step("Check server", || {
+        assert!(SERVER_RUNNING.load(Ordering::SeqCst));
+    });
+}
+
+#[allure_feature("Harness")]
+#[allure_test]
+fn test_result_test() -> Result<(), String> {
+    "42".parse::<u32>().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[should_panic(expected = "division by zero")]
+#[allure_feature("Harness")]
+#[allure_test]
+fn test_should_panic() {
+    panic!("division by zero");
+}
+
+#[ignore = "needs a staging environment"]
+#[allure_feature("Harness")]
+#[allure_test]
+fn test_ignored_is_reported_as_skipped() {
+    panic!("ignored tests don't run");
+}
+
+#[cfg(feature = "async")]
+#[allure_feature("Harness")]
+#[allure_test]
+async fn test_async_test() {
+    let value = async { 7 }.await;
+    assert_eq!(value, 7);
+}
+
+mod nested {
+    use super::*;
+
+    #[allure_test]
+    fn test_in_module() {
+        step("Work", || {});
+    }
+}
+
+fn main() {
+    let _ = configure()
+        .results_dir("../allure-results")
+        .clean_results(false)
+        .init();
+    Harness::new()
+        .before_all("Start test server", || {
+            SERVER_RUNNING.store(true, Ordering::SeqCst)
+        })
+        .after_all("Stop test server", || {
+            SERVER_RUNNING.store(false, Ordering::SeqCst)
+        })
+        .run();
+}
@@ -0,0 +1,44 @@
+//! Tests for initializing the runtime more than once.
+//!
+//! The first `configure().init()` wins and cleans the results directory;
+//! later calls neither clean it again nor change the configuration.
+
+mod common;
+
+use std::path::PathBuf;
+
+use allure_rs::prelude::configure;
+use allure_rs::runtime::get_config;
+
+use common::results_dir;
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .init();
+}
+
+#[test]
+fn test_later_init_calls_keep_first_configuration_and_results() {
+    let written = results_dir().join("earlier-result.json");
+    std::fs::write(&written, "{}").unwrap();
+
+    configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .init()
+        .unwrap();
+    assert!(written.exists(), "a repeated init must not clean again");
+
+    configure()
+        .results_dir("somewhere-else")
+        .clean_results(false)
+        .init()
+        .unwrap();
+    let config = get_config();
+    assert_eq!(PathBuf::from(config.results_dir), results_dir());
+    assert!(config.clean_results);
+    assert!(!PathBuf::from("somewhere-else").exists());
+}
@@ -4,7 +4,7 @@
 
 use allure_core::enums::ContentType;
 use allure_core::model::{FixtureResult, TestResultContainer};
-use allure_core::runtime::{self, set_context, take_context, with_async_context, TestContext};
+use allure_core::runtime::{self, set_context, take_context, TestContext};
 use allure_core::writer::AllureWriter;
 use allure_rs::prelude::*;
 use allure_rs::{bdd, Category};
@@ -12,7 +12,6 @@ use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
-use tokio::runtime::Runtime;
 
 /// Test helper that provides a temporary directory and writer.
 struct TestHelper {
@@ -559,6 +558,32 @@ fn test_log_step() {
     assert_eq!(steps[0]["status"], "passed");
 }
 
+#[test]
+fn test_soft_assertions_report_every_failure() {
+    let helper = TestHelper::new();
+    helper.run_failing_test("my_test", "module::my_test", || {
+        soft(|s| {
+            soft_assert_eq!(s, 404, 200, "status code");
+            soft_assert!(s, "".contains("user"), "body mentions user");
+            soft_assert_eq!(s, 1 + 1, 2);
+        });
+    });
+
+    let results = helper.read_result_files();
+    assert_eq!(results[0]["status"], "failed");
+    let message = results[0]["statusDetails"]["message"].as_str().unwrap();
+    assert!(message.contains("2 soft assertion(s) failed"));
+    assert!(message.contains("status code: expected 200, got 404"));
+    assert!(message.contains("body mentions user"));
+
+    let steps = results[0]["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0]["name"], "status code");
+    assert_eq!(steps[0]["status"], "failed");
+    assert_eq!(steps[0]["parameters"][0]["value"], "200");
+    assert_eq!(steps[0]["parameters"][1]["value"], "404");
+}
+
 // =============================================================================
 // 6. Attachments
 // =============================================================================
@@ -714,7 +739,21 @@ fn test_skip_records_skipped_status_with_reason() {
     ctx.writer.init(true).unwrap();
     set_context(ctx);
 
-    runtime::skip("not needed");
+    let mut reached_end = false;
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        step("Prepare", || {
+            runtime::skip("not needed");
+        });
+        reached_end = true;
+    }));
+
+    // The body stops at skip() and the payload is recognized as a skip
+    assert!(!reached_end);
+    let reason = runtime::skip_reason(outcome.unwrap_err().as_ref());
+    assert_eq!(reason.as_deref(), Some("not needed"));
+
+    let mut ctx = take_context().unwrap();
+    ctx.finish(Status::Skipped, reason, None);
 
     let results = helper.read_result_files();
     assert_eq!(results.len(), 1);
@@ -723,11 +762,40 @@ fn test_skip_records_skipped_status_with_reason() {
         results[0]["statusDetails"]["message"].as_str().unwrap(),
         "not needed"
     );
+    assert_eq!(results[0]["steps"][0]["name"], "Prepare");
+    assert_eq!(results[0]["steps"][0]["status"], "skipped");
+}
+
+#[test]
+fn test_skipped_subtest_does_not_fail_parent() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("parent", "module::parent");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+
+    subtest("unsupported", Vec::<(&str, &str)>::new(), || {
+        assume!(cfg!(target_os = "none"), "no bare-metal target");
+    });
+
+    let mut ctx = take_context().unwrap();
+    assert_eq!(ctx.deferred_failure(), None);
+    ctx.finish(Status::Passed, None, None);
+
+    let results = helper.read_result_files();
+    let child = results
+        .iter()
+        .find(|r| r["name"] == "unsupported")
+        .expect("subtest result should be written");
+    assert_eq!(child["status"], "skipped");
+    assert_eq!(child["statusDetails"]["message"], "no bare-metal target");
 }
 
 #[cfg(feature = "tokio")]
 #[test]
 fn test_tokio_spawn_inherits_context() {
+    use allure_core::runtime::with_async_context;
+    use tokio::runtime::Runtime;
+
     let helper = TestHelper::new();
     let mut ctx = TestContext::new("tokio_spawn", "module::tokio_spawn");
     ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
@@ -858,6 +926,131 @@ fn test_flaky_flag_in_output() {
     assert_eq!(status_details["flaky"], true);
 }
 
+#[test]
+fn test_retry_writes_each_attempt_with_shared_history_id() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("retried", "module::retried");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+    runtime::parameter("region", "eu");
+
+    let mut retry = runtime::Retry::new(3, 0);
+    let mut calls = 0;
+    loop {
+        calls += 1;
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            step("Call service", || {
+                assert!(calls >= 3, "attempt {} failed", calls)
+            });
+        }));
+        let again = match &outcome {
+            Ok(_) => retry.complete(),
+            Err(panic) => retry.retry_after_panic(panic.as_ref()),
+        };
+        if !again {
+            break;
+        }
+    }
+    assert_eq!(calls, 3);
+    take_context().unwrap().finish(Status::Passed, None, None);
+
+    // Attempts can start within the same millisecond, so order by message
+    let mut results = helper.read_result_files();
+    results.sort_by_key(|r| {
+        (
+            r["status"] == "passed",
+            r["statusDetails"]["message"].as_str().map(str::to_string),
+        )
+    });
+    assert_eq!(results.len(), 3);
+    let statuses: Vec<_> = results.iter().map(|r| r["status"].clone()).collect();
+    assert_eq!(statuses, ["failed", "failed", "passed"]);
+    assert!(results
+        .iter()
+        .all(|r| r["historyId"] == results[0]["historyId"] && r["name"] == "retried"));
+    assert_eq!(
+        results[0]["statusDetails"]["message"].as_str().unwrap(),
+        "attempt 1 failed"
+    );
+    assert_eq!(
+        results[1]["statusDetails"]["message"].as_str().unwrap(),
+        "attempt 2 failed"
+    );
+    assert_eq!(results[2]["statusDetails"]["flaky"], true);
+    assert_eq!(results[2]["parameters"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_retry_runs_attempt_teardowns_once_per_attempt() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("retried_fixtures", "module::retried_fixtures");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+    runtime::label("layer", "api");
+    runtime::before("Start server", || ());
+
+    let closed = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut retry = runtime::Retry::new(2, 0);
+    let mut calls = 0;
+    loop {
+        calls += 1;
+        let closed = closed.clone();
+        runtime::teardown("Close client", move || {
+            closed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+        if !retry.retry_after_error(format!("attempt {}", calls)) {
+            break;
+        }
+    }
+    assert_eq!(calls, 3);
+    // The last attempt is still running
+    assert_eq!(closed.load(std::sync::atomic::Ordering::SeqCst), 2);
+    take_context()
+        .unwrap()
+        .finish(Status::Failed, Some("attempt 3".into()), None);
+    assert_eq!(closed.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+    let results = helper.read_result_files();
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(|r| r["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|label| label["name"] == "layer" && label["value"] == "api")));
+
+    let containers = helper.read_container_files();
+    assert_eq!(containers.len(), 3);
+    for container in &containers {
+        assert_eq!(container["befores"][0]["name"], "Start server");
+        let afters = container["afters"].as_array().unwrap();
+        assert_eq!(afters.len(), 1);
+        assert_eq!(afters[0]["name"], "Close client");
+    }
+}
+
+#[test]
+fn test_retry_gives_up_after_last_attempt() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("always_fails", "module::always_fails");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+
+    let mut retry = runtime::Retry::new(1, 0);
+    assert!(retry.retry_after_error("first"));
+    assert!(!retry.retry_after_error("second"));
+    assert_eq!(retry.attempt(), 2);
+    take_context()
+        .unwrap()
+        .finish(Status::Failed, Some("second".into()), None);
+
+    let results = helper.read_result_files();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r["status"] == "failed"));
+    assert!(results
+        .iter()
+        .all(|r| r["statusDetails"]["flaky"].is_null()));
+}
+
 #[test]
 fn test_known_issue_flag_and_link() {
     let helper = TestHelper::new();
@@ -1032,6 +1225,191 @@ fn test_after_fixture_in_container() {
     assert_eq!(afters[0]["status"], "passed");
 }
 
+#[test]
+fn test_runtime_fixtures_written_into_container() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("with_fixtures", "module::with_fixtures");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+
+    let order = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let port = runtime::before("Start database", || {
+        step("Pull image", || {});
+        runtime::parameter("image", "postgres:16");
+        attachment::text("docker log", "ready");
+        5432
+    });
+    assert_eq!(port, 5432);
+    for name in ["Drop schema", "Stop database"] {
+        let order = order.clone();
+        runtime::teardown(name, move || {
+            step(format!("{} now", name), || {});
+            order.lock().unwrap().push(name);
+        });
+    }
+    step("Query", || {});
+
+    take_context().unwrap().finish(Status::Passed, None, None);
+
+    // Teardowns run newest first
+    assert_eq!(*order.lock().unwrap(), ["Stop database", "Drop schema"]);
+
+    let results = helper.read_result_files();
+    assert_eq!(results[0]["status"], "passed");
+    let steps = results[0]["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 1, "fixtures are not test steps");
+
+    let containers = helper.read_container_files();
+    assert_eq!(containers.len(), 1);
+    let container = &containers[0];
+    assert_eq!(container["children"][0], results[0]["uuid"]);
+
+    let before = &container["befores"][0];
+    assert_eq!(before["name"], "Start database");
+    assert_eq!(before["status"], "passed");
+    assert_eq!(before["steps"][0]["name"], "Pull image");
+    assert_eq!(before["parameters"][0]["value"], "postgres:16");
+    assert_eq!(before["attachments"][0]["name"], "docker log");
+
+    let afters = container["afters"].as_array().unwrap();
+    assert_eq!(afters.len(), 2);
+    assert_eq!(afters[0]["name"], "Stop database");
+    assert_eq!(afters[0]["steps"][0]["name"], "Stop database now");
+    assert_eq!(afters[1]["name"], "Drop schema");
+}
+
+#[allure_fixture("Open account for {owner}")]
+fn account(owner: &str) -> String {
+    step("Insert row", || {});
+    parameter("currency", "EUR");
+    owner.to_string()
+}
+
+#[allure_fixture]
+fn scratch_dir() -> FixtureGuard<Vec<String>> {
+    FixtureGuard::new("Remove scratch dir", vec!["scratch".to_string()])
+}
+
+#[test]
+fn test_fixture_functions_written_into_container() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("fixture_fns", "module::fixture_fns");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+
+    let owner = account("alice");
+    {
+        let dir = scratch_dir();
+        step("Use scratch dir", || assert_eq!(dir.len(), 1));
+    }
+    assert_eq!(owner, "alice");
+
+    take_context().unwrap().finish(Status::Passed, None, None);
+
+    let results = helper.read_result_files();
+    assert_eq!(results[0]["status"], "passed");
+    assert_eq!(results[0]["steps"].as_array().unwrap().len(), 1);
+
+    let containers = helper.read_container_files();
+    let befores = containers[0]["befores"].as_array().unwrap();
+    assert_eq!(befores.len(), 2);
+    assert_eq!(befores[0]["name"], "Open account for \"alice\"");
+    assert_eq!(befores[0]["steps"][0]["name"], "Insert row");
+    assert_eq!(befores[0]["parameters"][0]["name"], "currency");
+    assert_eq!(befores[1]["name"], "scratch_dir");
+
+    let afters = containers[0]["afters"].as_array().unwrap();
+    assert_eq!(afters.len(), 1);
+    assert_eq!(afters[0]["name"], "Remove scratch dir");
+    assert_eq!(afters[0]["status"], "passed");
+}
+
+#[test]
+fn test_fixture_guard_into_inner_records_nothing() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("guard_inner", "module::guard_inner");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+
+    let files = scratch_dir().into_inner();
+    assert_eq!(files, ["scratch"]);
+
+    take_context().unwrap().finish(Status::Passed, None, None);
+
+    let containers = helper.read_container_files();
+    assert_eq!(containers[0]["befores"].as_array().unwrap().len(), 1);
+    assert!(containers[0]["afters"]
+        .as_array()
+        .map_or(true, |afters| afters.is_empty()));
+}
+
+#[test]
+fn test_failing_before_marks_test_broken() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("broken_setup", "module::broken_setup");
+    ctx.writer = AllureWriter::with_results_dir(helper.results_dir());
+    set_context(ctx);
+
+    let outcome = std::panic::catch_unwind(|| {
+        runtime::before("Connect", || panic!("connection refused"));
+    });
+    let message = outcome
+        .unwrap_err()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string());
+    take_context()
+        .unwrap()
+        .finish(Status::Failed, message, None);
+
+    let results = helper.read_result_files();
+    assert_eq!(results[0]["status"], "broken");
+    assert_eq!(results[0]["statusDetails"]["message"], "connection refused");
+
+    let containers = helper.read_container_files();
+    let before = &containers[0]["befores"][0];
+    assert_eq!(before["status"], "failed");
+    assert_eq!(before["statusDetails"]["message"], "connection refused");
+}
+
+#[test]
+fn test_subtests_written_as_results_grouped_by_container() {
+    let helper = TestHelper::new();
+    let mut ctx = TestContext::new("table_test", "module::table_test");
+    ctx.writer = helper.writer.clone();
+    set_context(ctx);
+
+    for (a, b, expected) in [(1, 1, 2), (2, 2, 5), (3, 3, 6)] {
+        subtest(format!("{a} + {b}"), [("a", a), ("b", b)], || {
+            assert_eq!(a + b, expected);
+        });
+    }
+
+    let mut ctx = take_context().unwrap();
+    ctx.finish(Status::Passed, None, None);
+    assert_eq!(
+        ctx.deferred_failure().as_deref(),
+        Some("1 of 3 subtest(s) failed: 2 + 2")
+    );
+
+    let results = helper.read_result_files();
+    assert_eq!(results.len(), 4);
+    let parent = results.iter().find(|r| r["name"] == "table_test").unwrap();
+    assert_eq!(parent["status"], "failed");
+    let failed: Vec<_> = results.iter().filter(|r| r["status"] == "failed").collect();
+    assert_eq!(failed.len(), 2);
+
+    let history_ids: std::collections::HashSet<_> = results
+        .iter()
+        .map(|r| r["historyId"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(history_ids.len(), 4);
+
+    let containers = helper.read_container_files();
+    assert_eq!(containers.len(), 1, "subtests share the parent's container");
+    assert_eq!(containers[0]["children"].as_array().unwrap().len(), 4);
+    assert_eq!(containers[0]["children"][0], parent["uuid"]);
+}
+
 // =============================================================================
 // 12. Proc Macro Validation Tests (compile-time)
 // =============================================================================
@@ -1181,7 +1559,7 @@ fn test_many_steps() {
     let helper = TestHelper::new();
     helper.run_test("my_test", "module::my_test", || {
         for i in 0..100 {
-            step(&format!("Step {}", i), || {});
+            step(format!("Step {}", i), || {});
         }
     });
 
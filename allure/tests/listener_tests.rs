@@ -0,0 +1,155 @@
+//! Tests for lifecycle listeners registered through `configure()`.
+//!
+//! The binary runs with a filter excluding tests tagged `excluded`, without
+//! reporting them.
+
+mod common;
+
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+use allure_macros::{allure_tag, allure_test};
+use allure_rs::prelude::{attachment, configure, run_test, step};
+use allure_rs::runtime::{current_step_path, current_test, end_run};
+use allure_rs::{AllureListener, Attachment, Label, StepResult, TestResult};
+
+use common::{read_result, results_dir};
+
+/// Events with the thread they happened on, as libtest also runs
+/// `test_observed` on its own.
+static EVENTS: Mutex<Vec<(ThreadId, String)>> = Mutex::new(Vec::new());
+
+fn record(event: String) {
+    EVENTS.lock().unwrap().push((thread::current().id(), event));
+}
+
+struct Recorder;
+
+impl AllureListener for Recorder {
+    fn on_test_start(&self, result: &mut TestResult) {
+        record(format!("test start {}", result.name));
+    }
+
+    fn on_step_start(&self, step: &StepResult) {
+        // Listeners may call back into the runtime
+        let test = current_test().map(|test| test.name).unwrap_or_default();
+        record(format!("step start {} in {}", step.name, test));
+    }
+
+    fn on_step_stop(&self, step: &StepResult) {
+        record(format!("step stop {} {}", step.name, step.status));
+    }
+
+    fn on_attachment(&self, attachment: &Attachment) {
+        let path = current_step_path().join(" > ");
+        record(format!("attachment {} at {}", attachment.name, path));
+    }
+
+    fn before_test_write(&self, result: &mut TestResult) {
+        result.name = format!("[billing] {}", result.name);
+        result.labels.push(Label::new("team", "payments"));
+        record(format!("before write {}", result.name));
+    }
+
+    fn on_test_stop(&self, result: &TestResult) {
+        record(format!("test stop {}", result.name));
+    }
+
+    fn on_run_end(&self) {
+        record("run end".to_string());
+    }
+}
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .listener(Recorder)
+        .filter("!tag:excluded")
+        .init();
+}
+
+#[allure_test]
+fn test_observed() {
+    step("Charge card", || {
+        attachment::text("Receipt", "paid");
+    });
+}
+
+#[allure_tag("excluded")]
+#[allure_test]
+fn test_filtered_out() {
+    step("Never run", || {});
+}
+
+fn events_on_this_thread() -> Vec<String> {
+    EVENTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(thread, _)| *thread == thread::current().id())
+        .map(|(_, event)| event.clone())
+        .collect()
+}
+
+#[test]
+fn test_listener_can_call_the_runtime() {
+    run_test("reentrant", "listener_tests::reentrant", || {
+        step("Look up", || {
+            step("Read row", || attachment::text("Row", "1"));
+        });
+    });
+
+    let events = events_on_this_thread();
+    assert_eq!(
+        events[..5],
+        [
+            "test start reentrant",
+            "step start Look up in reentrant",
+            "step start Read row in reentrant",
+            "attachment Row at Look up > Read row",
+            "step stop Read row passed",
+        ]
+    );
+}
+
+#[test]
+fn test_listener_does_not_see_filtered_tests() {
+    test_filtered_out();
+    test_observed();
+
+    let events = events_on_this_thread();
+    assert!(!events
+        .iter()
+        .any(|event| event.contains("test_filtered_out")));
+    assert_eq!(events[0], "test start test_observed");
+}
+
+#[test]
+fn test_listener_sees_lifecycle_and_adjusts_results() {
+    test_observed();
+    end_run();
+    end_run();
+
+    let events = events_on_this_thread();
+    assert_eq!(
+        events,
+        [
+            "test start test_observed",
+            "step start Charge card in test_observed",
+            "attachment Receipt at Charge card",
+            "step stop Charge card passed",
+            "before write [billing] test_observed",
+            "test stop [billing] test_observed",
+            "run end",
+        ]
+    );
+
+    let result = read_result("[billing] test_observed");
+    assert!(result["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|label| label["name"] == "team" && label["value"] == "payments"));
+}
@@ -5,6 +5,8 @@
 //! - #[ignore]
 //! - Result<T, E> return types
 
+#![allow(clippy::assertions_on_constants)]
+
 use allure_macros::allure_test;
 use allure_rs::prelude::configure;
 
@@ -0,0 +1,79 @@
+//! Tests for redacting secrets before results are written.
+
+mod common;
+
+use allure_macros::allure_test;
+use allure_rs::prelude::{attachment, before, configure, parameter, parameter_masked, skip, step};
+use allure_rs::redact::REDACTED;
+use serde_json::Value;
+
+use common::{read_attachment, read_containers, read_result, results_dir};
+
+const TOKEN: &str = "tok-8f2c1e";
+
+#[ctor::ctor]
+fn init() {
+    std::env::set_var("BILLING_API_TOKEN", TOKEN);
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .redact_secret("hunter2")
+        .redact_pattern(r"Bearer [A-Za-z0-9._-]+")
+        .redact_env("BILLING_API_TOKEN")
+        .init();
+}
+
+#[allure_test]
+fn test_with_secrets() {
+    before(format!("Log in with {}", TOKEN), || {});
+    parameter("user", "admin:hunter2");
+    parameter_masked("password", "s3cret");
+    step(format!("Call API with {}", TOKEN), || {
+        attachment::text("Request", "Authorization: Bearer abc.def");
+        attachment::json("Body", &serde_json::json!({"token": TOKEN, "amount": 10}));
+    });
+}
+
+#[allure_test]
+fn test_skipped_with_secret() {
+    skip(format!("sandbox rejected {}", TOKEN));
+}
+
+fn read_container_of(uuid: &Value) -> Value {
+    read_containers()
+        .into_iter()
+        .find(|container| container["children"][0] == *uuid)
+        .expect("container should be written")
+}
+
+#[test]
+fn test_secrets_never_reach_disk() {
+    test_with_secrets();
+    test_skipped_with_secret();
+
+    let result = read_result("test_with_secrets");
+    let text = result.to_string();
+    for secret in ["hunter2", "s3cret", TOKEN] {
+        assert!(!text.contains(secret), "{} leaked into {}", secret, text);
+    }
+    assert_eq!(result["parameters"][0]["value"], "admin:[REDACTED]");
+    assert_eq!(result["parameters"][1]["value"], REDACTED);
+    assert_eq!(result["steps"][0]["name"], "Call API with [REDACTED]");
+
+    let attachments = result["steps"][0]["attachments"].as_array().unwrap();
+    assert_eq!(
+        read_attachment(&attachments[0]),
+        "Authorization: [REDACTED]"
+    );
+    let body: Value = serde_json::from_str(&read_attachment(&attachments[1])).unwrap();
+    assert_eq!(body, serde_json::json!({"token": REDACTED, "amount": 10}));
+
+    let container = read_container_of(&result["uuid"]);
+    assert_eq!(container["befores"][0]["name"], "Log in with [REDACTED]");
+
+    let skipped = read_result("test_skipped_with_secret");
+    assert_eq!(
+        skipped["statusDetails"]["message"],
+        "sandbox rejected [REDACTED]"
+    );
+}
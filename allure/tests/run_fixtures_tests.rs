@@ -0,0 +1,47 @@
+//! Tests for binary-wide before-all/after-all fixtures.
+//!
+//! The server is started once for the whole binary with `#[ctor::ctor]`
+//! and stopped with `#[ctor::dtor]`. Both show up in a run-level container
+//! whose children are every test result written by this binary.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use allure_macros::{allure_after_all, allure_before_all, allure_test};
+use allure_rs::prelude::{configure, step};
+
+static SERVER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Initialize Allure once before tests run
+// Note: Tests run from the crate directory (allure/), so use parent path
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir("../allure-results")
+        .clean_results(false)
+        .init();
+}
+
+#[ctor::ctor]
+#[allure_before_all("Start test server")]
+fn start_server() {
+    step("Bind port", || {});
+    SERVER_RUNNING.store(true, Ordering::SeqCst);
+}
+
+#[ctor::dtor]
+#[allure_after_all("Stop test server")]
+fn stop_server() {
+    SERVER_RUNNING.store(false, Ordering::SeqCst);
+}
+
+#[allure_test]
+fn test_server_started_before_tests() {
+    step("Check server", || {
+        assert!(SERVER_RUNNING.load(Ordering::SeqCst));
+    });
+}
+
+#[allure_test]
+fn test_second_child_of_run_container() {
+    assert!(SERVER_RUNNING.load(Ordering::SeqCst));
+}
@@ -0,0 +1,65 @@
+//! Tests for the step path added to failure messages.
+
+mod common;
+
+use std::panic::catch_unwind;
+
+use allure_rs::prelude::{configure, run_test, soft, step};
+
+use common::{read_result, results_dir};
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .step_path_in_messages(true)
+        .print_step_path(true)
+        .init();
+}
+
+#[test]
+fn test_failure_messages_start_with_step_path() {
+    let outcome = catch_unwind(|| {
+        run_test("checkout", "step_path_tests::checkout", || {
+            step("Checkout", || {
+                step("Pay", || {
+                    step("Submit card", || panic!("card declined"));
+                });
+            });
+        });
+    });
+    assert!(outcome.is_err());
+
+    let result = read_result("checkout");
+    assert_eq!(result["status"], "failed");
+    assert_eq!(
+        result["statusDetails"]["message"],
+        "Checkout > Pay > Submit card: card declined"
+    );
+    let checkout = &result["steps"][0];
+    assert_eq!(
+        checkout["statusDetails"]["message"],
+        "Checkout > Pay > Submit card: card declined"
+    );
+    assert_eq!(
+        checkout["steps"][0]["steps"][0]["statusDetails"]["message"],
+        "Checkout > Pay > Submit card: card declined"
+    );
+}
+
+#[test]
+fn test_passed_steps_clear_the_step_path() {
+    let outcome = catch_unwind(|| {
+        run_test("after_soft", "step_path_tests::after_soft", || {
+            step("Verify", || {
+                let _ = catch_unwind(|| soft(|s| s.fail("total", "off by one")));
+            });
+            panic!("top-level failure");
+        });
+    });
+    assert!(outcome.is_err());
+
+    let result = read_result("after_soft");
+    assert_eq!(result["statusDetails"]["message"], "top-level failure");
+}
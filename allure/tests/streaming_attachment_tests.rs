@@ -0,0 +1,86 @@
+//! Tests for attachments streamed through `io::Write` and `io::Read`.
+
+mod common;
+
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
+
+use allure_rs::enums::ContentType;
+use allure_rs::prelude::{attachment, configure, run_test, step};
+
+use common::{read_attachment, read_result, results_dir};
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .init();
+}
+
+/// A `Write` target that can be read back after the writer is consumed.
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_streamed_attachments_are_added_when_closed() {
+    let echoed = SharedBuf::default();
+    let out = echoed.clone();
+    run_test(
+        "streaming",
+        "streaming_attachment_tests::streaming",
+        move || {
+            step("Soak", || {
+                let mut log = attachment::writer("Soak log", ContentType::Text);
+                for i in 0..1000 {
+                    writeln!(log, "iteration {}", i).unwrap();
+                }
+                // Added when dropped
+            });
+            let copied =
+                attachment::from_reader("Report", Cursor::new("<testsuite/>"), ContentType::Xml)
+                    .unwrap();
+            assert_eq!(copied, 12);
+            attachment::tee(
+                "Build output",
+                Cursor::new("build ok\n"),
+                ContentType::Text,
+                out,
+            )
+            .unwrap();
+        },
+    );
+
+    let result = read_result("streaming");
+    let soak = &result["steps"][0]["attachments"][0];
+    assert_eq!(soak["name"], "Soak log");
+    let log = read_attachment(soak);
+    assert_eq!(log.lines().count(), 1000);
+    assert!(log.ends_with("iteration 999\n"));
+
+    let attachments = result["attachments"].as_array().unwrap();
+    assert_eq!(attachments[0]["name"], "Report");
+    assert_eq!(attachments[0]["type"], "application/xml");
+    assert_eq!(read_attachment(&attachments[0]), "<testsuite/>");
+    assert_eq!(attachments[1]["name"], "Build output");
+    assert_eq!(read_attachment(&attachments[1]), "build ok\n");
+    assert_eq!(*echoed.0.lock().unwrap(), b"build ok\n");
+}
+
+#[test]
+fn test_streamed_attachment_outside_a_test_is_discarded() {
+    let mut log = attachment::writer("Orphan", ContentType::Text);
+    log.write_all(b"ignored").unwrap();
+    log.finish().unwrap();
+}
@@ -0,0 +1,64 @@
+//! Tests for strict mode and the fallible `try_*` runtime functions.
+
+mod common;
+
+use std::panic::catch_unwind;
+
+use allure_macros::allure_test;
+use allure_rs::prelude::{
+    attach_file, configure, label, step, try_attach_file, try_attach_text, try_label, try_step,
+    AllureError,
+};
+
+use common::{read_result, results_dir};
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(true)
+        .strict(true)
+        .init();
+}
+
+#[allure_test]
+fn test_fallible_calls() {
+    try_label("layer", "api").unwrap();
+    try_step("Checked step", || try_attach_text("Log", "ok").unwrap()).unwrap();
+    let missing = results_dir().join("missing.txt");
+    assert!(matches!(
+        try_attach_file("Missing", &missing, None),
+        Err(AllureError::Io(_))
+    ));
+    assert!(catch_unwind(|| attach_file("Missing", &missing, None)).is_err());
+}
+
+#[test]
+fn test_calls_outside_a_test_panic_or_fail() {
+    assert!(catch_unwind(|| label("layer", "api")).is_err());
+    assert!(catch_unwind(|| step("Orphan", || ())).is_err());
+
+    assert!(matches!(
+        try_label("layer", "api"),
+        Err(AllureError::NoActiveContext)
+    ));
+    let mut ran = false;
+    assert!(try_step("Orphan", || ran = true).is_err());
+    assert!(!ran);
+}
+
+#[test]
+fn test_try_calls_record_inside_a_test() {
+    test_fallible_calls();
+
+    let result = read_result("test_fallible_calls");
+    assert_eq!(result["status"], "passed");
+    assert!(result["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|label| label["name"] == "layer" && label["value"] == "api"));
+    let step = &result["steps"][0];
+    assert_eq!(step["name"], "Checked step");
+    assert_eq!(step["attachments"][0]["name"], "Log");
+}
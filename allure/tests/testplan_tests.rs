@@ -0,0 +1,83 @@
+//! Tests for Allure test plans.
+//!
+//! `ALLURE_TESTPLAN_PATH` is set before any test runs, selecting one test
+//! by `AS_ID` and one by full name. The other tests must neither run nor
+//! write a result, while still passing under libtest.
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use allure_macros::{allure_id, allure_test};
+use allure_rs::prelude::configure;
+use allure_rs::testplan;
+
+use common::{find_result, results_dir};
+
+static SELECTED_RUNS: AtomicU32 = AtomicU32::new(0);
+
+#[ctor::ctor]
+fn init() {
+    let plan = results_dir().join("testplan.json");
+    std::fs::write(
+        &plan,
+        r#"{
+            "version": "1.0",
+            "tests": [
+                { "id": "501" },
+                { "selector": "testplan_tests::test_selected_by_selector" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    std::env::set_var(testplan::TESTPLAN_PATH_ENV, &plan);
+
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(false)
+        .init();
+}
+
+#[allure_id("501")]
+#[allure_test]
+fn test_selected_by_id() {
+    SELECTED_RUNS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[allure_test]
+fn test_selected_by_selector() {
+    SELECTED_RUNS.fetch_add(1, Ordering::SeqCst);
+}
+
+#[allure_id("502")]
+#[allure_test]
+fn test_not_in_plan() {
+    panic!("tests outside the plan must not run");
+}
+
+#[allure_test]
+fn test_not_in_plan_result() -> Result<(), String> {
+    Err("tests outside the plan must not run".to_string())
+}
+
+#[allure_test]
+#[should_panic(expected = "overflow")]
+fn test_not_in_plan_should_panic() {}
+
+#[test]
+fn test_plan_selects_tests_by_id_and_selector() {
+    let plan = testplan::active().expect("test plan should be loaded");
+    assert_eq!(plan.tests.len(), 2);
+
+    let before = SELECTED_RUNS.load(Ordering::SeqCst);
+    test_selected_by_id();
+    test_selected_by_selector();
+    assert!(SELECTED_RUNS.load(Ordering::SeqCst) >= before + 2);
+
+    test_not_in_plan();
+    assert_eq!(test_not_in_plan_result(), Ok(()));
+
+    assert!(find_result("test_selected_by_id").is_some());
+    assert!(find_result("test_not_in_plan").is_none());
+    assert!(find_result("test_not_in_plan_result").is_none());
+}
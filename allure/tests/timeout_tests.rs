@@ -0,0 +1,79 @@
+//! Tests for per-test and default timeouts.
+//!
+//! This binary configures a short default timeout, so every `#[allure_test]`
+//! here runs under a watchdog unless it sets its own limit.
+
+mod common;
+
+use std::time::Duration;
+
+use allure_macros::{allure_test, allure_timeout};
+use allure_rs::prelude::{configure, step};
+
+use common::results_dir;
+
+#[ctor::ctor]
+fn init() {
+    let _ = configure()
+        .results_dir(results_dir().to_string_lossy())
+        .clean_results(false)
+        .default_timeout(Duration::from_millis(300))
+        .init();
+}
+
+#[allure_test]
+fn test_quick_test_passes_under_default_timeout() {
+    step("Fast work", || {
+        assert_eq!(2 + 2, 4);
+    });
+}
+
+/// A hung body is reported as broken and fails the cargo test
+#[should_panic(expected = "timed out after 300ms")]
+#[allure_test]
+fn test_default_timeout_fails_hung_test() {
+    step("Wait for a lock that is never released", || {
+        std::thread::sleep(Duration::from_secs(3));
+    });
+}
+
+/// Outputs that can't leave the thread skip the worker and run inline
+#[allure_test]
+fn test_non_send_output_runs_inline() -> Result<(), Box<dyn std::error::Error>> {
+    let value: i32 = "42".parse()?;
+    assert_eq!(value, 42);
+    Ok(())
+}
+
+#[should_panic(expected = "timed out after 50ms")]
+#[allure_timeout(50)]
+#[allure_test]
+fn test_explicit_timeout_fails_hung_test() {
+    std::thread::sleep(Duration::from_secs(3));
+}
+
+/// A per-test limit replaces the shorter default
+#[allure_timeout(3000)]
+#[allure_test]
+fn test_explicit_timeout_overrides_default() {
+    step("Slow but bounded work", || {
+        std::thread::sleep(Duration::from_millis(500));
+    });
+}
+
+#[tokio::test]
+#[allure_timeout(2000)]
+#[allure_test]
+async fn test_timeout_with_tokio_test() {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+}
+
+#[cfg(feature = "async")]
+#[should_panic(expected = "timed out after 50ms")]
+#[allure_timeout(50)]
+#[allure_test]
+#[tokio::test]
+async fn test_async_timeout_races_the_future() {
+    step("Start slow request", || {});
+    tokio::time::sleep(Duration::from_secs(3)).await;
+}